@@ -0,0 +1,43 @@
+//! DSP benchmarks that run without a model: `cargo bench --bench dsp`
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use zipenhancer::audio::AudioConverter;
+use zipenhancer::bench::synthetic_input;
+use zipenhancer::config::NormalizationConfig;
+use zipenhancer::processing::loudness::{normalize, NormalizationPolicy};
+use zipenhancer::processing::{AudioPostprocessor, AudioPreprocessor, PostprocessingConfig, PreprocessingConfig};
+
+const SECONDS: f64 = 10.0;
+
+fn resampling(c: &mut Criterion) {
+    let audio = synthetic_input(SECONDS, 48000);
+    c.bench_function("resample_48k_to_16k", |b| b.iter(|| AudioConverter::convert_sample_rate(black_box(&audio), 16000).unwrap()));
+}
+
+fn segmentation(c: &mut Criterion) {
+    let audio = synthetic_input(SECONDS, 16000);
+    let preprocessor = AudioPreprocessor::new(PreprocessingConfig::default());
+    c.bench_function("segment", |b| b.iter(|| preprocessor.preprocess_and_segment(black_box(&audio)).unwrap()));
+}
+
+fn overlap_add(c: &mut Criterion) {
+    let audio = synthetic_input(SECONDS, 16000);
+    let segments = AudioPreprocessor::new(PreprocessingConfig::default()).preprocess_and_segment(&audio).unwrap();
+    let mut postprocessor = AudioPostprocessor::new(PostprocessingConfig::default());
+    c.bench_function("overlap_add", |b| b.iter(|| postprocessor.reconstruct_from_segments(black_box(&segments)).unwrap()));
+}
+
+fn normalization(c: &mut Criterion) {
+    let data = synthetic_input(SECONDS, 16000).data.to_mono();
+    let mut group = c.benchmark_group("normalize");
+    for policy in [NormalizationPolicy::Peak, NormalizationPolicy::Rms, NormalizationPolicy::Loudness] {
+        let config = NormalizationConfig { policy, ..Default::default() };
+        group.bench_function(format!("{:?}", policy).to_lowercase(), |b| {
+            b.iter_batched(|| data.clone(), |mut d| normalize(&mut d, 16000, &config), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, resampling, segmentation, overlap_add, normalization);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+//! Enhance one file with the library API.
+//!
+//! ```text
+//! cargo run --example enhance_file -- noisy.wav clean.wav [model.onnx]
+//! ```
+//!
+//! Without a model path the built-in spectral gate is used.
+
+use std::path::PathBuf;
+use zipenhancer::prelude::*;
+
+fn main() -> Result<()> {
+    let args: Vec<PathBuf> = std::env::args_os().skip(1).map(PathBuf::from).collect();
+    let [input, output, model @ ..] = args.as_slice() else {
+        eprintln!("usage: enhance_file <input.wav> <output.wav> [model.onnx]");
+        std::process::exit(2);
+    };
+
+    let builder = match model.first() {
+        Some(model) => Enhancer::builder().model(model),
+        None => Enhancer::builder().spectral(),
+    };
+    let enhancer = builder
+        .normalization(NormalizationPolicy::Loudness, -23.0)
+        .observer(|p: &Progress| eprint!("\r{:<14} {:>3.0}%", p.stage.to_string(), p.fraction() * 100.0))
+        .build()?;
+    enhancer.warm_up()?;
+
+    let result = enhancer.process_file(input, output)?;
+    let metrics = &result.performance_metrics;
+    eprintln!("\n{} -> {}: {:.2}s of audio, RTF {:.3}",
+        input.display(), output.display(), metrics.input_duration_seconds, metrics.real_time_factor);
+    Ok(())
+}
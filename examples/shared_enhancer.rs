@@ -0,0 +1,42 @@
+//! Share one enhancer between threads and enhance audio in memory.
+//!
+//! ```text
+//! cargo run --example shared_enhancer -- [model.onnx]
+//! ```
+//!
+//! Clones share the model sessions, so each request handler can hold its own copy.
+//! Requests run concurrently: here they share the two workers' queues, while a
+//! serial enhancer would check out one session per request.
+
+use std::thread;
+use ndarray::Array1;
+use zipenhancer::prelude::*;
+
+fn main() -> Result<()> {
+    let builder = match std::env::args().nth(1) {
+        Some(model) => Enhancer::builder().model(model).workers(2),
+        None => Enhancer::builder().spectral(),
+    };
+    let enhancer = builder.strength(0.9).build()?;
+
+    let handles: Vec<_> = (0..4u32).map(|n| {
+        let enhancer = enhancer.clone();
+        thread::spawn(move || -> Result<f32> {
+            // A 1 kHz tone in noise, at a different rate for every request
+            let rate = [16000, 22050, 44100, 48000][n as usize];
+            let noisy = Array1::from_iter((0..rate).map(|i| {
+                let t = i as f32 / rate as f32;
+                0.3 * (2.0 * std::f32::consts::PI * 1000.0 * t).sin() + 0.05 * ((i * 7919 % 1000) as f32 / 500.0 - 1.0)
+            }));
+            let clean = enhancer.enhance(&WavAudio::new_mono(rate, noisy, AudioFormat::Float32))?;
+            assert_eq!((clean.sample_rate(), clean.data().len()), (rate, rate as usize));
+            Ok(clean.data().to_mono().iter().fold(0.0f32, |m, s| m.max(s.abs())))
+        })
+    }).collect();
+
+    for (n, handle) in handles.into_iter().enumerate() {
+        let peak = handle.join().expect("worker panicked")?;
+        println!("request {}: enhanced, peak {:.3}", n, peak);
+    }
+    Ok(())
+}
@@ -1,10 +1,12 @@
 //! Audio Processing Module
 //!
 //! Provides audio file reading, writing, format conversion, and basic processing functions.
-//! Currently focuses on WAV format support.
+//! Currently focuses on WAV format support, plus STFT-based spectral analysis.
 
 pub mod wav;
 pub mod converter;
+pub mod spectral;
 
 pub use wav::{WavAudio, AudioFormat, AudioHeader, AudioData};
-pub use converter::AudioConverter;
\ No newline at end of file
+pub use converter::AudioConverter;
+pub use spectral::{Stft, StftConfig, WindowType, StreamingStft, StreamingIstft};
\ No newline at end of file
@@ -0,0 +1,371 @@
+//! Spectral Analysis - STFT/ISTFT, spectra and mel filterbanks
+//!
+//! Spectrograms are `Array2<Complex32>` of shape (frames, fft_size / 2 + 1).
+
+use std::sync::Arc;
+use ndarray::{Array1, Array2, ArrayView1};
+use rustfft::{Fft, FftPlanner};
+use serde::{Deserialize, Serialize};
+use crate::error::{ZipEnhancerError, Result};
+
+pub use rustfft::num_complex::Complex32;
+
+/// Window sum-of-squares below which ISTFT samples are left unnormalized
+const WSS_EPS: f32 = 1e-8;
+
+/// Analysis/synthesis window shape (periodic, as used for overlap-add)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowType {
+    #[default]
+    Hann,
+    Hamming,
+    Blackman,
+    Rectangular,
+}
+
+impl WindowType {
+    pub fn build(self, length: usize) -> Vec<f32> {
+        let n = length.max(1) as f32;
+        (0..length).map(|i| {
+            let phase = std::f32::consts::TAU * i as f32 / n;
+            match self {
+                Self::Hann => 0.5 - 0.5 * phase.cos(),
+                Self::Hamming => 0.54 - 0.46 * phase.cos(),
+                Self::Blackman => 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos(),
+                Self::Rectangular => 1.0,
+            }
+        }).collect()
+    }
+}
+
+/// STFT parameters
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StftConfig {
+    pub fft_size: usize,
+    /// Window length; frames are zero-padded up to `fft_size`
+    pub win_length: usize,
+    pub hop_size: usize,
+    pub window: WindowType,
+    /// Pad `win_length / 2` zeros at both ends so frame `t` is centred on sample `t * hop_size`
+    pub center: bool,
+}
+
+impl Default for StftConfig {
+    fn default() -> Self {
+        Self { fft_size: 512, win_length: 512, hop_size: 128, window: WindowType::Hann, center: true }
+    }
+}
+
+impl StftConfig {
+    pub fn new(fft_size: usize, hop_size: usize) -> Self {
+        Self { fft_size, win_length: fft_size, hop_size, ..Default::default() }
+    }
+
+    pub fn num_bins(&self) -> usize {
+        self.fft_size / 2 + 1
+    }
+}
+
+/// Short-time Fourier transform with cached FFT plans
+#[derive(Clone)]
+pub struct Stft {
+    config: StftConfig,
+    window: Vec<f32>,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+}
+
+impl std::fmt::Debug for Stft {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Stft").field("config", &self.config).finish()
+    }
+}
+
+impl Stft {
+    pub fn new(config: StftConfig) -> Result<Self> {
+        let window = config.window.build(config.win_length);
+        Self::with_window(config, window)
+    }
+
+    /// Use a custom analysis/synthesis window of `config.win_length` samples
+    pub fn with_window(config: StftConfig, window: Vec<f32>) -> Result<Self> {
+        if config.fft_size < 2 || config.hop_size == 0 {
+            return Err(ZipEnhancerError::config("STFT needs fft_size >= 2 and hop_size > 0"));
+        }
+        if config.win_length == 0 || config.win_length > config.fft_size || window.len() != config.win_length {
+            return Err(ZipEnhancerError::config(format!(
+                "STFT window length must be in 1..={} and match the window ({} samples)", config.fft_size, window.len()
+            )));
+        }
+        let mut planner = FftPlanner::new();
+        Ok(Self {
+            forward: planner.plan_fft_forward(config.fft_size),
+            inverse: planner.plan_fft_inverse(config.fft_size),
+            config,
+            window,
+        })
+    }
+
+    pub fn config(&self) -> &StftConfig { &self.config }
+    pub fn window(&self) -> &[f32] { &self.window }
+
+    /// Spectrum of one frame of up to `win_length` samples (shorter frames are zero-padded)
+    pub fn analyze_frame(&self, frame: &[f32]) -> Array1<Complex32> {
+        let mut buffer = vec![Complex32::new(0.0, 0.0); self.config.fft_size];
+        for (i, (&x, &w)) in frame.iter().zip(&self.window).enumerate() {
+            buffer[i] = Complex32::new(x * w, 0.0);
+        }
+        self.forward.process(&mut buffer);
+        buffer.truncate(self.config.num_bins());
+        Array1::from(buffer)
+    }
+
+    /// Inverse of one half spectrum, windowed for overlap-add (`win_length` samples)
+    pub fn synthesize_frame(&self, bins: ArrayView1<Complex32>) -> Vec<f32> {
+        let n = self.config.fft_size;
+        let mut buffer = vec![Complex32::new(0.0, 0.0); n];
+        for (k, &c) in bins.iter().enumerate().take(n / 2 + 1) {
+            buffer[k] = c;
+            if k > 0 && k < n - k {
+                buffer[n - k] = c.conj();
+            }
+        }
+        self.inverse.process(&mut buffer);
+        (0..self.config.win_length).map(|i| buffer[i].re / n as f32 * self.window[i]).collect()
+    }
+
+    fn padding(&self) -> usize {
+        if self.config.center { self.config.win_length / 2 } else { 0 }
+    }
+
+    /// Whole-signal STFT, shape (frames, bins)
+    pub fn forward(&self, signal: &Array1<f32>) -> Array2<Complex32> {
+        let (win, hop, pad) = (self.config.win_length, self.config.hop_size, self.padding());
+        let mut padded = vec![0.0f32; pad];
+        padded.extend(signal.iter());
+        padded.extend(std::iter::repeat_n(0.0, pad));
+
+        let frames = if padded.len() < win { 0 } else if self.config.center {
+            // Cover every input sample, zero-extending the tail as needed
+            (padded.len() - win).div_ceil(hop) + 1
+        } else {
+            (padded.len() - win) / hop + 1
+        };
+        padded.resize(padded.len().max((frames.max(1) - 1) * hop + win), 0.0);
+
+        let mut out = Array2::zeros((frames, self.config.num_bins()));
+        for t in 0..frames {
+            out.row_mut(t).assign(&self.analyze_frame(&padded[t * hop..t * hop + win]));
+        }
+        out
+    }
+
+    /// Weighted overlap-add ISTFT; reconstructs `forward` input exactly, trimmed or padded to `length`
+    pub fn inverse(&self, spectrogram: &Array2<Complex32>, length: usize) -> Array1<f32> {
+        let (win, hop, pad) = (self.config.win_length, self.config.hop_size, self.padding());
+        let frames = spectrogram.nrows();
+        let total = if frames == 0 { 0 } else { (frames - 1) * hop + win };
+        let mut out = vec![0.0f32; total];
+        let mut wss = vec![0.0f32; total];
+        for (t, row) in spectrogram.rows().into_iter().enumerate() {
+            for (i, x) in self.synthesize_frame(row).into_iter().enumerate() {
+                out[t * hop + i] += x;
+                wss[t * hop + i] += self.window[i] * self.window[i];
+            }
+        }
+        for (x, &w) in out.iter_mut().zip(&wss) {
+            if w > WSS_EPS { *x /= w; }
+        }
+        Array1::from_iter((0..length).map(|i| out.get(i + pad).copied().unwrap_or(0.0)))
+    }
+}
+
+pub fn magnitude(spectrogram: &Array2<Complex32>) -> Array2<f32> {
+    spectrogram.mapv(|c| c.norm())
+}
+
+pub fn phase(spectrogram: &Array2<Complex32>) -> Array2<f32> {
+    spectrogram.mapv(|c| c.arg())
+}
+
+pub fn power(spectrogram: &Array2<Complex32>) -> Array2<f32> {
+    spectrogram.mapv(|c| c.norm_sqr())
+}
+
+/// Rebuild a complex spectrogram from magnitude and phase
+pub fn from_polar(magnitude: &Array2<f32>, phase: &Array2<f32>) -> Array2<Complex32> {
+    ndarray::Zip::from(magnitude).and(phase).map_collect(|&m, &p| Complex32::from_polar(m, p))
+}
+
+/// HTK mel scale
+pub fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+pub fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// Triangular mel filterbank, shape (n_mels, fft_size / 2 + 1); apply as `power.dot(&fb.t())`
+pub fn mel_filterbank(n_mels: usize, fft_size: usize, sample_rate: u32, fmin: f32, fmax: f32) -> Array2<f32> {
+    let bins = fft_size / 2 + 1;
+    let (mel_min, mel_max) = (hz_to_mel(fmin), hz_to_mel(fmax.min(sample_rate as f32 / 2.0)));
+    let edges: Vec<f32> = (0..n_mels + 2)
+        .map(|i| mel_to_hz(mel_min + (mel_max - mel_min) * i as f32 / (n_mels + 1) as f32))
+        .collect();
+    let bin_hz = |k: usize| k as f32 * sample_rate as f32 / fft_size as f32;
+
+    let mut fb = Array2::zeros((n_mels, bins));
+    for m in 0..n_mels {
+        let (lo, center, hi) = (edges[m], edges[m + 1], edges[m + 2]);
+        for k in 0..bins {
+            let f = bin_hz(k);
+            let weight = if f <= lo || f >= hi { 0.0 } else if f <= center {
+                (f - lo) / (center - lo)
+            } else {
+                (hi - f) / (hi - center)
+            };
+            fb[[m, k]] = weight;
+        }
+    }
+    fb
+}
+
+/// Streaming STFT: push samples, receive each frame as soon as it is complete (no centring)
+#[derive(Debug, Clone)]
+pub struct StreamingStft {
+    stft: Stft,
+    buffer: Vec<f32>,
+}
+
+impl StreamingStft {
+    pub fn new(stft: Stft) -> Self {
+        Self { stft, buffer: Vec::new() }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Array1<Complex32>> {
+        self.buffer.extend_from_slice(samples);
+        let (win, hop) = (self.stft.config.win_length, self.stft.config.hop_size);
+        let mut frames = Vec::new();
+        let mut start = 0;
+        while start + win <= self.buffer.len() {
+            frames.push(self.stft.analyze_frame(&self.buffer[start..start + win]));
+            start += hop;
+        }
+        self.buffer.drain(..start.min(self.buffer.len()));
+        frames
+    }
+}
+
+/// Streaming ISTFT: each pushed frame yields `hop_size` finished samples.
+///
+/// Output is sample-aligned with the `StreamingStft` input and normalized by the
+/// steady-state window overlap, so the first `win_length - hop_size` samples fade in.
+#[derive(Debug, Clone)]
+pub struct StreamingIstft {
+    stft: Stft,
+    overlap: Vec<f32>,
+    norm: Vec<f32>,
+}
+
+impl StreamingIstft {
+    pub fn new(stft: Stft) -> Self {
+        let (win, hop) = (stft.config.win_length, stft.config.hop_size);
+        let norm = (0..hop).map(|i| {
+            (i..win).step_by(hop).map(|j| stft.window[j] * stft.window[j]).sum::<f32>()
+        }).collect();
+        Self { overlap: vec![0.0; win], stft, norm }
+    }
+
+    /// Overlap-add one frame and return the next `hop_size` output samples
+    pub fn push(&mut self, frame: ArrayView1<Complex32>) -> Vec<f32> {
+        let hop = self.stft.config.hop_size;
+        for (acc, x) in self.overlap.iter_mut().zip(self.stft.synthesize_frame(frame)) {
+            *acc += x;
+        }
+        let out = self.overlap[..hop.min(self.overlap.len())].iter().zip(&self.norm)
+            .map(|(&x, &w)| if w > WSS_EPS { x / w } else { x })
+            .collect();
+        self.overlap.drain(..hop.min(self.overlap.len()));
+        self.overlap.resize(self.stft.config.win_length, 0.0);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn random_signal(len: usize) -> Array1<f32> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        Array1::from_iter((0..len).map(|_| rng.gen_range(-0.5..0.5)))
+    }
+
+    #[test]
+    fn test_perfect_reconstruction() {
+        let signal = random_signal(5000);
+        for config in [StftConfig::default(), StftConfig { center: false, ..StftConfig::new(256, 64) },
+                       StftConfig { win_length: 400, window: WindowType::Hamming, ..StftConfig::new(512, 100) }] {
+            let stft = Stft::new(config).unwrap();
+            let spec = stft.forward(&signal);
+            assert_eq!(spec.ncols(), config.num_bins());
+            let rebuilt = stft.inverse(&spec, signal.len());
+            // Without centring the edges are only partially covered
+            let skip = if config.center { 0 } else { config.win_length };
+            let err = (skip..signal.len() - skip).map(|i| (rebuilt[i] - signal[i]).abs()).fold(0.0f32, f32::max);
+            assert!(err < 1e-4, "{:?}: {}", config, err);
+        }
+    }
+
+    #[test]
+    fn test_sine_peak_and_polar_roundtrip() {
+        // 1 kHz at 16 kHz with a 512-point FFT lands exactly on bin 32
+        let signal = Array1::from_iter((0..4096).map(|i| (std::f32::consts::TAU * 1000.0 * i as f32 / 16000.0).sin()));
+        let spec = Stft::new(StftConfig::new(512, 256)).unwrap().forward(&signal);
+        let mag = magnitude(&spec);
+        let row = mag.row(4);
+        let peak = (0..row.len()).max_by(|&a, &b| row[a].total_cmp(&row[b])).unwrap();
+        assert_eq!(peak, 32);
+        let rebuilt = from_polar(&mag, &phase(&spec));
+        assert!(spec.iter().zip(rebuilt.iter()).all(|(a, b)| (a - b).norm() < 1e-3));
+    }
+
+    #[test]
+    fn test_streaming_matches_offline() {
+        let signal = random_signal(4000);
+        let config = StftConfig { center: false, ..StftConfig::new(256, 64) };
+        let stft = Stft::new(config).unwrap();
+        let offline = stft.forward(&signal);
+
+        let mut analysis = StreamingStft::new(stft.clone());
+        let mut synthesis = StreamingIstft::new(stft.clone());
+        let mut frames = Vec::new();
+        let mut output = Vec::new();
+        for chunk in signal.as_slice().unwrap().chunks(100) {
+            for frame in analysis.push(chunk) {
+                output.extend(synthesis.push(frame.view()));
+                frames.push(frame);
+            }
+        }
+        assert_eq!(frames.len(), offline.nrows());
+        assert!(frames.iter().zip(offline.rows()).all(|(a, b)| a.iter().zip(b).all(|(x, y)| (x - y).norm() < 1e-4)));
+        // Past the fade-in, output reproduces the input sample for sample
+        assert_eq!(output.len(), frames.len() * config.hop_size);
+        let err = (config.win_length - config.hop_size..output.len()).map(|i| (output[i] - signal[i]).abs()).fold(0.0f32, f32::max);
+        assert!(err < 1e-4, "{}", err);
+    }
+
+    #[test]
+    fn test_mel_filterbank() {
+        assert!((hz_to_mel(1000.0) - 1000.0).abs() < 0.5);
+        assert!((mel_to_hz(hz_to_mel(3000.0)) - 3000.0).abs() < 0.1);
+        let fb = mel_filterbank(40, 512, 16000, 0.0, 8000.0);
+        assert_eq!(fb.dim(), (40, 257));
+        assert!(fb.iter().all(|&w| (0.0..=1.0).contains(&w)));
+        // Filter centres increase with the filter index
+        let centre = |m: usize| (0..257).max_by(|&a, &b| fb[[m, a]].total_cmp(&fb[[m, b]])).unwrap();
+        assert!((1..40).all(|m| centre(m) >= centre(m - 1)));
+    }
+}
@@ -2,9 +2,10 @@
 
 use std::path::Path;
 use std::fs::File;
+use std::io::BufWriter;
 use hound::{WavReader, WavWriter, SampleFormat};
 use ndarray::{Array1, Array2};
-use crate::error::{ZipEnhancerError, Result};
+use crate::error::{ResultExt, ZipEnhancerError, Result};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AudioFormat {
@@ -44,6 +45,22 @@ impl AudioHeader {
         }
     }
 
+    /// Read only the header of a WAV file; fails like `WavAudio::from_file` on unsupported formats
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let reader = WavReader::open(path).map_err(|e| ZipEnhancerError::wav(path, e))?;
+        let spec = reader.spec();
+        let format = check_spec(&spec).with_path(path)?;
+        Ok(Self {
+            sample_rate: spec.sample_rate,
+            channels: spec.channels,
+            format,
+            total_samples: reader.len(),
+            bits_per_sample: spec.bits_per_sample,
+            duration: reader.duration() as f64 / spec.sample_rate as f64,
+        })
+    }
+
     pub fn to_wav_spec(&self) -> hound::WavSpec {
         hound::WavSpec {
             channels: self.channels,
@@ -54,6 +71,20 @@ impl AudioHeader {
     }
 }
 
+/// Supported WAV layouts: mono/stereo, 16-bit PCM or 32-bit float
+fn check_spec(spec: &hound::WavSpec) -> Result<AudioFormat> {
+    let unsupported = |detail: String| Err(ZipEnhancerError::UnsupportedFormat { detail });
+    if spec.sample_rate == 0 { return unsupported("sample rate 0".into()); }
+    if spec.channels == 0 || spec.channels > 2 {
+        return unsupported(format!("{} channels, only mono/stereo supported", spec.channels));
+    }
+    match spec.bits_per_sample {
+        16 => Ok(AudioFormat::Int16),
+        32 if spec.sample_format == SampleFormat::Float => Ok(AudioFormat::Float32),
+        bits => unsupported(format!("{} bit {:?}", bits, spec.sample_format)),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum AudioData {
     Mono(Array1<f32>),
@@ -88,30 +119,18 @@ pub struct WavAudio {
 impl WavAudio {
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref();
-        let file = File::open(path)
-            .map_err(|e| ZipEnhancerError::audio(format!("Cannot open {}: {}", path.display(), e)))?;
-        
-        let mut reader = WavReader::new(file)
-            .map_err(|e| ZipEnhancerError::audio(format!("WAV read error: {}", e)))?;
+        let _span = tracing::info_span!("decode", path = %path.display()).entered();
+        let mut reader = WavReader::open(path).map_err(|e| ZipEnhancerError::wav(path, e))?;
 
         let spec = reader.spec();
-        if spec.sample_rate == 0 { return Err(ZipEnhancerError::audio("Invalid sample rate")); }
-        if spec.channels == 0 || spec.channels > 2 {
-            return Err(ZipEnhancerError::audio("Only mono/stereo supported"));
-        }
-
-        let format = match spec.bits_per_sample {
-            16 => AudioFormat::Int16,
-            32 if spec.sample_format == SampleFormat::Float => AudioFormat::Float32,
-            _ => return Err(ZipEnhancerError::audio(format!("Unsupported format: {} bit", spec.bits_per_sample))),
-        };
+        let format = check_spec(&spec).with_path(path)?;
 
         let samples: Vec<f32> = match spec.bits_per_sample {
             16 => reader.samples::<i16>()
-                .map(|s| s.map(|v| v as f32 / 32767.0).map_err(|e| ZipEnhancerError::audio(e.to_string())))
+                .map(|s| s.map(|v| v as f32 / 32767.0).map_err(|e| ZipEnhancerError::wav(path, e)))
                 .collect::<Result<Vec<_>>>()?,
             32 => reader.samples::<f32>()
-                .map(|s| s.map_err(|e| ZipEnhancerError::audio(e.to_string())))
+                .map(|s| s.map_err(|e| ZipEnhancerError::wav(path, e)))
                 .collect::<Result<Vec<_>>>()?,
             _ => unreachable!(),
         };
@@ -133,7 +152,7 @@ impl WavAudio {
                 sample_rate: spec.sample_rate,
                 channels: spec.channels,
                 format,
-                total_samples: reader.len() as u32,
+                total_samples: reader.len(),
                 bits_per_sample: spec.bits_per_sample,
                 duration: reader.len() as f64 / spec.sample_rate as f64,
             },
@@ -141,18 +160,32 @@ impl WavAudio {
         })
     }
 
+    /// Write the file atomically: samples go to a `.part` sibling that is renamed into place
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let path = path.as_ref();
+        let _span = tracing::info_span!("encode", path = %path.display()).entered();
         if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| ZipEnhancerError::audio(e.to_string()))?;
+            std::fs::create_dir_all(parent).map_err(|e| ZipEnhancerError::io_at(parent, e))?;
+        }
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".part");
+        let partial = Path::new(&partial);
+
+        let written = self.write_wav(partial, path)
+            .and_then(|_| std::fs::rename(partial, path).map_err(|e| ZipEnhancerError::io_at(path, e)));
+        if written.is_err() {
+            let _ = std::fs::remove_file(partial);
         }
+        written
+    }
+
+    /// Write to `file`; errors name `path`, the file the caller asked for
+    fn write_wav(&self, file: &Path, path: &Path) -> Result<()> {
+        let file = File::create(file).map_err(|e| ZipEnhancerError::io_at(path, e))?;
 
-        let file = File::create(path)
-            .map_err(|e| ZipEnhancerError::audio(format!("Cannot create {}: {}", path.display(), e)))?;
-        
         let spec = self.header.to_wav_spec();
-        let mut writer = WavWriter::new(file, spec)
-            .map_err(|e| ZipEnhancerError::audio(e.to_string()))?;
+        let mut writer = WavWriter::new(BufWriter::new(file), spec)
+            .map_err(|e| ZipEnhancerError::wav_write(path, e))?;
 
         let write_sample = |w: &mut WavWriter<_>, s: f32| -> Result<()> {
             let clamped = s.clamp(-1.0, 1.0);
@@ -160,7 +193,7 @@ impl WavAudio {
                 w.write_sample(clamped)
             } else {
                 w.write_sample((clamped * 32767.0) as i16)
-            }.map_err(|e| ZipEnhancerError::audio(e.to_string()))
+            }.map_err(|e| ZipEnhancerError::wav_write(path, e))
         };
 
         match &self.data {
@@ -168,7 +201,7 @@ impl WavAudio {
             AudioData::Stereo(d) => { for row in d.rows() { for &s in row { write_sample(&mut writer, s)?; } } }
         }
 
-        writer.finalize().map_err(|e| ZipEnhancerError::audio(e.to_string()))
+        writer.finalize().map_err(|e| ZipEnhancerError::wav_write(path, e))
     }
 
     pub fn new_mono(sample_rate: u32, data: Array1<f32>, format: AudioFormat) -> Self {
@@ -185,6 +218,10 @@ impl WavAudio {
     pub fn total_samples(&self) -> u32 { self.header.total_samples }
     pub fn duration(&self) -> f64 { self.header.duration }
     pub fn format(&self) -> AudioFormat { self.header.format }
+    /// Size of the sample data in the file's encoding
+    pub fn sample_bytes(&self) -> u64 {
+        (self.data.len() * self.data.channels() as usize) as u64 * self.header.format.bytes_per_sample() as u64
+    }
 }
 
 #[cfg(test)]
@@ -218,6 +255,33 @@ mod tests {
 
         assert_eq!(loaded.sample_rate(), original.sample_rate());
         assert_eq!(loaded.total_samples(), original.total_samples());
+        let mut partial = temp.path().as_os_str().to_owned();
+        partial.push(".part");
+        assert!(!Path::new(&partial).exists());
+    }
+
+    #[test]
+    fn test_header_only() {
+        let temp = NamedTempFile::new().unwrap();
+        WavAudio::new_mono(8000, Array1::zeros(4000), AudioFormat::Int16).save_to_file(temp.path()).unwrap();
+        let header = AudioHeader::from_file(temp.path()).unwrap();
+        assert_eq!((header.sample_rate, header.channels, header.bits_per_sample), (8000, 1, 16));
+        assert_eq!(header.duration, 0.5);
+        assert!(matches!(AudioHeader::from_file("missing.wav"), Err(ZipEnhancerError::InputNotFound { .. })));
+    }
+
+    #[test]
+    fn test_unsupported_bit_depth() {
+        let temp = NamedTempFile::new().unwrap();
+        let spec = hound::WavSpec { channels: 1, sample_rate: 16000, bits_per_sample: 24, sample_format: SampleFormat::Int };
+        let mut writer = WavWriter::create(temp.path(), spec).unwrap();
+        writer.write_sample(0i32).unwrap();
+        writer.finalize().unwrap();
+
+        let err = WavAudio::from_file(temp.path()).unwrap_err();
+        assert!(matches!(err.root(), ZipEnhancerError::UnsupportedFormat { detail } if detail.starts_with("24 bit")));
+        assert_eq!(err.context().unwrap().path.as_deref(), Some(temp.path()));
+        assert_eq!(err.code(), crate::error::ErrorCode::UnsupportedFormat);
     }
 
     #[test]
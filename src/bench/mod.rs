@@ -0,0 +1,208 @@
+//! Performance Sweep
+//!
+//! Runs one input through the parallel enhancer for every combination of worker
+//! count, threads per worker and segment size, and picks the fastest settings.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use ndarray::Array1;
+use serde::Serialize;
+use crate::audio::{AudioFormat, WavAudio};
+use crate::config::Config;
+use crate::error::{ZipEnhancerError, Result};
+use crate::processing::{Enhancer, Execution};
+use crate::synth::{self, GenerateSpec, NoiseKind, SignalKind};
+
+/// One point of the sweep
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BenchCase {
+    pub workers: usize,
+    pub threads_per_worker: usize,
+    pub segment_size: usize,
+}
+
+impl BenchCase {
+    /// `base` with this case's segment size, worker count and total thread count
+    pub fn apply(&self, base: &Config) -> Config {
+        let mut config = base.clone();
+        config.audio.segment_size = self.segment_size;
+        config.model.inference_threads = self.workers * self.threads_per_worker;
+        config.processing.parallel_workers = self.workers;
+        config
+    }
+}
+
+/// Timing of one case, from the median of its timed runs
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchResult {
+    pub case: BenchCase,
+    pub runs: usize,
+    pub audio_secs: f64,
+    /// Wall time to process the whole file
+    pub wall_secs: f64,
+    pub rtf: f64,
+    /// Seconds of audio processed per second
+    pub throughput: f64,
+    /// Mean model time of one segment
+    pub segment_latency_ms: f64,
+}
+
+/// A case left out of the sweep because its settings are invalid
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedCase {
+    pub case: BenchCase,
+    pub reason: String,
+}
+
+/// Timings of the valid cases and the cases that were skipped
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SweepReport {
+    pub results: Vec<BenchResult>,
+    pub skipped: Vec<SkippedCase>,
+}
+
+/// Every combination of the given values
+pub fn cases(workers: &[usize], threads: &[usize], segment_sizes: &[usize]) -> Vec<BenchCase> {
+    let mut cases = Vec::new();
+    for &segment_size in segment_sizes {
+        for &workers in workers {
+            for &threads_per_worker in threads {
+                cases.push(BenchCase { workers, threads_per_worker, segment_size });
+            }
+        }
+    }
+    cases
+}
+
+/// Run every valid case on `input`: one untimed warm-up, then `repeat` timed runs.
+/// Cases whose settings fail validation are skipped and reported instead of ending
+/// the sweep. Outputs go to a scratch directory that is removed afterwards.
+pub fn sweep(base: &Config, input: &Path, cases: &[BenchCase], repeat: usize, mut on_result: impl FnMut(&BenchResult)) -> Result<SweepReport> {
+    let scratch = ScratchDir::new("bench")?;
+    let output = scratch.0.join("bench.wav");
+    let mut report = SweepReport::default();
+    for case in cases {
+        let config = case.apply(base);
+        if let Err(e) = config.validate() {
+            report.skipped.push(SkippedCase { case: *case, reason: e.report() });
+            continue;
+        }
+        let enhancer = Enhancer::new(config, Execution::Parallel { workers: case.workers })?;
+        enhancer.warm_up()?;
+        enhancer.process_file(input, &output)?;
+
+        let mut runs = Vec::with_capacity(repeat.max(1));
+        for _ in 0..repeat.max(1) {
+            let start = Instant::now();
+            let result = enhancer.process_file(input, &output)?;
+            let metrics = result.performance_metrics;
+            runs.push((start.elapsed().as_secs_f64(), metrics.input_duration_seconds, metrics.average_inference_time_ms));
+        }
+        runs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (wall_secs, audio_secs, segment_latency_ms) = runs[runs.len() / 2];
+        let result = BenchResult {
+            case: *case,
+            runs: runs.len(),
+            audio_secs,
+            wall_secs,
+            rtf: wall_secs / audio_secs,
+            throughput: audio_secs / wall_secs,
+            segment_latency_ms,
+        };
+        on_result(&result);
+        report.results.push(result);
+    }
+    Ok(report)
+}
+
+/// The case with the lowest real-time factor
+pub fn best(results: &[BenchResult]) -> Option<&BenchResult> {
+    results.iter().min_by(|a, b| a.rtf.total_cmp(&b.rtf))
+}
+
+/// Seeded speech-like signal in babble at 10 dB SNR, 32-bit float mono
+pub fn synthetic_input(duration_secs: f64, sample_rate: u32) -> WavAudio {
+    let spec = GenerateSpec { duration_secs, sample_rate, seed: 44, ..Default::default() };
+    let mixture = synth::generate_mixture(&spec, SignalKind::Speech, NoiseKind::Babble, 10.0);
+    WavAudio::new_mono(sample_rate, Array1::from(mixture.noisy), AudioFormat::Float32)
+}
+
+/// Temporary directory removed on drop; unique per instance, so concurrent sweeps and
+/// regression runs in one process do not share or delete each other's files
+pub(crate) struct ScratchDir(pub PathBuf);
+
+impl ScratchDir {
+    pub fn new(purpose: &str) -> Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("zipenhancer-{}-{}-{}", purpose, std::process::id(), id));
+        std::fs::create_dir_all(&path).map_err(|e| ZipEnhancerError::io_at(&path, e))?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::spectral_gate::DenoiseBackend;
+
+    #[test]
+    fn test_scratch_dirs_are_distinct() {
+        let (a, b) = (ScratchDir::new("test").unwrap(), ScratchDir::new("test").unwrap());
+        assert_ne!(a.0, b.0);
+        let path = b.0.clone();
+        drop(a);
+        assert!(path.is_dir());
+    }
+
+    #[test]
+    fn test_cases_cover_every_combination() {
+        let all = cases(&[1, 2], &[1, 2], &[8000, 16000]);
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], BenchCase { workers: 1, threads_per_worker: 1, segment_size: 8000 });
+
+        let config = all[7].apply(&Config::default());
+        assert_eq!((config.audio.segment_size, config.model.inference_threads, config.processing.parallel_workers), (16000, 4, 2));
+    }
+
+    #[test]
+    fn test_sweep_picks_fastest() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.wav");
+        synthetic_input(2.0, 16000).save_to_file(&input).unwrap();
+        let mut config = Config::default();
+        config.denoiser.backend = DenoiseBackend::Spectral;
+
+        let mut seen = 0;
+        let report = sweep(&config, &input, &cases(&[1], &[1], &[8000, 16000]), 1, |_| seen += 1).unwrap();
+        let results = report.results;
+        assert_eq!((results.len(), seen, report.skipped.len()), (2, 2, 0));
+        assert!((results[0].audio_secs - 2.0).abs() < 1e-3);
+        assert!(results.iter().all(|r| r.rtf > 0.0 && (r.throughput * r.rtf - 1.0).abs() < 1e-9));
+        let fastest = best(&results).unwrap();
+        assert!(results.iter().all(|r| fastest.rtf <= r.rtf));
+    }
+
+    #[test]
+    fn test_sweep_skips_invalid_cases() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.wav");
+        synthetic_input(1.0, 16000).save_to_file(&input).unwrap();
+        let mut config = Config::default();
+        config.denoiser.backend = DenoiseBackend::Spectral;
+
+        // An odd segment size fails validation; the other cases still run
+        let report = sweep(&config, &input, &cases(&[1, 0], &[1], &[8000, 8001]), 1, |_| {}).unwrap();
+        assert_eq!(report.results.len(), 1);
+        assert_eq!(report.results[0].case, BenchCase { workers: 1, threads_per_worker: 1, segment_size: 8000 });
+        assert_eq!(report.skipped.len(), 3);
+        assert!(report.skipped.iter().any(|s| s.case.segment_size == 8001 && s.reason.contains("even")));
+    }
+}
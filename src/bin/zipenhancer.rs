@@ -1,20 +1,707 @@
-//! ZipEnhancer Command Line Tool Entry Point
-//!
-//! This is a simple binary entry point for testing basic project structure.
+//! ZipEnhancer Rust - Audio Denoise Processor
 
-use zipenhancer::{Config, Args};
+use clap::Parser;
+use std::collections::HashMap;
+use std::process;
+use std::path::{Path, PathBuf};
+use zipenhancer::{init_logging, Args, Result, ZipEnhancerError};
+use std::sync::Arc;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use zipenhancer::error::{exit_code, ErrorCode, ResultExt};
+use zipenhancer::processing::{CancellationToken, Enhancer, Execution, Progress, ProgressObserver, Stage};
+use zipenhancer::report::{ErrorRecord, FileRecord};
+use zipenhancer::config::{BenchArgs, Command, ConfigCommand, ConfigInitArgs, ConfigShowArgs, ConfigSourceArgs, EnhanceArgs,
+    EvaluateArgs, GenerateArgs, InfoArgs, OutputFormat, RegressArgs, ReportArgs, SelftestArgs, VisualizeArgs};
 
 fn main() {
-    println!("=== ZipEnhancer Rust Version Basic Test ===");
-    println!("Project structure created successfully!");
-
-    // Test config system
-    let config = Config::default();
-    println!("Default config:");
-    println!("  Model path: {:?}", config.model_path);
-    println!("  Sample rate: {}", config.sample_rate);
-    println!("  Overlap ratio: {}", config.overlap_ratio);
-
-    println!();
-    println!("Next step: implement audio I/O module and ONNX inference functions.");
-}
\ No newline at end of file
+    let command = Args::parse().into_command();
+    let verbose = match &command {
+        Command::Enhance(args) => args.verbose,
+        Command::Selftest(args) => args.verbose,
+        _ => false,
+    };
+    init_logging(verbose);
+
+    let result = match command {
+        Command::Enhance(args) => run_enhance(*args),
+        Command::Info(args) => run_info(args).map(|_| exit_code::SUCCESS),
+        Command::Selftest(args) => run_selftest(args).map(|_| exit_code::SUCCESS),
+        Command::Config(ConfigCommand::Init(init)) => run_config_init(init).map(|_| exit_code::SUCCESS),
+        Command::Config(ConfigCommand::Show(show)) => run_config_show(show).map(|_| exit_code::SUCCESS),
+        Command::Config(ConfigCommand::Validate(source)) => run_config_validate(source).map(|_| exit_code::SUCCESS),
+        Command::Evaluate(eval) => run_evaluate(eval).map(|_| exit_code::SUCCESS),
+        Command::Visualize(vis) => run_visualize(vis).map(|_| exit_code::SUCCESS),
+        Command::Report(report) => run_report(report).map(|_| exit_code::SUCCESS),
+        Command::Bench(bench) => run_bench(bench).map(|_| exit_code::SUCCESS),
+        Command::Generate(generate) => run_generate(generate).map(|_| exit_code::SUCCESS),
+        Command::Regress(regress) => run_regress(regress),
+    };
+    match result {
+        Ok(exit_code::SUCCESS) => {}
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("Error: {}", e.report());
+            process::exit(e.exit_code());
+        }
+    }
+}
+
+/// Human-readable output: stdout in text mode, stderr when stdout carries JSON
+struct Console {
+    format: OutputFormat,
+}
+
+impl Console {
+    fn line(&self, text: impl std::fmt::Display) {
+        if self.format == OutputFormat::Text {
+            println!("{}", text);
+        } else {
+            eprintln!("{}", text);
+        }
+    }
+}
+
+/// Terminal progress bar fed by the enhancer's progress observer
+#[derive(Clone)]
+struct ProgressDisplay {
+    bar: ProgressBar,
+}
+
+impl ProgressDisplay {
+    /// Drawn on stderr, hidden when it is not a terminal or `--no-progress` is given
+    fn new(enabled: bool) -> Self {
+        let target = if enabled { ProgressDrawTarget::stderr() } else { ProgressDrawTarget::hidden() };
+        let bar = ProgressBar::with_draw_target(None, target);
+        bar.set_style(ProgressStyle::with_template("{prefix}{bar:30} {pos}/{len} segments  {msg}")
+            .expect("valid progress template"));
+        Self { bar }
+    }
+
+    fn start_file(&self, index: usize, total: usize, input: &Path) {
+        let name = input.file_name().unwrap_or_default().to_string_lossy();
+        self.bar.set_prefix(if total > 1 { format!("[{}/{}] {} ", index + 1, total, name) } else { format!("{} ", name) });
+        self.bar.reset();
+        self.bar.unset_length();
+    }
+
+    /// Clear the bar before the file's result is printed
+    fn finish_file(&self) { self.bar.finish_and_clear(); }
+
+    fn observer(&self) -> Arc<dyn ProgressObserver> {
+        let bar = self.bar.clone();
+        Arc::new(move |p: &Progress| {
+            if p.segments_total > 0 {
+                bar.set_length(p.segments_total as u64);
+            }
+            bar.set_position(p.segments_done as u64);
+            let mut message = p.stage.to_string();
+            if let Some(eta) = p.eta().filter(|_| p.stage == Stage::Denoising) {
+                message.push_str(&format!("  ETA {:.1}s", eta.as_secs_f64()));
+            }
+            if let Some(rtf) = p.rtf() {
+                message.push_str(&format!("  RTF {:.3}", rtf));
+            }
+            bar.set_message(message);
+        })
+    }
+}
+
+/// Cancel `token` on the first Ctrl-C; a second one exits immediately
+fn install_interrupt_handler(token: CancellationToken) {
+    let handler = ctrlc::set_handler(move || {
+        if token.is_cancelled() {
+            process::exit(exit_code::CANCELLED);
+        }
+        eprintln!("\nInterrupted: stopping after the current segment (Ctrl-C again to abort)");
+        token.cancel();
+    });
+    if let Err(e) = handler {
+        log::warn!("Cannot install the Ctrl-C handler: {}", e);
+    }
+}
+
+/// Enhance one file of a run into its result record
+fn process_job(enhancer: &Enhancer, input: &Path, output: &Path) -> Result<FileRecord> {
+    if !input.exists() {
+        return Err(ZipEnhancerError::InputNotFound { path: input.to_path_buf() });
+    }
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    enhancer.process_file(input, output).map(|r| FileRecord::from(&r)).with_path(input)
+}
+
+/// Input/output pairs: the configured input, or every `--input` written into `--output-dir`
+fn enhance_jobs(args: &EnhanceArgs, config: &zipenhancer::Config) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let inputs = if args.input.len() > 1 {
+        if args.output_dir.is_none() {
+            return Err(ZipEnhancerError::config("Several inputs need --output-dir"));
+        }
+        if config.residual_path.is_some() || config.vad.export_path.is_some() {
+            return Err(ZipEnhancerError::config("--residual and --vad-export write a single file and need a single input"));
+        }
+        args.input.clone()
+    } else {
+        vec![config.input_path.clone()]
+    };
+    if inputs[0].as_os_str().is_empty() {
+        return Err(ZipEnhancerError::config("No input file: pass --input or set input_path"));
+    }
+    let jobs: Vec<_> = inputs.into_iter().map(|input| {
+        let output = match &args.output_dir {
+            Some(dir) => dir.join(input.file_name().unwrap_or_default()),
+            None => config.output_path.clone(),
+        };
+        (input, output)
+    }).collect();
+    check_job_paths(&jobs, args.result_json.as_deref())?;
+    Ok(jobs)
+}
+
+/// Where `--result-json` puts the record of `output`: the path itself for a single input,
+/// otherwise `<output stem>.json` inside it
+fn result_path(result_json: &Path, output: &Path, batch: bool) -> PathBuf {
+    if !batch { return result_json.to_path_buf(); }
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    result_json.join(format!("{}.json", stem))
+}
+
+/// Reject jobs that would overwrite their own input, or another job's output or result record
+fn check_job_paths(jobs: &[(PathBuf, PathBuf)], result_json: Option<&Path>) -> Result<()> {
+    let same_file = |a: &Path, b: &Path| a == b || a.canonicalize().is_ok_and(|a| b.canonicalize().is_ok_and(|b| a == b));
+    let mut outputs = HashMap::new();
+    let mut results = HashMap::new();
+    for (input, output) in jobs {
+        if same_file(input, output) {
+            return Err(ZipEnhancerError::config(format!("Output {} would overwrite its input", output.display())));
+        }
+        if let Some(other) = outputs.insert(output, input) {
+            return Err(ZipEnhancerError::config(format!("{} and {} would both be written to {}",
+                other.display(), input.display(), output.display())));
+        }
+        if let Some(path) = result_json {
+            let result = result_path(path, output, jobs.len() > 1);
+            if let Some(other) = results.insert(result.clone(), input) {
+                return Err(ZipEnhancerError::config(format!("The results of {} and {} would both be written to {}",
+                    other.display(), input.display(), result.display())));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Process every input; returns the exit code (partial batch when only some files failed)
+fn run_enhance(args: EnhanceArgs) -> Result<i32> {
+    let console = Console { format: args.output_format };
+    if args.verbose {
+        console.line(zipenhancer::get_library_info());
+        console.line("");
+    }
+
+    let config = zipenhancer::config::Config::from_args_and_config(args.clone())?;
+    let jobs = enhance_jobs(&args, &config)?;
+    let uses_model = config.denoiser.backend.uses_model();
+    if uses_model {
+        check_and_setup_onnx_library(args.onnx_lib.as_deref(), args.verbose)?;
+        if !config.model_path().exists() {
+            return Err(ZipEnhancerError::ModelNotFound { path: config.model_path().clone() });
+        }
+    }
+
+    console.line("=== ZipEnhancer Audio Denoise Processor ===");
+    if let [(input, output)] = jobs.as_slice() {
+        console.line(format_args!("Input: {}", input.display()));
+        console.line(format_args!("Output: {}", output.display()));
+    } else {
+        console.line(format_args!("Inputs: {} files", jobs.len()));
+        console.line(format_args!("Output directory: {}", args.output_dir.as_deref().unwrap_or(Path::new(".")).display()));
+    }
+    if !uses_model {
+        console.line("Backend: spectral gate (no model)");
+    }
+
+    let _trace = args.trace_chrome.as_deref().map(zipenhancer::telemetry::init_chrome_trace).transpose()?;
+    if let Some(addr) = &args.metrics_addr {
+        let bound = zipenhancer::telemetry::serve(addr)?;
+        console.line(format_args!("Metrics: http://{}/metrics", bound));
+    }
+
+    let cancel = CancellationToken::new();
+    install_interrupt_handler(cancel.clone());
+    let display = ProgressDisplay::new(!args.no_progress && !args.verbose);
+
+    let execution = if args.serial { Execution::Serial } else { Execution::from_config(&config) };
+    match execution {
+        Execution::Serial => console.line(format_args!("Mode: Serial ({} threads)", config.inference_threads())),
+        Execution::Parallel { workers } => console.line(format_args!("Mode: Parallel ({} workers)", workers)),
+    }
+    let enhancer = Enhancer::new(config.clone(), execution)?
+        .with_observer(display.observer())
+        .with_cancellation(cancel.clone());
+    enhancer.warm_up()?;
+    console.line("============================================\n");
+
+    let mut records = Vec::with_capacity(jobs.len());
+    let mut cancelled = false;
+    for (index, (input, output)) in jobs.iter().enumerate() {
+        display.start_file(index, jobs.len(), input);
+        let result = process_job(&enhancer, input, output);
+        display.finish_file();
+        let mut record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                // The remaining files are skipped, but the records so far are still reported
+                if e.code() == ErrorCode::Cancelled {
+                    eprintln!("Cancelled: {} was not written", output.display());
+                    cancelled = true;
+                } else {
+                    eprintln!("Error: {}: {}", input.display(), e.report());
+                }
+                FileRecord::failed(input, output, &e)
+            }
+        };
+        if let Some(path) = &args.result_json {
+            // A record that cannot be saved fails its file, not the rest of the batch
+            let path = result_path(path, output, jobs.len() > 1);
+            if let Err(e) = record.save(&path).with_path(&path) {
+                eprintln!("Error: {}: {}", input.display(), e.report());
+                if record.error.is_none() { record.error = Some((&e).into()); }
+            }
+        }
+        match args.output_format {
+            OutputFormat::Text if record.error.is_none() => print_record(&console, &record, jobs.len() > 1, config.verbose()),
+            OutputFormat::Jsonl => println!("{}", to_json(&record, false)?),
+            _ => {}
+        }
+        records.push(record);
+        if cancelled { break; }
+    }
+    if args.output_format == OutputFormat::Json {
+        println!("{}", to_json(&records, true)?);
+    }
+    if cancelled {
+        return Ok(exit_code::CANCELLED);
+    }
+
+    let failed: Vec<&ErrorRecord> = records.iter().filter_map(|r| r.error.as_ref()).collect();
+    Ok(match failed.first() {
+        None => exit_code::SUCCESS,
+        Some(first) if failed.len() == records.len() => first.exit_code,
+        Some(_) => {
+            eprintln!("{} of {} files failed", failed.len(), records.len());
+            exit_code::PARTIAL_BATCH
+        }
+    })
+}
+
+fn to_json<T: serde::Serialize>(value: &T, pretty: bool) -> Result<String> {
+    let json = if pretty { serde_json::to_string_pretty(value) } else { serde_json::to_string(value) };
+    json.map_err(|e| ZipEnhancerError::processing(format!("Failed to serialize result: {}", e)))
+}
+
+fn print_record(console: &Console, record: &FileRecord, batch: bool, verbose: bool) {
+    if batch {
+        console.line(format_args!("=== {} ===", record.input_path.display()));
+    } else {
+        console.line("=== Processing Complete ===");
+    }
+    console.line(format_args!("Time: {:.2}s", record.processing_time_secs));
+    console.line(format_args!("RTF: {:.3}", record.rtf));
+    if verbose {
+        console.line(format_args!("Segments: {}", record.segment_count));
+        if record.worker_count > 1 {
+            console.line(format_args!("Workers: {}", record.worker_count));
+        }
+        console.line(format_args!("Avg inference: {:.2}ms", record.avg_inference_ms));
+        print_vad(console, record.speech_regions.as_deref(), record.skipped_segments, record.cpu_saved_ms);
+        print_alignment(console, &record.alignment);
+        print_residual(console, record.residual_path.as_deref(), &record.residual_energy_db);
+        print_quality(console, &record.quality);
+    }
+}
+
+fn print_alignment(console: &Console, alignment: &zipenhancer::processing::AlignmentInfo) {
+    console.line(format_args!("Alignment: delay {} samples ({:?}), {} samples @ {}Hz (padded {}, trimmed {})",
+        alignment.delay_samples, alignment.delay_mode, alignment.output_samples,
+        alignment.output_sample_rate, alignment.padded_samples, alignment.trimmed_samples));
+}
+
+fn print_vad(console: &Console, regions: Option<&[zipenhancer::processing::SpeechRegion]>, skipped: usize, saved_ms: f64) {
+    if let Some(regions) = regions {
+        let speech: f64 = regions.iter().map(|r| r.end_secs - r.start_secs).sum();
+        console.line(format_args!("VAD: {} speech regions ({:.1}s), {} segments skipped, ~{:.0}ms inference saved",
+            regions.len(), speech, skipped, saved_ms));
+    }
+}
+
+fn print_residual(console: &Console, path: Option<&Path>, energy_db: &[f32]) {
+    if let Some(path) = path {
+        console.line(format_args!("Residual: {}", path.display()));
+    }
+    if !energy_db.is_empty() {
+        let mean = energy_db.iter().sum::<f32>() / energy_db.len() as f32;
+        let max = energy_db.iter().copied().fold(f32::MIN, f32::max);
+        console.line(format_args!("Residual energy: mean {:.1} dBFS, max {:.1} dBFS per segment", mean, max));
+    }
+}
+
+fn print_quality(console: &Console, quality: &zipenhancer::metrics::QualityEstimate) {
+    for (label, s) in [("Input", &quality.input), ("Output", &quality.output)] {
+        console.line(format_args!("{}: SNR ~{:.1} dB, noise floor {:.1} dBFS, peak {:.1} dBFS, RMS {:.1} dBFS, {:.1} LUFS, {} clipped",
+            label, s.snr_db, s.noise_floor_db, s.levels.peak_dbfs, s.levels.rms_dbfs, s.levels.integrated_lufs, s.clipped_samples));
+    }
+    console.line(format_args!("Estimated SNR improvement: {:.1} dB, noise reduction {:.1} dB", quality.snr_improvement_db, quality.noise_reduction_db));
+    for warning in quality.warnings() {
+        console.line(format_args!("Warning: {}", warning));
+    }
+}
+
+fn run_evaluate(args: EvaluateArgs) -> Result<()> {
+    let report = zipenhancer::metrics::evaluate_paths(&args.enhanced, &args.clean)?;
+    if let Some(path) = &args.output {
+        report.save(path)?;
+        eprintln!("Report: {}", path.display());
+    }
+    if args.csv {
+        print!("{}", report.to_csv());
+    } else {
+        println!("{}", report.to_json()?);
+    }
+    Ok(())
+}
+
+fn run_visualize(args: VisualizeArgs) -> Result<()> {
+    let config = zipenhancer::visualize::VisualizeConfig {
+        fft_size: args.fft_size,
+        hop_size: args.hop_size,
+        width: args.width,
+        height: args.height,
+        min_db: args.min_db,
+        ..Default::default()
+    };
+    let written = zipenhancer::visualize::visualize_files(&args.input, &args.output, args.residual.as_deref(), &args.out_dir, &config)?;
+    for path in written {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+fn resolve_layers(source: &ConfigSourceArgs) -> Result<zipenhancer::config::ResolvedConfig> {
+    zipenhancer::config::ConfigLayers::discover(source.config_file.as_deref(), source.profile.as_deref())?.resolve(&[])
+}
+
+fn run_config_show(args: ConfigShowArgs) -> Result<()> {
+    let resolved = resolve_layers(&args.source)?;
+    if args.origin {
+        print!("{}", resolved.format_origins());
+    } else {
+        let text = toml::to_string_pretty(&resolved.config)
+            .map_err(|e| ZipEnhancerError::config(format!("Failed to serialize config: {}", e)))?;
+        print!("{}", text);
+    }
+    if let Err(e) = resolved.config.validate() {
+        eprintln!("Warning: {}", e.report());
+    }
+    Ok(())
+}
+
+fn run_config_init(args: ConfigInitArgs) -> Result<()> {
+    let path = if args.user {
+        zipenhancer::config::layered::user_config_path()
+            .ok_or_else(|| ZipEnhancerError::config("Cannot locate the user config directory (HOME is not set)"))?
+    } else {
+        args.path
+    };
+    if path.exists() && !args.force {
+        return Err(ZipEnhancerError::config(format!("{} already exists (use --force to overwrite)", path.display())));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    zipenhancer::Config::create_default_config(&path)?;
+    println!("Created {}", path.display());
+    Ok(())
+}
+
+fn run_config_validate(source: ConfigSourceArgs) -> Result<()> {
+    let resolved = resolve_layers(&source)?;
+    for (key, origin) in &resolved.unknown_keys {
+        println!("Unknown key: {} ({})", key, origin);
+    }
+    resolved.config.validate()?;
+    if !resolved.unknown_keys.is_empty() {
+        return Err(ZipEnhancerError::config(format!("{} unknown config keys", resolved.unknown_keys.len())));
+    }
+    println!("Configuration is valid");
+    Ok(())
+}
+
+fn run_report(args: ReportArgs) -> Result<()> {
+    let records = zipenhancer::report::load_records(&args.results)?;
+    let html = zipenhancer::report::render_report(&records, !args.no_thumbnails)?;
+    if let Some(parent) = args.output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&args.output, html)?;
+    println!("Report: {} ({} files)", args.output.display(), records.len());
+    Ok(())
+}
+
+fn run_bench(args: BenchArgs) -> Result<()> {
+    use zipenhancer::bench;
+    let console = Console { format: args.output_format };
+    if let Some(path) = args.write_config.as_ref().filter(|p| p.exists() && !args.force) {
+        return Err(ZipEnhancerError::config(format!("{} already exists (use --force to overwrite)", path.display())));
+    }
+    let mut config = resolve_layers(&args.source)?.config;
+    if let Some(model) = args.model { config.model.path = model; }
+    if let Some(backend) = args.backend { config.denoiser.backend = backend; }
+    if config.denoiser.backend.uses_model() {
+        check_and_setup_onnx_library(args.onnx_lib.as_deref(), false)?;
+        if !config.model_path().exists() {
+            return Err(ZipEnhancerError::ModelNotFound { path: config.model_path().clone() });
+        }
+    }
+
+    // The synthetic input lives only for the duration of the sweep
+    let synthetic = args.input.is_none()
+        .then(|| std::env::temp_dir().join(format!("zipenhancer-bench-{}.wav", process::id())));
+    if let Some(path) = &synthetic {
+        bench::synthetic_input(args.duration, config.sample_rate()).save_to_file(path)?;
+    }
+    let input = args.input.clone().or_else(|| synthetic.clone()).expect("input or synthetic file");
+    let cases = bench::cases(&args.workers, &args.threads, &args.segment_sizes);
+    console.line(format_args!("Benchmarking {} settings on {} ({} timed runs each)", cases.len(),
+        args.input.as_deref().map_or("synthetic input".into(), |p| p.display().to_string()), args.repeat.max(1)));
+    console.line(format_args!("{:>7} {:>7} {:>8} {:>9} {:>8} {:>10} {:>12}", "workers", "threads", "segment", "wall (s)", "RTF", "audio x", "segment ms"));
+
+    let results = bench::sweep(&config, &input, &cases, args.repeat, |r| {
+        if args.output_format == OutputFormat::Jsonl && let Ok(json) = to_json(r, false) {
+            println!("{}", json);
+        }
+        console.line(format_args!("{:>7} {:>7} {:>8} {:>9.3} {:>8.4} {:>10.1} {:>12.1}", r.case.workers, r.case.threads_per_worker,
+            r.case.segment_size, r.wall_secs, r.rtf, r.throughput, r.segment_latency_ms));
+    });
+    if let Some(path) = &synthetic {
+        let _ = std::fs::remove_file(path);
+    }
+    let report = results?;
+    for skipped in &report.skipped {
+        console.line(format_args!("{:>7} {:>7} {:>8}  skipped: {}", skipped.case.workers, skipped.case.threads_per_worker,
+            skipped.case.segment_size, skipped.reason));
+    }
+    if args.output_format == OutputFormat::Json {
+        println!("{}", to_json(&report, true)?);
+    }
+
+    let best = bench::best(&report.results).ok_or_else(|| ZipEnhancerError::config("No settings to benchmark"))?;
+    console.line(format_args!("\nFastest: --parallel-workers {} --inference-threads {} --segment-size {} (RTF {:.4})",
+        best.case.workers, best.case.workers * best.case.threads_per_worker, best.case.segment_size, best.rtf));
+    if let Some(path) = &args.write_config {
+        best.case.apply(&config).save_to_file(path)?;
+        console.line(format_args!("Wrote {}", path.display()));
+    }
+    Ok(())
+}
+
+fn run_generate(args: GenerateArgs) -> Result<()> {
+    let spec = zipenhancer::synth::GenerateSpec {
+        signals: args.signals,
+        noises: args.noises,
+        snrs_db: args.snr_db,
+        duration_secs: args.duration,
+        sample_rate: args.sample_rate,
+        seed: args.seed,
+    };
+    let manifest = zipenhancer::synth::write_pairs(&spec, &args.out_dir)?;
+    for entry in &manifest.entries {
+        println!("{}", args.out_dir.join(&entry.noisy).display());
+    }
+    println!("Wrote {} clean/noisy pairs; manifest: {}", manifest.entries.len(),
+        args.out_dir.join(zipenhancer::synth::MANIFEST_FILE).display());
+    Ok(())
+}
+
+fn run_regress(args: RegressArgs) -> Result<i32> {
+    use zipenhancer::regression::{self, CaseStatus, Tolerance};
+    let tolerance = Tolerance { max_abs_error: args.max_abs_error, min_snr_db: args.min_snr_db };
+    let reports = regression::run(&args.golden_dir, &tolerance, args.bless)?;
+    for report in &reports {
+        let golden = report.golden.map_or_else(String::new, |d| format!("golden: {}; ", d));
+        let parity = if report.parity.is_identical() { "serial = parallel".to_string() } else { format!("serial != parallel ({})", report.parity) };
+        println!("{:<8} {:<20} {}{}", report.status, report.name, golden, parity);
+    }
+    let failed = reports.iter().filter(|r| matches!(r.status, CaseStatus::Fail | CaseStatus::Missing)).count();
+    if args.bless {
+        println!("Blessed {} goldens in {}", reports.len(), args.golden_dir.display());
+    } else if failed > 0 {
+        println!("{} of {} cases did not pass; if the change is intended, rerun with --bless", failed, reports.len());
+        return Ok(exit_code::FAILURE);
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+fn run_info(args: InfoArgs) -> Result<()> {
+    let mut failure = None;
+    for path in &args.files {
+        match zipenhancer::audio::AudioHeader::from_file(path) {
+            Ok(header) => println!("{}: {} Hz, {} ch, {}-bit {:?}, {:.2}s ({} samples)", path.display(), header.sample_rate,
+                header.channels, header.bits_per_sample, header.format, header.duration, header.total_samples),
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e.report());
+                failure = Some(e);
+            }
+        }
+    }
+
+    let config = resolve_layers(&args.source)?.config;
+    let model = args.model.unwrap_or_else(|| config.model_path().clone());
+    let uses_model = config.denoiser.backend.uses_model();
+    println!("Backend: {}", if uses_model { "onnx" } else { "spectral gate (no model)" });
+    match std::fs::metadata(&model) {
+        Ok(meta) => println!("Model: {} ({:.1} MB)", model.display(), meta.len() as f64 / 1_048_576.0),
+        Err(_) if uses_model => {
+            eprintln!("Model: {} (missing)", model.display());
+            failure = Some(ZipEnhancerError::ModelNotFound { path: model.clone() });
+        }
+        Err(_) => println!("Model: {} (missing, not needed)", model.display()),
+    }
+    failure.map_or(Ok(()), Err)
+}
+
+fn run_selftest(args: SelftestArgs) -> Result<()> {
+    println!("=== Self Test ===");
+    let mut failure = None;
+    let mut check = |name: &str, result: Result<String>| match result {
+        Ok(detail) => println!("✅ {}: {}", name, detail),
+        Err(e) => {
+            eprintln!("❌ {}: {}", name, e.report());
+            failure.get_or_insert(e);
+        }
+    };
+
+    let config = match resolve_layers(&args.source).and_then(|r| r.config.validate().map(|_| r.config)) {
+        Ok(config) => {
+            check("Config", Ok("valid".to_string()));
+            config
+        }
+        Err(e) => {
+            check("Config", Err(e));
+            Default::default()
+        }
+    };
+    check("CPU cores", Ok(zipenhancer::config::utils::cpu_count().to_string()));
+
+    if config.denoiser.backend.uses_model() {
+        check("ONNX Runtime", check_and_setup_onnx_library(args.onnx_lib.as_deref(), args.verbose).map(|_| "found".to_string()));
+        let model = config.model_path();
+        check("Model", if model.exists() {
+            Ok(model.display().to_string())
+        } else {
+            Err(ZipEnhancerError::ModelNotFound { path: model.clone() })
+        });
+    }
+    check("Spectral gate", spectral_gate_check(&config));
+
+    match failure {
+        None => {
+            println!("✅ Ready for processing");
+            Ok(())
+        }
+        Some(e) => Err(e),
+    }
+}
+
+/// Denoise half a second of noise followed by a noisy tone; the noise-only half must get quieter
+fn spectral_gate_check(config: &zipenhancer::Config) -> Result<String> {
+    let signal = ndarray::Array1::from_iter((0..16000).map(|i| {
+        let noise = ((i as u32).wrapping_mul(2_654_435_761) >> 16) as f32 / 65536.0 - 0.5;
+        0.05 * noise + if i >= 8000 { 0.3 * (i as f32 * 0.12).sin() } else { 0.0 }
+    }));
+    let gate = zipenhancer::processing::SpectralGate::fit(&signal, &config.denoiser, config.denoiser.reduction_db)?;
+    let output = gate.process(&signal);
+    let noise_energy = |x: &ndarray::Array1<f32>| x.iter().take(8000).map(|v| v * v).sum::<f32>();
+    let removed_db = 10.0 * (noise_energy(&signal) / noise_energy(&output).max(1e-12)).log10();
+    if output.iter().all(|v| v.is_finite()) && removed_db > 1.0 {
+        Ok(format!("{:.1} dB noise removed", removed_db))
+    } else {
+        Err(ZipEnhancerError::processing("Spectral gate produced invalid output"))
+    }
+}
+
+fn check_and_setup_onnx_library(onnx_lib: Option<&Path>, verbose: bool) -> Result<()> {
+    if let Some(lib_path) = onnx_lib {
+        if !lib_path.exists() {
+            return Err(ZipEnhancerError::onnx(format!(
+                "ONNX Runtime library not found: {}", lib_path.display()
+            )));
+        }
+        unsafe {
+            std::env::set_var("LD_LIBRARY_PATH", format!("{}:{}",
+                lib_path.parent().unwrap_or(Path::new(".")).display(),
+                std::env::var("LD_LIBRARY_PATH").unwrap_or_default()
+            ));
+        }
+        if verbose { eprintln!("Using ONNX Runtime: {}", lib_path.display()); }
+        return Ok(());
+    }
+
+    let paths = [
+        "/opt/homebrew/lib/libonnxruntime.dylib",
+        "/usr/local/lib/libonnxruntime.dylib",
+        "lib/libonnxruntime.1.24.0.dylib",
+        "lib/onnxruntime_sdk/lib/libonnxruntime.dylib",
+    ];
+
+    for path in &paths {
+        if Path::new(path).exists() {
+            unsafe {
+                std::env::set_var("LD_LIBRARY_PATH", format!("{}:{}",
+                    Path::new(path).parent().unwrap_or(Path::new(".")).display(),
+                    std::env::var("LD_LIBRARY_PATH").unwrap_or_default()
+                ));
+            }
+            if verbose { eprintln!("Found ONNX Runtime: {}", path); }
+            return Ok(());
+        }
+    }
+
+    Err(ZipEnhancerError::onnx("ONNX Runtime not found. Use --onnx-lib to specify path."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch_args(args: &[&str]) -> EnhanceArgs {
+        let args = ["zipenhancer", "--output-dir", "out"].iter().chain(args);
+        match Args::try_parse_from(args).unwrap().into_command() {
+            Command::Enhance(args) => *args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_same_named_inputs_are_rejected() {
+        let config = zipenhancer::Config::default();
+        let err = enhance_jobs(&batch_args(&["-i", "a/x.wav", "-i", "b/x.wav"]), &config).unwrap_err();
+        assert_eq!(err.message(), "a/x.wav and b/x.wav would both be written to out/x.wav");
+
+        // Distinct outputs whose result records would share a name
+        let args = batch_args(&["-i", "x.wav", "-i", "x.flac", "--result-json", "results"]);
+        let err = enhance_jobs(&args, &config).unwrap_err();
+        assert!(err.message().ends_with("would both be written to results/x.json"));
+
+        let jobs = enhance_jobs(&batch_args(&["-i", "a/x.wav", "-i", "b/y.wav"]), &config).unwrap();
+        assert_eq!(jobs[1], (PathBuf::from("b/y.wav"), PathBuf::from("out/y.wav")));
+    }
+
+    #[test]
+    fn test_output_over_input_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("x.wav");
+        std::fs::write(&input, b"").unwrap();
+        let jobs = [(input.clone(), dir.path().join(".").join("x.wav"))];
+        assert_eq!(check_job_paths(&jobs, None).unwrap_err().code(), ErrorCode::Config);
+    }
+}
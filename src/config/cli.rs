@@ -0,0 +1,450 @@
+//! Command line interface: one subcommand per task, each with its own options
+
+use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use crate::error::Result;
+use crate::processing::loudness::NormalizationPolicy;
+use crate::processing::mix::MixRange;
+use crate::processing::spectral_gate::DenoiseBackend;
+use crate::processing::vad::SilenceMode;
+use crate::synth::{NoiseKind, SignalKind};
+use super::layered::FlagOverride;
+
+#[derive(Debug, Clone, Parser)]
+#[command(name = "zipenhancer", about = "Audio Denoise Processor", version, author,
+    args_conflicts_with_subcommands = true, subcommand_negates_reqs = true,
+    after_help = "Without a subcommand the options of `enhance` apply.\n\
+        Exit codes: 0 success, 1 processing failure, 2 invalid arguments or configuration,\n\
+        3 model or runtime failure, 4 unreadable input audio, 5 partial batch failure, 130 interrupted.")]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub enhance: EnhanceArgs,
+}
+
+impl Args {
+    /// The subcommand to run; a bare invocation is `enhance`
+    pub fn into_command(self) -> Command {
+        self.command.unwrap_or_else(|| Command::Enhance(Box::new(self.enhance)))
+    }
+}
+
+/// Subcommands
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Denoise an audio file (default without a subcommand)
+    Enhance(Box<EnhanceArgs>),
+    /// Show WAV file headers and check the configured model
+    Info(InfoArgs),
+    /// Check configuration, ONNX Runtime, model and DSP without processing files
+    Selftest(SelftestArgs),
+    /// Create, inspect and check the layered configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Score enhanced audio against clean references
+    Evaluate(EvaluateArgs),
+    /// Render spectrogram and waveform PNGs of input, output and residual
+    Visualize(VisualizeArgs),
+    /// Build an HTML QA report from per-file result records
+    Report(ReportArgs),
+    /// Sweep workers, threads and segment sizes to find the fastest settings
+    Bench(BenchArgs),
+    /// Write seeded synthetic clean/noisy WAV pairs and a manifest
+    Generate(GenerateArgs),
+    /// Compare pipeline outputs on fixed inputs with stored golden files
+    Regress(RegressArgs),
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct EnhanceArgs {
+    #[command(flatten)]
+    pub source: ConfigSourceArgs,
+
+    #[arg(short = 'm', long = "model", help = "ONNX model file path [default: ./model/ZipEnhancer_ONNX/ZipEnhancer.onnx]")]
+    pub model: Option<PathBuf>,
+
+    #[arg(short = 'i', long = "input", num_args = 1.., help = "Input WAV file(s); several files need --output-dir. Required unless input_path is configured")]
+    pub input: Vec<PathBuf>,
+
+    #[arg(short = 'o', long = "output", help = "Output audio file path [default: output.wav]")]
+    pub output: Option<PathBuf>,
+
+    #[arg(long = "output-dir", conflicts_with = "output", help = "Write each output to this directory under its input file name")]
+    pub output_dir: Option<PathBuf>,
+
+    #[arg(long = "output-format", default_value = "text", help = "Result output: text, json (one array) or jsonl (one record per file)")]
+    pub output_format: OutputFormat,
+
+    #[arg(long = "residual", help = "Also write the removed component (input minus enhanced) to this path")]
+    pub residual: Option<PathBuf>,
+
+    #[arg(short = 'r', long = "sample-rate", help = "Audio sample rate (Hz) [default: 16000]")]
+    pub sample_rate: Option<u32>,
+
+    #[arg(short = 'l', long = "overlap", help = "Inter-segment overlap ratio (0.0 - 1.0) [default: 0.1]")]
+    pub overlap: Option<f32>,
+
+    #[arg(short = 'v', long = "verbose", help = "Enable verbose output mode")]
+    pub verbose: bool,
+
+    #[arg(short = 's', long = "segment-size", help = "Audio segment size (number of samples) [default: 16000]")]
+    pub segment_size: Option<usize>,
+
+    #[arg(long = "max-retries", help = "ONNX inference max retry count [default: 3]")]
+    pub max_retries: Option<u32>,
+
+    #[arg(long = "disable-performance-monitoring", help = "Disable performance monitoring")]
+    pub disable_performance_monitoring: bool,
+
+    #[arg(long = "inference-threads", help = "ONNX inference thread count [default: 4]")]
+    pub inference_threads: Option<usize>,
+
+    #[arg(long = "onnx-lib", help = "ONNX Runtime library file path")]
+    pub onnx_lib: Option<PathBuf>,
+
+    #[arg(long = "parallel-workers", help = "Number of parallel ONNX sessions (default: 4)")]
+    pub parallel_workers: Option<usize>,
+
+    #[arg(long = "max-sessions", help = "Maximum ONNX sessions kept per model, in use or idle (default: 16)")]
+    pub max_sessions: Option<usize>,
+
+    #[arg(long = "session-idle-timeout", value_name = "SECS", help = "Close ONNX sessions idle for this long (default: 300)")]
+    pub session_idle_timeout: Option<u64>,
+
+    #[arg(long = "serial", help = "Use serial processing (single session, multi-thread)")]
+    pub serial: bool,
+
+    #[arg(long = "normalize", help = "Output normalization policy (none, peak, rms, loudness)")]
+    pub normalize: Option<NormalizationPolicy>,
+
+    #[arg(long = "target-level", allow_hyphen_values = true, help = "Normalization target (dBFS for peak/rms, LUFS for loudness)")]
+    pub target_level: Option<f32>,
+
+    #[arg(long = "agc", overrides_with = "no_agc", help = "Enable smoothed segment-level automatic gain control")]
+    pub agc: bool,
+
+    #[arg(long = "no-agc", help = "Disable automatic gain control even if the config enables it")]
+    pub no_agc: bool,
+
+    #[arg(long = "strength", help = "Enhancement strength (0.0 = original, 1.0 = fully enhanced)")]
+    pub strength: Option<f32>,
+
+    #[arg(long = "atten-limit-db", help = "Maximum noise attenuation in dB")]
+    pub atten_limit_db: Option<f32>,
+
+    #[arg(long = "mix-range", help = "Strength for a time range as START:END:STRENGTH (repeatable)")]
+    pub mix_ranges: Vec<MixRange>,
+
+    #[arg(long = "vad", overrides_with = "no_vad", help = "Enable voice activity detection")]
+    pub vad: bool,
+
+    #[arg(long = "no-vad", conflicts_with_all = ["vad_mode", "vad_export"], help = "Disable voice activity detection even if the config enables it")]
+    pub no_vad: bool,
+
+    #[arg(long = "vad-mode", help = "Handling of non-speech segments (infer, attenuate, passthrough); implies --vad")]
+    pub vad_mode: Option<SilenceMode>,
+
+    #[arg(long = "vad-export", help = "Write detected speech regions (.rttm for RTTM, JSON otherwise); implies --vad")]
+    pub vad_export: Option<PathBuf>,
+
+    #[arg(long = "backend", help = "Denoising backend (onnx, spectral); spectral needs no model")]
+    pub backend: Option<DenoiseBackend>,
+
+    #[arg(long = "post-filter", overrides_with = "no_post_filter", help = "Apply the spectral gate after the model to suppress residual noise")]
+    pub post_filter: bool,
+
+    #[arg(long = "no-post-filter", help = "Skip the post-filter even if the config enables it")]
+    pub no_post_filter: bool,
+
+    #[arg(long = "result-json", help = "Write the per-file result record (JSON) for the QA report; a directory for several inputs")]
+    pub result_json: Option<PathBuf>,
+
+    #[arg(long = "no-progress", help = "Do not draw the progress bar (it is only drawn when stderr is a terminal)")]
+    pub no_progress: bool,
+
+    #[arg(long = "metrics-addr", value_name = "ADDR", help = "Serve Prometheus metrics on ADDR (e.g. 127.0.0.1:9898) while running")]
+    pub metrics_addr: Option<String>,
+
+    #[arg(long = "trace-chrome", value_name = "FILE", help = "Write a Chrome trace (JSON) of the decode/resample/segment/infer/reconstruct/encode spans")]
+    pub trace_chrome: Option<PathBuf>,
+}
+
+impl EnhanceArgs {
+    /// Config values set by flags that were actually passed; unset flags leave lower layers alone
+    pub fn explicit_overrides(&self) -> Result<Vec<FlagOverride>> {
+        let mut overrides = Vec::new();
+        macro_rules! set {
+            ($key:literal, $flag:literal, $value:expr) => {
+                overrides.push(FlagOverride::new($key, $flag, $value)?)
+            };
+        }
+        if let Some(model) = &self.model { set!("model.path", "--model", model); }
+        if let [input] = self.input.as_slice() { set!("input_path", "--input", input); }
+        if let Some(output) = &self.output { set!("output_path", "--output", output); }
+        if let Some(residual) = &self.residual { set!("residual_path", "--residual", residual); }
+        if let Some(rate) = self.sample_rate { set!("audio.sample_rate", "--sample-rate", rate); }
+        if let Some(overlap) = self.overlap { set!("audio.overlap_ratio", "--overlap", overlap); }
+        if let Some(size) = self.segment_size { set!("audio.segment_size", "--segment-size", size); }
+        if let Some(retries) = self.max_retries { set!("model.max_retries", "--max-retries", retries); }
+        if let Some(threads) = self.inference_threads { set!("model.inference_threads", "--inference-threads", threads); }
+        if self.verbose { set!("processing.verbose", "--verbose", true); }
+        if let Some(workers) = self.parallel_workers { set!("processing.parallel_workers", "--parallel-workers", workers); }
+        if let Some(max) = self.max_sessions { set!("sessions.max_sessions", "--max-sessions", max); }
+        if let Some(secs) = self.session_idle_timeout { set!("sessions.idle_timeout_secs", "--session-idle-timeout", secs); }
+        if self.disable_performance_monitoring {
+            set!("processing.enable_performance_monitoring", "--disable-performance-monitoring", false);
+        }
+        if self.agc { set!("processing.enable_agc", "--agc", true); }
+        if self.no_agc { set!("processing.enable_agc", "--no-agc", false); }
+        if let Some(policy) = self.normalize { set!("normalization.policy", "--normalize", policy); }
+        if let Some(target) = self.target_level { set!("normalization.target_db", "--target-level", target); }
+        if let Some(strength) = self.strength { set!("mix.strength", "--strength", strength); }
+        if let Some(limit) = self.atten_limit_db { set!("mix.attenuation_limit_db", "--atten-limit-db", limit); }
+        if !self.mix_ranges.is_empty() { set!("mix.ranges", "--mix-range", &self.mix_ranges); }
+        if self.vad { set!("vad.enabled", "--vad", true); }
+        if self.no_vad { set!("vad.enabled", "--no-vad", false); }
+        if let Some(mode) = self.vad_mode {
+            set!("vad.enabled", "--vad-mode", true);
+            set!("vad.silence_mode", "--vad-mode", mode);
+        }
+        if let Some(path) = &self.vad_export {
+            set!("vad.enabled", "--vad-export", true);
+            set!("vad.export_path", "--vad-export", path);
+        }
+        if let Some(backend) = self.backend { set!("denoiser.backend", "--backend", backend); }
+        if self.post_filter { set!("denoiser.post_filter", "--post-filter", true); }
+        if self.no_post_filter { set!("denoiser.post_filter", "--no-post-filter", false); }
+        Ok(overrides)
+    }
+}
+
+/// How `enhance` reports results on stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Human-readable summary
+    #[default]
+    Text,
+    /// One JSON array of per-file records after the run
+    Json,
+    /// One compact JSON record per line as each file finishes
+    Jsonl,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            _ => Err(format!("Unknown output format: {} (expected text|json|jsonl)", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct InfoArgs {
+    #[arg(help = "WAV files to describe")]
+    pub files: Vec<PathBuf>,
+
+    #[command(flatten)]
+    pub source: ConfigSourceArgs,
+
+    #[arg(short = 'm', long = "model", help = "ONNX model file path (overrides the configuration)")]
+    pub model: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct SelftestArgs {
+    #[command(flatten)]
+    pub source: ConfigSourceArgs,
+
+    #[arg(long = "onnx-lib", help = "ONNX Runtime library file path")]
+    pub onnx_lib: Option<PathBuf>,
+
+    #[arg(short = 'v', long = "verbose", help = "Enable verbose output mode")]
+    pub verbose: bool,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigCommand {
+    /// Write a config file with every default value
+    Init(ConfigInitArgs),
+    /// Print the effective configuration
+    Show(ConfigShowArgs),
+    /// Check the layered configuration, including unknown keys
+    Validate(ConfigSourceArgs),
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct ConfigInitArgs {
+    #[arg(default_value = "config.toml", help = "Config file to create")]
+    pub path: PathBuf,
+
+    #[arg(long = "user", conflicts_with = "path", help = "Create the user config ($XDG_CONFIG_HOME/zipenhancer/config.toml)")]
+    pub user: bool,
+
+    #[arg(short = 'f', long = "force", help = "Overwrite an existing file")]
+    pub force: bool,
+}
+
+/// Config file and profile selecting the configuration layers
+#[derive(Debug, Clone, clap::Args)]
+pub struct ConfigSourceArgs {
+    #[arg(short = 'c', long = "config", help = "Project config file (TOML format) [default: ./config.toml if present]")]
+    pub config_file: Option<PathBuf>,
+
+    #[arg(long = "profile", help = "Config profile: built-in preset or [profile.<name>] table")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct ConfigShowArgs {
+    #[command(flatten)]
+    pub source: ConfigSourceArgs,
+
+    #[arg(long = "origin", help = "Print every key with the layer it came from")]
+    pub origin: bool,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct EvaluateArgs {
+    #[arg(short = 'e', long = "enhanced", help = "Enhanced WAV file or directory")]
+    pub enhanced: PathBuf,
+
+    #[arg(short = 'c', long = "clean", help = "Clean reference WAV file or directory (paired by file name)")]
+    pub clean: PathBuf,
+
+    #[arg(short = 'o', long = "output", help = "Write the report to this path (.csv for CSV, JSON otherwise)")]
+    pub output: Option<PathBuf>,
+
+    #[arg(long = "csv", help = "Print CSV instead of JSON to stdout")]
+    pub csv: bool,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct VisualizeArgs {
+    #[arg(short = 'i', long = "input", help = "Original input WAV file")]
+    pub input: PathBuf,
+
+    #[arg(short = 'o', long = "output", help = "Enhanced output WAV file")]
+    pub output: PathBuf,
+
+    #[arg(long = "residual", help = "Residual WAV file (default: input minus output)")]
+    pub residual: Option<PathBuf>,
+
+    #[arg(short = 'd', long = "out-dir", default_value = ".", help = "Directory for the PNG images")]
+    pub out_dir: PathBuf,
+
+    #[arg(long = "fft-size", default_value = "1024", help = "Spectrogram FFT size")]
+    pub fft_size: usize,
+
+    #[arg(long = "hop-size", default_value = "256", help = "Spectrogram hop size")]
+    pub hop_size: usize,
+
+    #[arg(long = "width", default_value = "1000", help = "Image width in pixels (time axis)")]
+    pub width: usize,
+
+    #[arg(long = "height", default_value = "256", help = "Spectrogram height in pixels")]
+    pub height: usize,
+
+    #[arg(long = "min-db", default_value = "-100", allow_hyphen_values = true, help = "Bottom of the dB color scale")]
+    pub min_db: f32,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct ReportArgs {
+    #[arg(required = true, help = "Result JSON files or directories containing them")]
+    pub results: Vec<PathBuf>,
+
+    #[arg(short = 'o', long = "output", default_value = "report.html", help = "HTML report path")]
+    pub output: PathBuf,
+
+    #[arg(long = "no-thumbnails", help = "Skip waveform and spectrogram thumbnails")]
+    pub no_thumbnails: bool,
+}
+
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct BenchArgs {
+    #[command(flatten)]
+    pub source: ConfigSourceArgs,
+
+    #[arg(short = 'i', long = "input", help = "WAV file to process [default: synthetic tone bursts in noise]")]
+    pub input: Option<PathBuf>,
+
+    #[arg(long = "duration", default_value = "30", help = "Length of the synthetic input in seconds")]
+    pub duration: f64,
+
+    #[arg(short = 'm', long = "model", help = "ONNX model file path (overrides the configuration)")]
+    pub model: Option<PathBuf>,
+
+    #[arg(long = "backend", help = "Denoising backend: onnx (model) or spectral (no model)")]
+    pub backend: Option<DenoiseBackend>,
+
+    #[arg(long = "onnx-lib", help = "ONNX Runtime library file path")]
+    pub onnx_lib: Option<PathBuf>,
+
+    #[arg(long = "workers", value_delimiter = ',', default_value = "1,2,4", help = "Parallel worker counts to try")]
+    pub workers: Vec<usize>,
+
+    #[arg(long = "threads", value_delimiter = ',', default_value = "1,2", help = "Inference threads per worker to try")]
+    pub threads: Vec<usize>,
+
+    #[arg(long = "segment-sizes", value_delimiter = ',', default_value = "8000,16000,32000", help = "Segment sizes (samples) to try")]
+    pub segment_sizes: Vec<usize>,
+
+    #[arg(long = "repeat", default_value = "3", help = "Timed runs per setting (the median is reported)")]
+    pub repeat: usize,
+
+    #[arg(long = "write-config", help = "Write the effective configuration with the fastest settings to this file")]
+    pub write_config: Option<PathBuf>,
+
+    #[arg(short = 'f', long = "force", help = "Overwrite an existing --write-config file")]
+    pub force: bool,
+
+    #[arg(long = "output-format", default_value = "text", help = "Result output: text, json (one array) or jsonl (one record per setting)")]
+    pub output_format: OutputFormat,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct GenerateArgs {
+    #[arg(short = 'o', long = "out-dir", default_value = "synthetic", help = "Directory for clean/, noisy/ and manifest.json")]
+    pub out_dir: PathBuf,
+
+    #[arg(long = "signals", value_delimiter = ',', default_value = "speech", help = "Clean signals: speech, tone, sweep")]
+    pub signals: Vec<SignalKind>,
+
+    #[arg(long = "noises", value_delimiter = ',', default_value = "white,pink,babble", help = "Noises: white, pink, brown, babble, hum, clicks")]
+    pub noises: Vec<NoiseKind>,
+
+    #[arg(long = "snr", value_delimiter = ',', allow_hyphen_values = true, default_value = "0,5,10", help = "Mixing SNRs in dB")]
+    pub snr_db: Vec<f32>,
+
+    #[arg(long = "duration", default_value = "5", help = "Length of each file in seconds")]
+    pub duration: f64,
+
+    #[arg(long = "sample-rate", default_value = "16000", help = "Sample rate in Hz")]
+    pub sample_rate: u32,
+
+    #[arg(long = "seed", default_value = "0", help = "Random seed; the same seed gives identical files")]
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct RegressArgs {
+    #[arg(long = "golden-dir", default_value = "tests/golden", help = "Directory of the golden WAV files")]
+    pub golden_dir: PathBuf,
+
+    #[arg(long = "bless", help = "Rewrite the golden files from this run instead of comparing")]
+    pub bless: bool,
+
+    #[arg(long = "max-abs-error", default_value = "0.0001", help = "Largest accepted sample difference from a golden")]
+    pub max_abs_error: f32,
+
+    #[arg(long = "min-snr", default_value = "70", help = "Smallest accepted SNR against a golden (dB)")]
+    pub min_snr_db: f64,
+}
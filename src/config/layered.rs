@@ -0,0 +1,466 @@
+//! Layered configuration with per-key origins.
+//!
+//! Layers apply in order: built-in defaults, system config, user config,
+//! project `config.toml`, the selected profile, `ZIPENHANCER_*` environment
+//! variables, explicit flags.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+use crate::error::{ZipEnhancerError, Result};
+use super::Config;
+use super::profile::{builtin_profile, BUILTIN_PROFILES, PROFILE_ENV};
+
+/// Prefix of configuration environment variables
+pub const ENV_PREFIX: &str = "ZIPENHANCER_";
+/// Project config file picked up from the working directory
+pub const PROJECT_CONFIG: &str = "config.toml";
+const SYSTEM_CONFIG: &str = "/etc/zipenhancer/config.toml";
+
+/// Where an effective configuration value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    Project(PathBuf),
+    Profile(String),
+    Env(String),
+    Flag(&'static str),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::System(path) => write!(f, "system config {}", path.display()),
+            Self::User(path) => write!(f, "user config {}", path.display()),
+            Self::Project(path) => write!(f, "project config {}", path.display()),
+            Self::Profile(name) => write!(f, "profile {}", name),
+            Self::Env(var) => write!(f, "env {}", var),
+            Self::Flag(flag) => write!(f, "flag {}", flag),
+        }
+    }
+}
+
+/// A value set by an explicitly passed command line flag
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlagOverride {
+    /// Dotted config key, e.g. `audio.overlap_ratio`
+    pub key: &'static str,
+    pub flag: &'static str,
+    pub value: Value,
+}
+
+impl FlagOverride {
+    pub fn new<T: serde::Serialize>(key: &'static str, flag: &'static str, value: T) -> Result<Self> {
+        let value = Value::try_from(value)
+            .map_err(|e| ZipEnhancerError::config(format!("Invalid value for {}: {}", flag, e)))?;
+        Ok(Self { key, flag, value })
+    }
+}
+
+/// Config files and environment feeding one resolution
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLayers {
+    pub system: Option<PathBuf>,
+    pub user: Option<PathBuf>,
+    pub project: Option<PathBuf>,
+    /// Profile applied over the files: built-in or `[profile.<name>]`
+    pub profile: Option<String>,
+    pub env: Vec<(String, String)>,
+}
+
+/// Effective configuration and the origin of every key
+#[derive(Debug, Clone)]
+pub struct ResolvedConfig {
+    pub config: Config,
+    pub origins: BTreeMap<String, ConfigSource>,
+    /// Keys no config field reads, with the layer that set them
+    pub unknown_keys: Vec<(String, ConfigSource)>,
+    table: Table,
+}
+
+/// `$XDG_CONFIG_HOME/zipenhancer/config.toml`, falling back to `~/.config`
+pub fn user_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()).map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("zipenhancer").join("config.toml"))
+}
+
+impl ConfigLayers {
+    /// Standard locations and the process environment.
+    ///
+    /// `config_file` (from `--config`) replaces `./config.toml` and must exist;
+    /// the other files are optional. `profile` falls back to `ZIPENHANCER_PROFILE`.
+    pub fn discover(config_file: Option<&Path>, profile: Option<&str>) -> Result<Self> {
+        let project = match config_file {
+            Some(path) if !path.exists() => {
+                return Err(ZipEnhancerError::config(format!("Config file does not exist: {}", path.display())));
+            }
+            Some(path) => Some(path.to_path_buf()),
+            None => Some(PathBuf::from(PROJECT_CONFIG)).filter(|p| p.exists()),
+        };
+        Ok(Self {
+            system: Some(PathBuf::from(SYSTEM_CONFIG)).filter(|p| p.exists()),
+            user: user_config_path().filter(|p| p.exists()),
+            project,
+            profile: profile.map(str::to_string).or_else(|| std::env::var(PROFILE_ENV).ok().filter(|p| !p.is_empty())),
+            env: std::env::vars().filter(|(k, _)| k.starts_with(ENV_PREFIX) && k != PROFILE_ENV).collect(),
+        })
+    }
+
+    /// Merge all layers and `flags` on top of the built-in defaults
+    pub fn resolve(&self, flags: &[FlagOverride]) -> Result<ResolvedConfig> {
+        let mut table = match Value::try_from(Config::default()) {
+            Ok(Value::Table(table)) => table,
+            _ => return Err(ZipEnhancerError::config("Failed to serialize default config")),
+        };
+        let mut origins = BTreeMap::new();
+        mark_leaves(&table, "", &ConfigSource::Default, &mut origins);
+        let mut unknown = Vec::new();
+        let mut profiles = Table::new();
+
+        let files = [
+            (&self.system, ConfigSource::System as fn(PathBuf) -> ConfigSource),
+            (&self.user, ConfigSource::User),
+            (&self.project, ConfigSource::Project),
+        ];
+        for (path, source) in files {
+            if let Some(path) = path {
+                let source = source(path.clone());
+                let mut layer = read_table(path)?;
+                let file_profiles = match layer.remove("profile") {
+                    Some(Value::Table(named)) => named,
+                    Some(_) => return Err(ZipEnhancerError::config(format!(
+                        "`profile` must be a table of named profiles in {}", path.display()))),
+                    None => Table::new(),
+                };
+                unknown.extend(unknown_keys(&layer).into_iter().map(|k| (k, source.clone())));
+                for (name, overrides) in &file_profiles {
+                    let Value::Table(overrides) = overrides else {
+                        return Err(ZipEnhancerError::config(format!("Profile `{}` must be a table in {}", name, path.display())));
+                    };
+                    unknown.extend(unknown_keys(overrides).into_iter()
+                        .map(|k| (format!("profile.{}.{}", name, k), source.clone())));
+                }
+                merge(&mut table, &layer, "", &source, &mut origins);
+                merge(&mut profiles, &file_profiles, "", &source, &mut BTreeMap::new());
+            }
+        }
+
+        if let Some(name) = &self.profile {
+            let mut overrides = builtin_profile(name).unwrap_or_default();
+            match profiles.get(name) {
+                Some(Value::Table(from_files)) => merge(&mut overrides, from_files, "", &ConfigSource::Default, &mut BTreeMap::new()),
+                _ if overrides.is_empty() => {
+                    let mut available: BTreeSet<&str> = BUILTIN_PROFILES.iter().map(|(n, _, _)| *n).collect();
+                    available.extend(profiles.keys().map(String::as_str));
+                    return Err(ZipEnhancerError::config(format!("Unknown profile `{}` (available: {})",
+                        name, available.into_iter().collect::<Vec<_>>().join(", "))));
+                }
+                _ => {}
+            }
+            merge(&mut table, &overrides, "", &ConfigSource::Profile(name.clone()), &mut origins);
+        }
+
+        let unused_env = self.apply_env(&mut table, &mut origins)?;
+        unknown.extend(unused_env.into_iter().map(|name| (name.clone(), ConfigSource::Env(name))));
+
+        for flag in flags {
+            set_key(&mut table, flag.key, flag.value.clone());
+            origins.insert(flag.key.to_string(), ConfigSource::Flag(flag.flag));
+        }
+
+        let config = Value::Table(table.clone()).try_into()
+            .map_err(|e| ZipEnhancerError::config(format!("Invalid configuration: {}", e)))?;
+        Ok(ResolvedConfig { config, origins, unknown_keys: unknown, table })
+    }
+
+    /// Each key a config file may set, optional ones included, can be set as
+    /// `ZIPENHANCER_<SECTION>_<KEY>`; keys whose name is unique across sections also
+    /// accept the short `ZIPENHANCER_<KEY>`. Returns the variables that match no key.
+    fn apply_env(&self, table: &mut Table, origins: &mut BTreeMap<String, ConfigSource>) -> Result<Vec<String>> {
+        let env: BTreeMap<&str, &str> = self.env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        let schema = schema();
+        let mut schema_keys = BTreeMap::new();
+        mark_leaves(&schema, "", &ConfigSource::Default, &mut schema_keys);
+        let keys: Vec<String> = schema_keys.into_keys().collect();
+        let mut leaf_counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for key in &keys {
+            *leaf_counts.entry(leaf_name(key)).or_default() += 1;
+        }
+
+        let mut used = BTreeSet::new();
+        for key in &keys {
+            let mut names = vec![env_var_name(key)];
+            if key.contains('.') && leaf_counts[leaf_name(key)] == 1 {
+                names.push(env_var_name(leaf_name(key)));
+            }
+            used.extend(names.iter().filter(|n| env.contains_key(n.as_str())).cloned());
+            // The full name wins over the short alias
+            let Some(name) = names.into_iter().find(|n| env.contains_key(n.as_str())) else { continue };
+            let raw = env[name.as_str()];
+            // Typed by the schema, since unset optional keys are missing from `table`
+            let current = get_key(&schema, key).expect("keys come from the schema");
+            let value = parse_env_value(raw, current)
+                .ok_or_else(|| ZipEnhancerError::config(format!("Invalid value for {}: {:?}", name, raw)))?;
+            set_key(table, key, value);
+            origins.insert(key.clone(), ConfigSource::Env(name));
+        }
+
+        Ok(self.env.iter().map(|(name, _)| name).filter(|name| !used.contains(*name)).cloned().collect())
+    }
+}
+
+impl ResolvedConfig {
+    /// `key = value  # origin` lines for every key, sorted by key
+    pub fn format_origins(&self) -> String {
+        let lines: Vec<(String, &ConfigSource)> = self.origins.iter()
+            .filter_map(|(key, source)| get_key(&self.table, key).map(|v| (format!("{} = {}", key, format_value(v)), source)))
+            .collect();
+        let width = lines.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+        lines.iter().map(|(line, source)| format!("{:<width$}  # {}\n", line, source, width = width)).collect()
+    }
+}
+
+/// Every key a config file may set, with optional fields present
+fn schema() -> Table {
+    let mut config = Config { residual_path: Some(PathBuf::new()), ..Default::default() };
+    config.mix.attenuation_limit_db = Some(0.0);
+    config.vad.export_path = Some(PathBuf::new());
+    match Value::try_from(config) {
+        Ok(Value::Table(table)) => table,
+        _ => unreachable!("config serializes to a table"),
+    }
+}
+
+/// Dotted keys in `table` that no config field reads
+pub fn unknown_keys(table: &Table) -> Vec<String> {
+    fn walk(table: &Table, schema: &Table, prefix: &str, found: &mut Vec<String>) {
+        for (key, value) in table {
+            let path = join_key(prefix, key);
+            match (schema.get(key), value) {
+                (None, _) => found.push(path),
+                (Some(Value::Table(inner)), Value::Table(sub)) => walk(sub, inner, &path, found),
+                _ => {}
+            }
+        }
+    }
+    let mut found = Vec::new();
+    walk(table, &schema(), "", &mut found);
+    found
+}
+
+fn read_table(path: &Path) -> Result<Table> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| ZipEnhancerError::io_at(path, e))?;
+    content.parse::<Table>()
+        .map_err(|e| ZipEnhancerError::config(format!("Failed to parse config file {}: {}", path.display(), e)))
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) }
+}
+
+fn leaf_name(key: &str) -> &str {
+    key.rsplit('.').next().unwrap_or(key)
+}
+
+fn env_var_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_ascii_uppercase())
+}
+
+fn mark_leaves(table: &Table, prefix: &str, source: &ConfigSource, origins: &mut BTreeMap<String, ConfigSource>) {
+    for (key, value) in table {
+        let path = join_key(prefix, key);
+        match value {
+            Value::Table(inner) => mark_leaves(inner, &path, source, origins),
+            _ => { origins.insert(path, source.clone()); }
+        }
+    }
+}
+
+fn merge(base: &mut Table, layer: &Table, prefix: &str, source: &ConfigSource, origins: &mut BTreeMap<String, ConfigSource>) {
+    for (key, value) in layer {
+        let path = join_key(prefix, key);
+        match (base.get_mut(key), value) {
+            (Some(Value::Table(inner)), Value::Table(overlay)) => merge(inner, overlay, &path, source, origins),
+            _ => {
+                match value {
+                    Value::Table(inner) => mark_leaves(inner, &path, source, origins),
+                    _ => { origins.insert(path, source.clone()); }
+                }
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+fn get_key<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    let (section, rest) = match key.split_once('.') {
+        Some(split) => split,
+        None => return table.get(key),
+    };
+    match table.get(section)? {
+        Value::Table(inner) => get_key(inner, rest),
+        _ => None,
+    }
+}
+
+fn set_key(table: &mut Table, key: &str, value: Value) {
+    match key.split_once('.') {
+        Some((section, rest)) => {
+            let entry = table.entry(section).or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            if let Value::Table(inner) = entry {
+                set_key(inner, rest, value);
+            }
+        }
+        None => { table.insert(key.to_string(), value); }
+    }
+}
+
+/// Parse `raw` as the type of the value it replaces; arrays and tables use TOML syntax
+fn parse_env_value(raw: &str, current: &Value) -> Option<Value> {
+    match current {
+        Value::String(_) => Some(Value::String(raw.to_string())),
+        Value::Boolean(_) => match raw.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Some(Value::Boolean(true)),
+            "0" | "false" | "no" | "off" => Some(Value::Boolean(false)),
+            _ => None,
+        },
+        Value::Integer(_) => raw.trim().parse().ok().map(Value::Integer),
+        Value::Float(_) => raw.trim().parse().ok().map(Value::Float),
+        _ => format!("value = {}", raw).parse::<Table>().ok()?.remove("value"),
+    }
+}
+
+/// Config floats are `f32`; print them at that precision
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Float(v) => format!("{:?}", *v as f32),
+        Value::Array(items) => format!("[{}]", items.iter().map(format_value).collect::<Vec<_>>().join(", ")),
+        Value::Table(table) => format!("{{ {} }}", table.iter()
+            .map(|(k, v)| format!("{} = {}", k, format_value(v))).collect::<Vec<_>>().join(", ")),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use crate::config::Args;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_files_layer_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let layers = ConfigLayers {
+            user: Some(write(dir.path(), "user.toml", "[audio]\noverlap_ratio = 0.2\nsegment_size = 8000\n")),
+            project: Some(write(dir.path(), "project.toml", "input_path = \"in.wav\"\n[audio]\noverlap_ratio = 0.3\n")),
+            ..Default::default()
+        };
+        let resolved = layers.resolve(&[]).unwrap();
+        assert_eq!(resolved.config.audio.overlap_ratio, 0.3);
+        assert_eq!(resolved.config.audio.segment_size, 8000);
+        assert_eq!(resolved.config.input_path, PathBuf::from("in.wav"));
+        assert_eq!(resolved.config.audio.sample_rate, 16000);
+        assert!(matches!(resolved.origins["audio.segment_size"], ConfigSource::User(_)));
+        assert!(matches!(resolved.origins["audio.overlap_ratio"], ConfigSource::Project(_)));
+        assert_eq!(resolved.origins["audio.sample_rate"], ConfigSource::Default);
+    }
+
+    #[test]
+    fn test_env_layer() {
+        let env = |pairs: &[(&str, &str)]| ConfigLayers {
+            env: pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            ..Default::default()
+        };
+        let resolved = env(&[("ZIPENHANCER_MODEL_PATH", "m.onnx"), ("ZIPENHANCER_SAMPLE_RATE", "48000"),
+            ("ZIPENHANCER_VERBOSE", "yes"), ("ZIPENHANCER_MIX_RANGES", "[{ start_secs = 1.0, end_secs = 2.0, strength = 0.5 }]")])
+            .resolve(&[]).unwrap();
+        assert_eq!(resolved.config.model.path, PathBuf::from("m.onnx"));
+        assert_eq!(resolved.config.audio.sample_rate, 48000);
+        assert!(resolved.config.processing.verbose);
+        assert_eq!(resolved.config.mix.ranges.len(), 1);
+        assert_eq!(resolved.origins["audio.sample_rate"], ConfigSource::Env("ZIPENHANCER_SAMPLE_RATE".into()));
+
+        assert!(env(&[("ZIPENHANCER_AUDIO_SEGMENT_SIZE", "lots")]).resolve(&[]).is_err());
+
+        // Optional keys, absent from the defaults, are accepted as well
+        let resolved = env(&[("ZIPENHANCER_RESIDUAL_PATH", "noise.wav"), ("ZIPENHANCER_ATTENUATION_LIMIT_DB", "12"),
+            ("ZIPENHANCER_VAD_EXPORT_PATH", "speech.rttm")]).resolve(&[]).unwrap();
+        assert!(resolved.unknown_keys.is_empty(), "{:?}", resolved.unknown_keys);
+        assert_eq!(resolved.config.residual_path, Some(PathBuf::from("noise.wav")));
+        assert_eq!(resolved.config.mix.attenuation_limit_db, Some(12.0));
+        assert_eq!(resolved.config.vad.export_path, Some(PathBuf::from("speech.rttm")));
+        assert_eq!(resolved.origins["residual_path"], ConfigSource::Env("ZIPENHANCER_RESIDUAL_PATH".into()));
+    }
+
+    #[test]
+    fn test_flags_override_only_when_passed() {
+        let dir = tempfile::tempdir().unwrap();
+        let layers = ConfigLayers {
+            project: Some(write(dir.path(), "config.toml", "[audio]\noverlap_ratio = 0.3\nsegment_size = 8000\n[processing]\nverbose = true\n")),
+            env: vec![("ZIPENHANCER_AUDIO_SEGMENT_SIZE".into(), "4000".into())],
+            ..Default::default()
+        };
+        let args = Args::parse_from(["zipenhancer", "-i", "a.wav", "-s", "2000"]);
+        let resolved = layers.resolve(&args.enhance.explicit_overrides().unwrap()).unwrap();
+        assert_eq!(resolved.config.audio.overlap_ratio, 0.3);
+        assert_eq!(resolved.config.audio.segment_size, 2000);
+        assert!(resolved.config.processing.verbose);
+        assert_eq!(resolved.config.input_path, PathBuf::from("a.wav"));
+        assert_eq!(resolved.origins["audio.segment_size"], ConfigSource::Flag("--segment-size"));
+
+        // Switches enabled by the file or environment can be turned off again
+        let layers = ConfigLayers {
+            project: Some(write(dir.path(), "config.toml", "[processing]\nenable_agc = true\n[denoiser]\npost_filter = true\n")),
+            env: vec![("ZIPENHANCER_VAD_ENABLED".into(), "true".into())],
+            ..Default::default()
+        };
+        let args = Args::parse_from(["zipenhancer", "-i", "a.wav", "--agc", "--no-agc", "--no-vad", "--no-post-filter"]);
+        let resolved = layers.resolve(&args.enhance.explicit_overrides().unwrap()).unwrap();
+        assert!(!resolved.config.processing.enable_agc && !resolved.config.vad.enabled && !resolved.config.denoiser.post_filter);
+        assert_eq!(resolved.origins["vad.enabled"], ConfigSource::Flag("--no-vad"));
+        let args = Args::parse_from(["zipenhancer", "-i", "a.wav", "--no-agc", "--agc"]);
+        assert!(layers.resolve(&args.enhance.explicit_overrides().unwrap()).unwrap().config.processing.enable_agc);
+    }
+
+    #[test]
+    fn test_profiles_and_unknown_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = write(dir.path(), "config.toml", "verbos = true\n[audio]\nsegment_size = 8000\n\
+            [profile.podcast.mix]\nstrength = 0.5\n[profile.mine.audio]\nsegmnet_size = 1\n");
+        let layers = ConfigLayers { project: Some(project), profile: Some("podcast".into()), ..Default::default() };
+        let resolved = layers.resolve(&[]).unwrap();
+        // Built-in preset, then the file's table of the same name
+        assert_eq!(resolved.config.audio.segment_size, 32000);
+        assert_eq!(resolved.config.mix.strength, 0.5);
+        assert_eq!(resolved.origins["mix.strength"], ConfigSource::Profile("podcast".into()));
+        let unknown: Vec<&str> = resolved.unknown_keys.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(unknown, ["verbos", "profile.mine.audio.segmnet_size"]);
+
+        let missing = ConfigLayers { profile: Some("nope".into()), ..layers };
+        assert!(missing.resolve(&[]).unwrap_err().to_string().contains("mine"));
+    }
+
+    #[test]
+    fn test_format_origins() {
+        let layers = ConfigLayers { env: vec![("ZIPENHANCER_AUDIO_OVERLAP_RATIO".into(), "0.2".into())], ..Default::default() };
+        let text = layers.resolve(&[]).unwrap().format_origins();
+        let line = text.lines().find(|l| l.starts_with("audio.overlap_ratio")).unwrap();
+        assert!(line.starts_with("audio.overlap_ratio = 0.2 ") && line.ends_with("# env ZIPENHANCER_AUDIO_OVERLAP_RATIO"), "{}", line);
+        assert!(text.contains("# default"));
+    }
+}
@@ -0,0 +1,586 @@
+//! Configuration management for audio processing
+
+pub mod cli;
+pub mod layered;
+pub mod profile;
+
+use crate::error::{ZipEnhancerError, Result};
+use crate::onnx::PoolLimits;
+use crate::processing::alignment::DelayMode;
+use crate::processing::loudness::NormalizationPolicy;
+use crate::processing::mix::MixRange;
+use crate::processing::spectral_gate::DenoiseBackend;
+use crate::processing::vad::SilenceMode;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub use cli::{Args, BenchArgs, Command, ConfigCommand, ConfigInitArgs, ConfigShowArgs, ConfigSourceArgs, EnhanceArgs,
+    EvaluateArgs, GenerateArgs, InfoArgs, OutputFormat, RegressArgs, ReportArgs, SelftestArgs, VisualizeArgs};
+pub use layered::{ConfigLayers, ConfigSource, FlagOverride, ResolvedConfig};
+pub use profile::BUILTIN_PROFILES;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub model: ModelConfig,
+    pub audio: AudioConfig,
+    pub processing: ProcessingConfig,
+    #[serde(default)]
+    pub sessions: SessionsConfig,
+    #[serde(default)]
+    pub alignment: AlignmentConfig,
+    #[serde(default)]
+    pub normalization: NormalizationConfig,
+    #[serde(default)]
+    pub mix: MixConfig,
+    #[serde(default)]
+    pub vad: VadConfig,
+    #[serde(default)]
+    pub denoiser: DenoiserConfig,
+    pub input_path: PathBuf,
+    pub output_path: PathBuf,
+    /// Optional residual stem (input minus enhanced) output path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub residual_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelConfig {
+    pub path: PathBuf,
+    pub max_retries: u32,
+    /// Total ONNX intra-op threads, split across parallel workers with at least one each
+    pub inference_threads: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioConfig {
+    pub sample_rate: u32,
+    pub overlap_ratio: f32,
+    pub segment_size: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessingConfig {
+    pub enable_agc: bool,
+    pub agc_max_gain: f32,
+    pub agc_smoothing: f32,
+    pub enable_performance_monitoring: bool,
+    pub verbose: bool,
+    /// ONNX sessions of the parallel processor
+    pub parallel_workers: usize,
+}
+
+/// Limits of an enhancer's checkouts from the process-wide ONNX session pool, per model and thread count
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionsConfig {
+    /// Sessions checked out or idle; at least `processing.parallel_workers`
+    pub max_sessions: usize,
+    /// Idle sessions kept for reuse
+    pub max_idle: usize,
+    /// Idle sessions unused for this long are closed (seconds)
+    pub idle_timeout_secs: u64,
+    /// How long a file waits for a session once `max_sessions` are in use (seconds)
+    pub checkout_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlignmentConfig {
+    pub delay_mode: DelayMode,
+    pub declared_delay_samples: i64,
+    pub max_delay_ms: f32,
+    pub match_input_rate: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NormalizationConfig {
+    pub policy: NormalizationPolicy,
+    /// Target level: dBFS for peak/rms, LUFS for loudness
+    pub target_db: f32,
+    pub max_gain_db: f32,
+    pub limiter: bool,
+    pub true_peak_limit_db: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MixConfig {
+    /// Enhancement strength: 1.0 = fully enhanced, 0.0 = original
+    pub strength: f32,
+    /// Maximum noise attenuation in dB; the original is never mixed in below this level
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attenuation_limit_db: Option<f32>,
+    /// Per time-range strength overrides
+    pub ranges: Vec<MixRange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VadConfig {
+    pub enabled: bool,
+    pub silence_mode: SilenceMode,
+    /// Attenuation applied to non-speech segments in `attenuate` mode (dB)
+    pub attenuation_db: f32,
+    pub frame_ms: f32,
+    pub hangover_ms: f32,
+    /// Speech threshold above the estimated noise floor (dB)
+    pub energy_margin_db: f32,
+    /// Absolute minimum speech level (dBFS)
+    pub min_energy_db: f32,
+    /// Minimum fraction of speech frames for a segment to count as speech
+    pub min_speech_ratio: f32,
+    /// Optional speech region export (`.rttm` for RTTM, JSON otherwise)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DenoiserConfig {
+    pub backend: DenoiseBackend,
+    /// Run the spectral gate after the model to suppress residual musical noise
+    pub post_filter: bool,
+    pub fft_size: usize,
+    pub hop_size: usize,
+    /// Fraction of the quietest frames used to estimate the noise profile
+    pub noise_fraction: f32,
+    /// Gate threshold above the mean noise level, in standard deviations
+    pub threshold_std: f32,
+    /// Attenuation of gated bins with the spectral backend (dB)
+    pub reduction_db: f32,
+    /// Attenuation of gated bins in the post-filter (dB)
+    pub post_filter_reduction_db: f32,
+    /// Mask smoothing radius across frequency bins and frames
+    pub smoothing_bins: usize,
+    pub smoothing_frames: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            model: ModelConfig::default(),
+            audio: AudioConfig::default(),
+            processing: ProcessingConfig::default(),
+            sessions: SessionsConfig::default(),
+            alignment: AlignmentConfig::default(),
+            normalization: NormalizationConfig::default(),
+            mix: MixConfig::default(),
+            vad: VadConfig::default(),
+            denoiser: DenoiserConfig::default(),
+            input_path: PathBuf::from("input.wav"),
+            output_path: PathBuf::from("output.wav"),
+            residual_path: None,
+        }
+    }
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("./model/ZipEnhancer_ONNX/ZipEnhancer.onnx"),
+            max_retries: 3,
+            inference_threads: 4,
+        }
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 16000,
+            overlap_ratio: 0.1,
+            segment_size: 16000,
+        }
+    }
+}
+
+impl Default for ProcessingConfig {
+    fn default() -> Self {
+        Self {
+            enable_agc: false,
+            agc_max_gain: 4.0,
+            agc_smoothing: 0.7,
+            enable_performance_monitoring: true,
+            verbose: false,
+            parallel_workers: 4,
+        }
+    }
+}
+
+impl Default for SessionsConfig {
+    fn default() -> Self {
+        let limits = PoolLimits::default();
+        Self {
+            max_sessions: limits.max_sessions,
+            max_idle: limits.max_idle,
+            idle_timeout_secs: limits.idle_timeout.as_secs(),
+            checkout_timeout_secs: limits.checkout_timeout.as_secs(),
+        }
+    }
+}
+
+impl SessionsConfig {
+    pub fn limits(&self) -> PoolLimits {
+        PoolLimits {
+            max_sessions: self.max_sessions,
+            max_idle: self.max_idle,
+            idle_timeout: std::time::Duration::from_secs(self.idle_timeout_secs),
+            checkout_timeout: std::time::Duration::from_secs(self.checkout_timeout_secs),
+        }
+    }
+}
+
+impl Default for AlignmentConfig {
+    fn default() -> Self {
+        Self {
+            delay_mode: DelayMode::Declared,
+            declared_delay_samples: 0,
+            max_delay_ms: 50.0,
+            match_input_rate: true,
+        }
+    }
+}
+
+impl Default for NormalizationConfig {
+    fn default() -> Self {
+        Self {
+            policy: NormalizationPolicy::Loudness,
+            target_db: -20.0,
+            max_gain_db: 24.0,
+            limiter: true,
+            true_peak_limit_db: -1.0,
+        }
+    }
+}
+
+impl Default for MixConfig {
+    fn default() -> Self {
+        Self {
+            strength: 1.0,
+            attenuation_limit_db: None,
+            ranges: Vec::new(),
+        }
+    }
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            silence_mode: SilenceMode::Infer,
+            attenuation_db: 12.0,
+            frame_ms: 20.0,
+            hangover_ms: 200.0,
+            energy_margin_db: 9.0,
+            min_energy_db: -55.0,
+            min_speech_ratio: 0.05,
+            export_path: None,
+        }
+    }
+}
+
+impl Default for DenoiserConfig {
+    fn default() -> Self {
+        Self {
+            backend: DenoiseBackend::Onnx,
+            post_filter: false,
+            fft_size: 512,
+            hop_size: 128,
+            noise_fraction: 0.1,
+            threshold_std: 1.5,
+            reduction_db: 18.0,
+            post_filter_reduction_db: 6.0,
+            smoothing_bins: 2,
+            smoothing_frames: 1,
+        }
+    }
+}
+
+impl Config {
+    //! Get model path (convenience method)
+        pub fn model_path(&self) -> &PathBuf {
+            &self.model.path
+        }
+    
+        /// Get sample rate (convenience method)
+        pub fn sample_rate(&self) -> u32 {
+            self.audio.sample_rate
+        }
+    
+        /// Get overlap ratio (convenience method)
+        pub fn overlap_ratio(&self) -> f32 {
+            self.audio.overlap_ratio
+        }
+    
+        /// Get segment size (convenience method)
+        pub fn segment_size(&self) -> usize {
+            self.audio.segment_size
+        }
+    
+        /// Get max retries (convenience method)
+        pub fn max_retries(&self) -> u32 {
+            self.model.max_retries
+        }
+    
+        /// Get inference thread count (convenience method)
+        pub fn inference_threads(&self) -> usize {
+            self.model.inference_threads
+        }
+    
+        /// Get verbose mode (convenience method)
+        pub fn verbose(&self) -> bool {
+            self.processing.verbose
+        }
+    
+        /// Get performance monitoring (convenience method)
+        pub fn enable_performance_monitoring(&self) -> bool {
+            self.processing.enable_performance_monitoring
+        }
+    
+        /// Get overlap sample count
+        pub fn overlap_samples(&self) -> usize {
+            (self.segment_size() as f32 * self.overlap_ratio()) as usize
+        }
+    
+        /// Get hop size (segment movement step)
+        pub fn hop_size(&self) -> usize {
+            self.segment_size() - self.overlap_samples()
+        }
+    }
+
+impl Config {
+    //! Create config from command line arguments
+        pub fn from_args() -> Result<Self> {
+            let args = Args::parse();
+            Self::from_args_and_config(args.enhance)
+        }
+    
+        /// Create config from command line arguments layered over defaults, config files and environment
+        pub fn from_args_and_config(args: EnhanceArgs) -> Result<Self> {
+            let layers = ConfigLayers::discover(args.source.config_file.as_deref(), args.source.profile.as_deref())?;
+            let resolved = layers.resolve(&args.explicit_overrides()?)?;
+            for (key, source) in &resolved.unknown_keys {
+                log::warn!("Unknown config key {} ({})", key, source);
+            }
+            let config = resolved.config;
+
+            // Validate config
+            config.validate()?;
+
+            Ok(config)
+        }
+
+        /// Load config from TOML config file
+        pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+            let path = path.as_ref();
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| ZipEnhancerError::io_at(path, e))?;
+    
+            toml::from_str(&content)
+                .map_err(|e| ZipEnhancerError::config(format!("Failed to parse config file: {}", e)))
+        }
+    
+        /// Validate configuration parameter validity
+        pub fn validate(&self) -> Result<()> {
+            // Validate sample rate
+            if self.audio.sample_rate == 0 {
+                return Err(ZipEnhancerError::config("Sample rate must be greater than 0"));
+            }
+            if self.audio.sample_rate > 192000 {
+                return Err(ZipEnhancerError::config("Sample rate cannot exceed 192000 Hz"));
+            }
+    
+            // Validate overlap ratio
+            if self.audio.overlap_ratio < 0.0 || self.audio.overlap_ratio >= 1.0 {
+                return Err(ZipEnhancerError::config("Overlap ratio must be in range [0.0, 1.0)"));
+            }
+    
+            // Validate segment size
+            if self.audio.segment_size == 0 {
+                return Err(ZipEnhancerError::config("Segment size must be greater than 0"));
+            }
+            if !self.audio.segment_size.is_multiple_of(2) {
+                return Err(ZipEnhancerError::config("Segment size must be even"));
+            }
+    
+            if self.processing.parallel_workers == 0 {
+                return Err(ZipEnhancerError::config("Parallel workers must be greater than 0"));
+            }
+            if self.sessions.max_sessions < self.processing.parallel_workers {
+                return Err(ZipEnhancerError::config(format!(
+                    "Max sessions ({}) must be at least the parallel worker count ({})", self.sessions.max_sessions, self.processing.parallel_workers
+                )));
+            }
+    
+            // Validate alignment search window
+            if !(0.0..=1000.0).contains(&self.alignment.max_delay_ms) {
+                return Err(ZipEnhancerError::config("Max alignment delay must be in range [0, 1000] ms"));
+            }
+    
+            // Validate normalization targets
+            if self.normalization.target_db > 0.0 || self.normalization.true_peak_limit_db > 0.0 {
+                return Err(ZipEnhancerError::config("Normalization target and true-peak limit must be <= 0 dB"));
+            }
+            if !(1.0..=10.0).contains(&self.processing.agc_max_gain) {
+                return Err(ZipEnhancerError::config("AGC max gain must be in range [1.0, 10.0]"));
+            }
+            if !(0.0..1.0).contains(&self.processing.agc_smoothing) {
+                return Err(ZipEnhancerError::config("AGC smoothing must be in range [0.0, 1.0)"));
+            }
+    
+            // Validate dry/wet mix
+            if !(0.0..=1.0).contains(&self.mix.strength) {
+                return Err(ZipEnhancerError::config("Enhancement strength must be in range [0.0, 1.0]"));
+            }
+            for range in &self.mix.ranges {
+                if range.start_secs < 0.0 || range.end_secs <= range.start_secs || !(0.0..=1.0).contains(&range.strength) {
+                    return Err(ZipEnhancerError::config(format!(
+                        "Invalid mix range {}..{}s (strength {})", range.start_secs, range.end_secs, range.strength
+                    )));
+                }
+            }
+    
+            // Validate VAD
+            if self.vad.frame_ms <= 0.0 || self.vad.frame_ms > 100.0 {
+                return Err(ZipEnhancerError::config("VAD frame length must be in range (0, 100] ms"));
+            }
+            if !(0.0..=1.0).contains(&self.vad.min_speech_ratio) {
+                return Err(ZipEnhancerError::config("VAD min speech ratio must be in range [0.0, 1.0]"));
+            }
+    
+            // Validate denoiser
+            if self.denoiser.fft_size < 64 || self.denoiser.hop_size == 0 || self.denoiser.hop_size > self.denoiser.fft_size / 2 {
+                return Err(ZipEnhancerError::config("Denoiser FFT size must be >= 64 with hop size in [1, fft_size / 2]"));
+            }
+            if self.denoiser.noise_fraction <= 0.0 || self.denoiser.noise_fraction > 1.0 {
+                return Err(ZipEnhancerError::config("Denoiser noise fraction must be in range (0.0, 1.0]"));
+            }
+
+            // Validate retry count
+            if self.model.max_retries > 10 {
+                return Err(ZipEnhancerError::config("Max retries cannot exceed 10"));
+            }
+    
+            // Validate thread count
+            if self.model.inference_threads == 0 {
+                return Err(ZipEnhancerError::config("Inference thread count must be greater than 0"));
+            }
+            if self.model.inference_threads > num_cpus::get() * 2 {
+                return Err(ZipEnhancerError::config("Inference thread count cannot exceed 2x logical CPU cores"));
+            }
+    
+            Ok(())
+        }
+    
+        /// Save config to file
+        pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+            let content = toml::to_string_pretty(self)
+                .map_err(|e| ZipEnhancerError::config(format!("Failed to serialize config: {}", e)))?;
+    
+            std::fs::write(path.as_ref(), content)
+                .map_err(|e| ZipEnhancerError::io_at(path.as_ref(), e))
+        }
+    
+        
+        /// Create default config file
+        pub fn create_default_config<P: AsRef<Path>>(path: P) -> Result<()> {
+            let default_config = Self::default();
+            default_config.save_to_file(path)
+        }
+    }
+
+pub mod utils {
+    pub fn cpu_count() -> usize {
+        num_cpus::get()
+    }
+
+    pub fn recommended_segment_size(sample_rate: u32) -> usize {
+        (sample_rate as usize).max(16000)
+    }
+
+    pub fn recommended_overlap_ratio(segment_size: usize) -> f32 {
+        if segment_size >= 32000 {
+            0.05
+        } else if segment_size >= 16000 {
+            0.1
+        } else {
+            0.2
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::default();
+        assert_eq!(config.sample_rate(), 16000);
+        assert_eq!(config.overlap_ratio(), 0.1);
+        assert_eq!(config.segment_size(), 16000);
+        assert_eq!(config.max_retries(), 3);
+        assert_eq!(config.inference_threads(), 4); 
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let mut config = Config::default();
+
+        assert!(config.validate().is_ok());
+
+        config.audio.sample_rate = 0;
+        assert!(config.validate().is_err());
+        config.audio.sample_rate = 16000;
+
+        config.audio.overlap_ratio = 1.0;
+        assert!(config.validate().is_err());
+        config.audio.overlap_ratio = 0.1;
+
+        config.audio.segment_size = 0;
+        assert!(config.validate().is_err());
+        config.audio.segment_size = 16000;
+
+        config.processing.parallel_workers = 17;
+        assert!(config.validate().is_err());
+        config.sessions.max_sessions = 17;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_file_operations() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        let config = Config::default();
+
+        assert!(config.save_to_file(&config_path).is_ok());
+        assert!(config_path.exists());
+
+        let loaded_config = Config::from_file(&config_path).unwrap();
+        assert_eq!(config.sample_rate(), loaded_config.sample_rate());
+        assert_eq!(config.overlap_ratio(), loaded_config.overlap_ratio());
+    }
+
+    #[test]
+    fn test_helper_functions() {
+        assert!(utils::cpu_count() > 0);
+
+        let segment_size = utils::recommended_segment_size(16000);
+        assert_eq!(segment_size, 16000);
+
+        let overlap = utils::recommended_overlap_ratio(16000);
+        assert_eq!(overlap, 0.1);
+    }
+
+    #[test]
+    fn test_overlap_calculations() {
+        let config = Config::default();
+        assert_eq!(config.overlap_samples(), 1600);
+        assert_eq!(config.hop_size(), 14400);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,100 @@
+//! Named configuration profiles.
+//!
+//! A profile is a partial config applied over the config files, selected with
+//! `--profile` or `ZIPENHANCER_PROFILE`. Files define their own as
+//! `[profile.<name>]` tables; the presets below ship with the binary.
+
+use toml::Table;
+
+/// Environment variable selecting a profile when `--profile` is not passed
+pub const PROFILE_ENV: &str = "ZIPENHANCER_PROFILE";
+
+/// Built-in presets: name, description, TOML overrides
+pub const BUILTIN_PROFILES: &[(&str, &str, &str)] = &[
+    ("low-latency", "Live voice: 200 ms segments, peak normalization", r#"
+[audio]
+segment_size = 3200
+overlap_ratio = 0.2
+
+[normalization]
+policy = "peak"
+target_db = -3.0
+"#),
+    ("podcast", "Archival podcast: long segments, gentle cleanup, -16 LUFS", r#"
+[audio]
+segment_size = 32000
+overlap_ratio = 0.05
+
+[normalization]
+policy = "loudness"
+target_db = -16.0
+true_peak_limit_db = -1.0
+
+[mix]
+strength = 0.85
+attenuation_limit_db = 24.0
+"#),
+    ("call-center", "Aggressive call-center cleanup: full strength, post-filter, attenuated silence", r#"
+[processing]
+enable_agc = true
+
+[normalization]
+policy = "rms"
+target_db = -20.0
+
+[mix]
+strength = 1.0
+
+[vad]
+enabled = true
+silence_mode = "attenuate"
+attenuation_db = 18.0
+
+[denoiser]
+post_filter = true
+"#),
+    ("asr", "ASR front-end: denoise only, no loudness or gain changes", r#"
+[processing]
+enable_agc = false
+
+[normalization]
+policy = "none"
+limiter = false
+
+[alignment]
+match_input_rate = true
+"#),
+];
+
+/// Overrides of the built-in profile `name`
+pub fn builtin_profile(name: &str) -> Option<Table> {
+    BUILTIN_PROFILES.iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, _, toml)| toml.parse().expect("built-in profiles are valid TOML"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigLayers, layered::unknown_keys};
+
+    #[test]
+    fn test_builtin_profiles_are_valid() {
+        for (name, _, _) in BUILTIN_PROFILES {
+            let layers = ConfigLayers { profile: Some(name.to_string()), ..Default::default() };
+            assert!(unknown_keys(&builtin_profile(name).unwrap()).is_empty(), "{}", name);
+            let mut config = layers.resolve(&[]).unwrap().config;
+            // The thread limit depends on the host
+            config.model.inference_threads = 1;
+            config.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_asr_profile_keeps_levels() {
+        let layers = ConfigLayers { profile: Some("asr".into()), ..Default::default() };
+        let config = layers.resolve(&[]).unwrap().config;
+        assert_eq!(config.normalization.policy, crate::processing::loudness::NormalizationPolicy::None);
+        assert!(!config.processing.enable_agc && !config.normalization.limiter);
+    }
+}
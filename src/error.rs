@@ -1,56 +1,418 @@
-//! Error Types - Simplified
+//! Error Types
+//!
+//! Specific failures (missing input, unreadable WAV, model shape mismatch, ...) are
+//! their own variants so callers can match on them; the free-form variants remain for
+//! everything else. Every error maps to a stable [`ErrorCode`], a coarse [`ErrorKind`]
+//! and a CLI [`exit_code`]. Underlying hound, I/O and ONNX Runtime errors stay reachable
+//! through [`std::error::Error::source`].
 
 use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
 
 /// Main error type
-#[derive(Debug, Clone)]
+///
+/// `Display` shows only this error, e.g. `"Audio error: cannot read WAV in.wav"`, with
+/// its context if any, e.g. `"IO error (file out.wav)"`; the underlying error is its
+/// `source()`. [`report`](Self::report) gives the whole chain on one line.
+#[derive(Debug, Error)]
 pub enum ZipEnhancerError {
+    #[error("{}", self.headline())]
+    InputNotFound { path: PathBuf },
+    #[error("{}", self.headline())]
+    InvalidWav { path: PathBuf, #[source] source: hound::Error },
+    #[error("{}", self.headline())]
+    WavWrite { path: PathBuf, #[source] source: hound::Error },
+    #[error("{}", self.headline())]
+    UnsupportedFormat { detail: String },
+    #[error("{}", self.headline())]
+    ModelNotFound { path: PathBuf },
+    #[error("{}", self.headline())]
+    ShapeMismatch { shape: Vec<usize>, len: usize },
+    #[error("{}", self.headline())]
+    Runtime { context: &'static str, #[source] source: onnxruntime::OrtError },
+    #[error("{}", self.headline())]
+    Io { path: Option<PathBuf>, #[source] source: std::io::Error },
+    #[error("{}", self.headline())]
     Audio { message: String },
+    #[error("{}", self.headline())]
     Onnx { message: String },
+    #[error("{}", self.headline())]
     Config { message: String },
-    Io { message: String },
+    #[error("{}", self.headline())]
     Processing { message: String },
+    /// Stopped through a `CancellationToken`
+    #[error("{}", self.headline())]
+    Cancelled,
+    /// Another error with the file, segment or sample offset it occurred at
+    #[error("{} ({context})", .source.headline())]
+    Context { context: ErrorContext, #[source] source: Box<ZipEnhancerError> },
 }
 
-impl fmt::Display for ZipEnhancerError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+/// Coarse error category, see [`ZipEnhancerError::kind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    Audio,
+    Model,
+    Config,
+    Io,
+    Processing,
+    Cancelled,
+}
+
+impl ErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Audio => "audio",
+            Self::Model => "model",
+            Self::Config => "config",
+            Self::Io => "io",
+            Self::Processing => "processing",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// `"<Label> error: "` prefix of displayed messages
+    fn label(self) -> &'static str {
+        match self {
+            Self::Audio => "Audio",
+            Self::Model => "ONNX",
+            Self::Config => "Config",
+            Self::Io => "IO",
+            Self::Processing | Self::Cancelled => "Processing",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(self.as_str()) }
+}
+
+/// Stable machine-readable error codes; new codes may be added
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    InputNotFound,
+    InvalidWav,
+    UnsupportedFormat,
+    Audio,
+    ModelNotFound,
+    ShapeMismatch,
+    Runtime,
+    Model,
+    Config,
+    Io,
+    Processing,
+    Cancelled,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
         match self {
-            Self::Audio { message } => write!(f, "Audio error: {}", message),
-            Self::Onnx { message } => write!(f, "ONNX error: {}", message),
-            Self::Config { message } => write!(f, "Config error: {}", message),
-            Self::Io { message } => write!(f, "IO error: {}", message),
-            Self::Processing { message } => write!(f, "Processing error: {}", message),
+            Self::InputNotFound => "input_not_found",
+            Self::InvalidWav => "invalid_wav",
+            Self::UnsupportedFormat => "unsupported_format",
+            Self::Audio => "audio",
+            Self::ModelNotFound => "model_not_found",
+            Self::ShapeMismatch => "shape_mismatch",
+            Self::Runtime => "runtime",
+            Self::Model => "model",
+            Self::Config => "config",
+            Self::Io => "io",
+            Self::Processing => "processing",
+            Self::Cancelled => "cancelled",
         }
     }
 }
 
-impl std::error::Error for ZipEnhancerError {}
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(self.as_str()) }
+}
+
+/// Where an error occurred
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    pub path: Option<PathBuf>,
+    /// Segment index within the file
+    pub segment: Option<usize>,
+    /// First sample of the segment, in samples at the model rate
+    pub sample_offset: Option<usize>,
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(path) = &self.path { parts.push(format!("file {}", path.display())); }
+        if let Some(segment) = self.segment { parts.push(format!("segment {}", segment)); }
+        if let Some(offset) = self.sample_offset { parts.push(format!("sample {}", offset)); }
+        f.write_str(&parts.join(", "))
+    }
+}
 
 impl ZipEnhancerError {
     pub fn audio<S: Into<String>>(msg: S) -> Self { Self::Audio { message: msg.into() } }
     pub fn onnx<S: Into<String>>(msg: S) -> Self { Self::Onnx { message: msg.into() } }
     pub fn config<S: Into<String>>(msg: S) -> Self { Self::Config { message: msg.into() } }
-    pub fn io<S: Into<String>>(msg: S) -> Self { Self::Io { message: msg.into() } }
     pub fn processing<S: Into<String>>(msg: S) -> Self { Self::Processing { message: msg.into() } }
+
+    /// I/O failure on `path`
+    pub fn io_at(path: &Path, source: std::io::Error) -> Self { Self::Io { path: Some(path.to_path_buf()), source } }
+
+    /// Failure opening or decoding the WAV file at `path`; a missing file becomes `InputNotFound`
+    pub fn wav(path: &Path, source: hound::Error) -> Self {
+        match source {
+            hound::Error::IoError(e) if e.kind() == std::io::ErrorKind::NotFound => Self::InputNotFound { path: path.to_path_buf() },
+            hound::Error::Unsupported => Self::UnsupportedFormat { detail: "unsupported WAV encoding".into() },
+            source => Self::InvalidWav { path: path.to_path_buf(), source },
+        }
+    }
+
+    /// Failure writing the WAV file at `path`; I/O failures become `Io`
+    pub fn wav_write(path: &Path, source: hound::Error) -> Self {
+        match source {
+            hound::Error::IoError(e) => Self::io_at(path, e),
+            source => Self::WavWrite { path: path.to_path_buf(), source },
+        }
+    }
+
+    /// Attach (or complete) the context of this error
+    fn with_context(self, update: impl FnOnce(&mut ErrorContext)) -> Self {
+        match self {
+            Self::Context { mut context, source } => {
+                update(&mut context);
+                Self::Context { context, source }
+            }
+            error => {
+                let mut context = ErrorContext::default();
+                update(&mut context);
+                Self::Context { context, source: Box::new(error) }
+            }
+        }
+    }
+
+    /// Record the file this error occurred in, unless already known
+    pub fn with_path(self, path: &Path) -> Self {
+        if self.path().is_some() { return self; }
+        self.with_context(|c| c.path = Some(path.to_path_buf()))
+    }
+
+    /// File the error refers to, from its context or the error itself
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Context { context, source } => context.path.as_deref().or_else(|| source.path()),
+            Self::InputNotFound { path } | Self::InvalidWav { path, .. } | Self::WavWrite { path, .. } | Self::ModelNotFound { path } => Some(path),
+            Self::Io { path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Record the segment (and its first sample) this error occurred in
+    pub fn with_segment(self, segment: usize, sample_offset: usize) -> Self {
+        self.with_context(|c| {
+            c.segment = Some(segment);
+            c.sample_offset = Some(sample_offset);
+        })
+    }
+
+    /// Context attached with `with_path`/`with_segment`, if any
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self { Self::Context { context, .. } => Some(context), _ => None }
+    }
+
+    /// The error without its context
+    pub fn root(&self) -> &Self {
+        match self { Self::Context { source, .. } => source.root(), error => error }
+    }
+
+    /// Stable machine-readable code
+    pub fn code(&self) -> ErrorCode {
+        match self.root() {
+            Self::InputNotFound { .. } => ErrorCode::InputNotFound,
+            Self::InvalidWav { .. } => ErrorCode::InvalidWav,
+            Self::UnsupportedFormat { .. } => ErrorCode::UnsupportedFormat,
+            Self::WavWrite { .. } | Self::Audio { .. } => ErrorCode::Audio,
+            Self::ModelNotFound { .. } => ErrorCode::ModelNotFound,
+            Self::ShapeMismatch { .. } => ErrorCode::ShapeMismatch,
+            Self::Runtime { .. } => ErrorCode::Runtime,
+            Self::Onnx { .. } => ErrorCode::Model,
+            Self::Config { .. } => ErrorCode::Config,
+            Self::Io { .. } => ErrorCode::Io,
+            Self::Processing { .. } => ErrorCode::Processing,
+            Self::Cancelled => ErrorCode::Cancelled,
+            Self::Context { .. } => unreachable!("root() strips context"),
+        }
+    }
+
+    /// Coarse category
+    pub fn kind(&self) -> ErrorKind {
+        match self.code() {
+            ErrorCode::InputNotFound | ErrorCode::InvalidWav | ErrorCode::UnsupportedFormat | ErrorCode::Audio => ErrorKind::Audio,
+            ErrorCode::ModelNotFound | ErrorCode::ShapeMismatch | ErrorCode::Runtime | ErrorCode::Model => ErrorKind::Model,
+            ErrorCode::Config => ErrorKind::Config,
+            ErrorCode::Io => ErrorKind::Io,
+            ErrorCode::Processing => ErrorKind::Processing,
+            ErrorCode::Cancelled => ErrorKind::Cancelled,
+        }
+    }
+
+    /// What this error itself says, without prefix, causes or context
+    fn text(&self) -> String {
+        match self {
+            Self::InputNotFound { path } => format!("input not found: {}", path.display()),
+            Self::InvalidWav { path, .. } => format!("cannot read WAV {}", path.display()),
+            Self::WavWrite { path, .. } => format!("cannot write WAV {}", path.display()),
+            Self::UnsupportedFormat { detail } => format!("unsupported format: {}", detail),
+            Self::ModelNotFound { path } => format!("model not found: {}", path.display()),
+            Self::ShapeMismatch { shape, len } => format!("tensor shape {:?} needs {} values, got {}", shape, shape.iter().product::<usize>(), len),
+            Self::Runtime { context, .. } => context.to_string(),
+            Self::Io { path, .. } => path.as_deref().map(|p| p.display().to_string()).unwrap_or_default(),
+            Self::Audio { message } | Self::Onnx { message } | Self::Config { message } | Self::Processing { message } => message.clone(),
+            Self::Cancelled => "cancelled".into(),
+            Self::Context { source, .. } => source.text(),
+        }
+    }
+
+    /// `Display` of everything but `Context`
+    fn headline(&self) -> String {
+        let text = self.text();
+        let label = self.kind().label();
+        if text.is_empty() { format!("{} error", label) } else { format!("{} error: {}", label, text) }
+    }
+
+    /// The message without its `"<Label> error: "` prefix, followed by the underlying
+    /// causes and the context, e.g. `"cannot read WAV in.wav: no RIFF tag found"`
+    pub fn message(&self) -> String {
+        let root = self.root();
+        let mut parts = vec![root.text()];
+        let mut cause = std::error::Error::source(root);
+        while let Some(error) = cause {
+            parts.push(error.to_string());
+            cause = error.source();
+        }
+        parts.retain(|part| !part.is_empty());
+        let mut message = parts.join(": ");
+        if let Some(context) = self.context() {
+            message.push_str(&format!(" ({})", context));
+        }
+        message
+    }
+
+    /// The whole error on one line: prefix, message, causes and context
+    pub fn report(&self) -> String {
+        format!("{} error: {}", self.kind().label(), self.message())
+    }
+
+    /// CLI exit code for this error, see [`exit_code`]
+    pub fn exit_code(&self) -> i32 {
+        match self.kind() {
+            ErrorKind::Config => exit_code::CONFIG,
+            ErrorKind::Model => exit_code::MODEL,
+            ErrorKind::Audio => exit_code::AUDIO,
+            ErrorKind::Cancelled => exit_code::CANCELLED,
+            ErrorKind::Io | ErrorKind::Processing => exit_code::FAILURE,
+        }
+    }
+}
+
+/// Attach context to the error of a `Result`
+pub trait ResultExt<T> {
+    fn with_path(self, path: &Path) -> Result<T>;
+    fn with_segment(self, segment: usize, sample_offset: usize) -> Result<T>;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn with_path(self, path: &Path) -> Result<T> { self.map_err(|e| e.with_path(path)) }
+    fn with_segment(self, segment: usize, sample_offset: usize) -> Result<T> { self.map_err(|e| e.with_segment(segment, sample_offset)) }
+}
+
+/// Process exit codes of the `zipenhancer` CLI
+pub mod exit_code {
+    pub const SUCCESS: i32 = 0;
+    /// Processing or I/O failure
+    pub const FAILURE: i32 = 1;
+    /// Invalid arguments or configuration (also used by clap for usage errors)
+    pub const CONFIG: i32 = 2;
+    /// Model file or ONNX Runtime failure
+    pub const MODEL: i32 = 3;
+    /// Input audio missing, unreadable or in an unsupported format
+    pub const AUDIO: i32 = 4;
+    /// Some files of a batch failed while others succeeded
+    pub const PARTIAL_BATCH: i32 = 5;
+    /// Stopped by Ctrl-C (128 + SIGINT)
+    pub const CANCELLED: i32 = 130;
 }
 
 pub type Result<T> = std::result::Result<T, ZipEnhancerError>;
 
 impl From<std::io::Error> for ZipEnhancerError {
-    fn from(err: std::io::Error) -> Self { Self::io(err.to_string()) }
+    fn from(source: std::io::Error) -> Self { Self::Io { path: None, source } }
 }
 
 impl From<onnxruntime::OrtError> for ZipEnhancerError {
-    fn from(err: onnxruntime::OrtError) -> Self { Self::onnx(format!("ORT: {}", err)) }
+    fn from(source: onnxruntime::OrtError) -> Self { Self::Runtime { context: "ORT", source } }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::error::Error as _;
 
     #[test]
     fn test_error_display() {
         let e = ZipEnhancerError::audio("test");
         assert!(e.to_string().contains("Audio"));
     }
+
+    #[test]
+    fn test_kind_and_exit_code() {
+        assert_eq!(ZipEnhancerError::onnx("x").kind(), ErrorKind::Model);
+        assert_eq!(ZipEnhancerError::onnx("x").exit_code(), exit_code::MODEL);
+        assert_eq!(ZipEnhancerError::audio("x").exit_code(), exit_code::AUDIO);
+        assert_eq!(ZipEnhancerError::config("bad").message(), "bad");
+    }
+
+    #[test]
+    fn test_wav_error_codes() {
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let e = ZipEnhancerError::wav(Path::new("a.wav"), hound::Error::IoError(missing));
+        assert!(matches!(e, ZipEnhancerError::InputNotFound { ref path } if path == Path::new("a.wav")));
+        assert_eq!(e.code(), ErrorCode::InputNotFound);
+        assert_eq!(e.exit_code(), exit_code::AUDIO);
+
+        let e = ZipEnhancerError::wav(Path::new("a.wav"), hound::Error::FormatError("no RIFF tag found"));
+        assert_eq!(e.code().as_str(), "invalid_wav");
+        assert!(e.source().unwrap().downcast_ref::<hound::Error>().is_some());
+        assert_eq!(e.message(), "cannot read WAV a.wav: Ill-formed WAVE file: no RIFF tag found");
+
+        let e = ZipEnhancerError::wav_write(Path::new("b.wav"), hound::Error::IoError(std::io::Error::other("disk full")));
+        assert!(matches!(e, ZipEnhancerError::Io { path: Some(ref path), .. } if path == Path::new("b.wav")));
+        assert_eq!(e.exit_code(), exit_code::FAILURE);
+        let e = ZipEnhancerError::wav_write(Path::new("b.wav"), hound::Error::TooWide);
+        assert_eq!(e.message(), "cannot write WAV b.wav: The sample has more bits than the destination type.");
+    }
+
+    #[test]
+    fn test_context_merges_and_keeps_code() {
+        let e = ZipEnhancerError::ShapeMismatch { shape: vec![1, 4], len: 3 }
+            .with_segment(2, 32000)
+            .with_path(Path::new("in.wav"));
+        assert_eq!(e.code(), ErrorCode::ShapeMismatch);
+        assert_eq!(e.context().unwrap(), &ErrorContext { path: Some("in.wav".into()), segment: Some(2), sample_offset: Some(32000) });
+        assert!(matches!(e.root(), ZipEnhancerError::ShapeMismatch { len: 3, .. }));
+        assert_eq!(e.report(), "ONNX error: tensor shape [1, 4] needs 4 values, got 3 (file in.wav, segment 2, sample 32000)");
+
+        // A path the error already names is not repeated
+        let e = ZipEnhancerError::InputNotFound { path: "in.wav".into() }.with_path(Path::new("in.wav"));
+        assert!(e.context().is_none() && e.path() == Some(Path::new("in.wav")));
+
+        // The underlying error stays at the end of the source chain
+        let e = ZipEnhancerError::from(std::io::Error::other("disk full")).with_path(Path::new("out.wav"));
+        let chain: Vec<_> = std::iter::successors(Some(&e as &dyn std::error::Error), |e| (*e).source()).collect();
+        assert_eq!(chain.last().unwrap().downcast_ref::<std::io::Error>().unwrap().to_string(), "disk full");
+        // ...and every link says something different, so walking the chain repeats nothing
+        let texts: Vec<_> = chain.iter().map(|e| e.to_string()).collect();
+        assert_eq!(texts, ["IO error (file out.wav)", "IO error", "disk full"]);
+        assert_eq!(e.report(), "IO error: disk full (file out.wav)");
+    }
 }
@@ -3,10 +3,18 @@
 //! Uses multi-session parallel ONNX inference for optimal performance.
 
 pub mod audio;
+pub mod bench;
 pub mod config;
 pub mod error;
+pub mod metrics;
 pub mod onnx;
+pub mod prelude;
 pub mod processing;
+pub mod regression;
+pub mod report;
+pub mod synth;
+pub mod telemetry;
+pub mod visualize;
 
 pub use config::{Config, Args};
 pub use error::{ZipEnhancerError, Result};
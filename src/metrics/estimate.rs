@@ -0,0 +1,185 @@
+//! Reference-free quality estimates (no clean signal required)
+
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use crate::processing::loudness::{to_db, LevelStats};
+
+/// Analysis frame length (seconds)
+const FRAME_SECS: f32 = 0.02;
+/// Window of the minimum-statistics noise tracker (seconds)
+const TRACKER_WINDOW_SECS: f32 = 1.5;
+/// Recursive smoothing of frame power before minimum tracking
+const POWER_SMOOTHING: f32 = 0.5;
+/// Compensates the downward bias of taking minima of a smoothed power estimate
+const MIN_BIAS: f32 = 1.2;
+/// Frames this far above the tracked noise floor count as active signal
+const ACTIVE_MARGIN_DB: f32 = 6.0;
+/// Samples at or above this magnitude count as clipped
+const CLIP_THRESHOLD: f32 = 0.999;
+/// SNR gain below which enhancement is reported as ineffective (dB)
+const MIN_IMPROVEMENT_DB: f32 = 1.0;
+/// Drop in active-signal level beyond which speech is reported as attenuated (dB)
+const MAX_SPEECH_LOSS_DB: f32 = 6.0;
+
+/// Blind level and noise estimates for one signal
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SignalEstimate {
+    pub levels: LevelStats,
+    /// Mean of the tracked noise floor (dBFS)
+    pub noise_floor_db: f32,
+    /// Mean level of frames above the noise floor (dBFS)
+    pub active_level_db: f32,
+    /// Estimated speech-to-noise ratio (dB)
+    pub snr_db: f32,
+    pub clipped_samples: usize,
+}
+
+/// Input vs. output estimates for one processed file
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct QualityEstimate {
+    pub input: SignalEstimate,
+    pub output: SignalEstimate,
+    pub snr_improvement_db: f32,
+    /// Drop of the noise floor from input to output, normalization gain undone (dB)
+    pub noise_reduction_db: f32,
+    /// Gain applied to the output by normalization (dB)
+    #[serde(default)]
+    pub gain_db: f32,
+}
+
+fn power_db(power: f32) -> f32 {
+    to_db(power.max(0.0).sqrt())
+}
+
+/// Track the noise floor per frame as the bias-corrected minimum of the smoothed frame power
+fn track_noise(frame_power: &[f32], window: usize) -> Vec<f32> {
+    let mut smoothed = Vec::with_capacity(frame_power.len());
+    let mut state = frame_power.first().copied().unwrap_or(0.0);
+    for &p in frame_power {
+        state = POWER_SMOOTHING * state + (1.0 - POWER_SMOOTHING) * p;
+        smoothed.push(state);
+    }
+    (0..smoothed.len()).map(|i| {
+        let start = i.saturating_sub(window / 2);
+        let end = (i + window / 2 + 1).min(smoothed.len());
+        smoothed[start..end].iter().copied().fold(f32::MAX, f32::min) * MIN_BIAS
+    }).collect()
+}
+
+impl SignalEstimate {
+    pub fn measure(data: &Array1<f32>, sample_rate: u32) -> Self {
+        let samples = data.to_vec();
+        let frame = ((FRAME_SECS * sample_rate as f32) as usize).max(1);
+        let frame_power: Vec<f32> = samples.chunks(frame)
+            .map(|c| c.iter().map(|x| x * x).sum::<f32>() / c.len() as f32)
+            .collect();
+        let window = ((TRACKER_WINDOW_SECS / FRAME_SECS) as usize).max(1);
+        let noise = track_noise(&frame_power, window);
+
+        let margin = 10f32.powf(ACTIVE_MARGIN_DB / 10.0);
+        let active: Vec<(f32, f32)> = frame_power.iter().zip(&noise)
+            .filter(|(p, n)| **p > **n * margin)
+            .map(|(&p, &n)| (p, n))
+            .collect();
+        let mean = |v: &[f32]| if v.is_empty() { 0.0 } else { v.iter().sum::<f32>() / v.len() as f32 };
+        let noise_power = mean(&noise);
+        let (active_power, snr_db) = if active.is_empty() {
+            (noise_power, 0.0)
+        } else {
+            let signal = mean(&active.iter().map(|(p, _)| *p).collect::<Vec<_>>());
+            let floor = mean(&active.iter().map(|(_, n)| *n).collect::<Vec<_>>());
+            (signal, power_db((signal - floor).max(0.0)) - power_db(floor))
+        };
+
+        Self {
+            levels: LevelStats::measure(data, sample_rate),
+            noise_floor_db: power_db(noise_power),
+            active_level_db: power_db(active_power),
+            snr_db,
+            clipped_samples: samples.iter().filter(|x| x.abs() >= CLIP_THRESHOLD).count(),
+        }
+    }
+}
+
+impl QualityEstimate {
+    /// Compare input and output; both must be at `sample_rate` and time-aligned.
+    /// `gain_db` is the normalization gain included in `output`.
+    pub fn measure(input: &Array1<f32>, output: &Array1<f32>, sample_rate: u32, gain_db: f32) -> Self {
+        let input = SignalEstimate::measure(input, sample_rate);
+        let output = SignalEstimate::measure(output, sample_rate);
+        Self {
+            input,
+            output,
+            snr_improvement_db: output.snr_db - input.snr_db,
+            noise_reduction_db: input.noise_floor_db - (output.noise_floor_db - gain_db),
+            gain_db,
+        }
+    }
+
+    /// Problems worth flagging in batch runs (little effect, clipping, speech loss)
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.snr_improvement_db < MIN_IMPROVEMENT_DB {
+            warnings.push(format!("estimated SNR improved by only {:.1} dB", self.snr_improvement_db));
+        }
+        if self.output.clipped_samples > self.input.clipped_samples {
+            warnings.push(format!("output has {} clipped samples (input {})", self.output.clipped_samples, self.input.clipped_samples));
+        }
+        // Compare against the input active level after undoing the normalization gain
+        if self.input.active_level_db - (self.output.active_level_db - self.gain_db) > MAX_SPEECH_LOSS_DB {
+            warnings.push("active signal level dropped; speech may be attenuated".to_string());
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn bursts_in_noise(noise_amp: f32, seed: u64) -> Array1<f32> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        Array1::from_iter((0..64000).map(|i| {
+            let t = i as f32 / 16000.0;
+            let tone = if (i / 8000) % 2 == 1 { 0.3 * (std::f32::consts::TAU * 200.0 * t).sin() } else { 0.0 };
+            tone + if noise_amp > 0.0 { rng.gen_range(-noise_amp..noise_amp) } else { 0.0 }
+        }))
+    }
+
+    #[test]
+    fn test_noise_floor_and_snr() {
+        // Uniform noise of amplitude a has RMS a / sqrt(3): 0.01 -> about -44.8 dBFS
+        let estimate = SignalEstimate::measure(&bursts_in_noise(0.01, 1), 16000);
+        assert!((estimate.noise_floor_db + 44.8).abs() < 3.0, "{}", estimate.noise_floor_db);
+        // Tone RMS 0.212 (-13.5 dBFS) over that floor: about 31 dB
+        assert!((estimate.snr_db - 31.3).abs() < 3.0, "{}", estimate.snr_db);
+    }
+
+    #[test]
+    fn test_noise_reduction_undoes_gain() {
+        // Noise amplitude 0.05 -> 0.005 lowers the floor by 20 dB, whatever gain follows
+        let (noisy, clean) = (bursts_in_noise(0.05, 4), bursts_in_noise(0.005, 4));
+        let plain = QualityEstimate::measure(&noisy, &clean, 16000, 0.0);
+        let gain = 20.0 * 2f32.log10();
+        let boosted = QualityEstimate::measure(&noisy, &clean.mapv(|x| x * 2.0), 16000, gain);
+        assert!((plain.noise_reduction_db - 20.0).abs() < 1.5, "{}", plain.noise_reduction_db);
+        assert!((boosted.noise_reduction_db - plain.noise_reduction_db).abs() < 0.01);
+        assert!(boosted.warnings().is_empty(), "{:?}", boosted.warnings());
+    }
+
+    #[test]
+    fn test_improvement_and_warnings() {
+        let noisy = bursts_in_noise(0.05, 2);
+        let clean = bursts_in_noise(0.002, 3);
+        let quality = QualityEstimate::measure(&noisy, &clean, 16000, 0.0);
+        assert!(quality.snr_improvement_db > 15.0);
+        assert!(quality.noise_reduction_db > 15.0);
+        assert!(quality.warnings().is_empty(), "{:?}", quality.warnings());
+
+        let clipped = noisy.mapv(|x| (x * 8.0).clamp(-1.0, 1.0));
+        let degraded = QualityEstimate::measure(&noisy, &clipped, 16000, 18.0);
+        assert!(degraded.output.clipped_samples > 0);
+        assert_eq!(degraded.warnings().len(), 2);
+    }
+}
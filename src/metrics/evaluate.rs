@@ -0,0 +1,217 @@
+//! Evaluation - Score enhanced files against clean references
+
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::audio::{AudioConverter, WavAudio};
+use crate::config::AlignmentConfig;
+use crate::error::{ZipEnhancerError, Result};
+use crate::processing::alignment::estimate_delay;
+use super::reference::{log_spectral_distance, segmental_snr, si_sdr, snr, stoi};
+
+/// Metrics for one enhanced/clean pair
+#[derive(Debug, Clone, Serialize)]
+pub struct FileScores {
+    pub name: String,
+    pub delay_samples: i64,
+    pub duration_secs: f64,
+    pub snr_db: f64,
+    pub si_sdr_db: f64,
+    pub seg_snr_db: f64,
+    pub lsd_db: f64,
+    pub stoi: f64,
+    pub estoi: f64,
+}
+
+/// Mean of each metric across all evaluated files
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AggregateScores {
+    pub files: usize,
+    pub snr_db: f64,
+    pub si_sdr_db: f64,
+    pub seg_snr_db: f64,
+    pub lsd_db: f64,
+    pub stoi: f64,
+    pub estoi: f64,
+}
+
+/// Per-file and aggregate results of an evaluation run
+#[derive(Debug, Clone, Serialize)]
+pub struct EvaluationReport {
+    pub files: Vec<FileScores>,
+    pub aggregate: AggregateScores,
+}
+
+impl EvaluationReport {
+    fn new(files: Vec<FileScores>) -> Self {
+        let n = files.len().max(1) as f64;
+        let mean = |f: fn(&FileScores) -> f64| files.iter().map(f).sum::<f64>() / n;
+        let aggregate = AggregateScores {
+            files: files.len(),
+            snr_db: mean(|s| s.snr_db),
+            si_sdr_db: mean(|s| s.si_sdr_db),
+            seg_snr_db: mean(|s| s.seg_snr_db),
+            lsd_db: mean(|s| s.lsd_db),
+            stoi: mean(|s| s.stoi),
+            estoi: mean(|s| s.estoi),
+        };
+        Self { files, aggregate }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| ZipEnhancerError::processing(format!("Failed to serialize evaluation report: {}", e)))
+    }
+
+    /// CSV with one row per file followed by a `MEAN` row
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("name,delay_samples,duration_secs,snr_db,si_sdr_db,seg_snr_db,lsd_db,stoi,estoi\n");
+        for s in &self.files {
+            out.push_str(&format!("{},{},{:.3},{:.3},{:.3},{:.3},{:.3},{:.4},{:.4}\n",
+                s.name, s.delay_samples, s.duration_secs, s.snr_db, s.si_sdr_db, s.seg_snr_db, s.lsd_db, s.stoi, s.estoi));
+        }
+        let a = &self.aggregate;
+        out.push_str(&format!("MEAN,,,{:.3},{:.3},{:.3},{:.3},{:.4},{:.4}\n",
+            a.snr_db, a.si_sdr_db, a.seg_snr_db, a.lsd_db, a.stoi, a.estoi));
+        out
+    }
+
+    /// Write the report to `path`; `.csv` files use CSV, anything else JSON
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")) {
+            self.to_csv()
+        } else {
+            self.to_json()?
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+/// Score an enhanced signal against a clean reference.
+///
+/// The enhanced signal is resampled to the reference rate and shifted by the
+/// cross-correlation delay before any metric is computed.
+pub fn evaluate_audio(name: &str, enhanced: &WavAudio, clean: &WavAudio) -> Result<FileScores> {
+    let sample_rate = clean.sample_rate();
+    let enhanced = if enhanced.sample_rate() != sample_rate {
+        AudioConverter::convert_sample_rate(enhanced, sample_rate)?
+    } else {
+        enhanced.clone()
+    };
+    let reference = clean.data().to_mono();
+    let estimate = enhanced.data().to_mono();
+    if reference.is_empty() || estimate.is_empty() {
+        return Err(ZipEnhancerError::audio(format!("Cannot evaluate empty audio: {}", name)));
+    }
+
+    // Same search range as output alignment
+    let max_lag = (AlignmentConfig::default().max_delay_ms / 1000.0 * sample_rate as f32) as usize;
+    let (delay, _) = estimate_delay(&reference, &estimate, max_lag);
+    let (reference, estimate) = (reference.to_vec(), estimate.to_vec());
+    let (reference, estimate) = if delay >= 0 {
+        (&reference[..], &estimate[(delay as usize).min(estimate.len())..])
+    } else {
+        (&reference[((-delay) as usize).min(reference.len())..], &estimate[..])
+    };
+    let len = reference.len().min(estimate.len());
+    let (reference, estimate) = (&reference[..len], &estimate[..len]);
+
+    Ok(FileScores {
+        name: name.to_string(),
+        delay_samples: delay,
+        duration_secs: len as f64 / sample_rate as f64,
+        snr_db: snr(reference, estimate),
+        si_sdr_db: si_sdr(reference, estimate),
+        seg_snr_db: segmental_snr(reference, estimate, sample_rate),
+        lsd_db: log_spectral_distance(reference, estimate)?,
+        stoi: stoi(reference, estimate, sample_rate, false)?,
+        estoi: stoi(reference, estimate, sample_rate, true)?,
+    })
+}
+
+/// Pair enhanced files with clean references by file name
+fn pair_files(enhanced: &Path, clean: &Path) -> Result<Vec<(String, PathBuf, PathBuf)>> {
+    if enhanced.is_file() && clean.is_file() {
+        let name = enhanced.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        return Ok(vec![(name, enhanced.to_path_buf(), clean.to_path_buf())]);
+    }
+    if !enhanced.is_dir() || !clean.is_dir() {
+        return Err(ZipEnhancerError::config(format!(
+            "Enhanced and clean must both be files or both be directories: {}, {}", enhanced.display(), clean.display()
+        )));
+    }
+
+    let mut pairs = Vec::new();
+    for entry in std::fs::read_dir(enhanced)? {
+        let path = entry?.path();
+        if !path.extension().is_some_and(|e| e.eq_ignore_ascii_case("wav")) {
+            continue;
+        }
+        let Some(name) = path.file_name() else { continue };
+        let reference = clean.join(name);
+        if reference.is_file() {
+            pairs.push((name.to_string_lossy().into_owned(), path, reference));
+        } else {
+            log::warn!("No clean reference for {}", path.display());
+        }
+    }
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    if pairs.is_empty() {
+        return Err(ZipEnhancerError::config(format!("No matching WAV pairs in {} and {}", enhanced.display(), clean.display())));
+    }
+    Ok(pairs)
+}
+
+/// Evaluate a single pair of files, or every same-named WAV pair in two directories
+pub fn evaluate_paths(enhanced: &Path, clean: &Path) -> Result<EvaluationReport> {
+    let files = pair_files(enhanced, clean)?.into_iter()
+        .map(|(name, enhanced, clean)| {
+            evaluate_audio(&name, &WavAudio::from_file(&enhanced)?, &WavAudio::from_file(&clean)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(EvaluationReport::new(files))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array1;
+    use crate::audio::AudioFormat;
+
+    fn tone(len: usize) -> WavAudio {
+        let data = Array1::from_iter((0..len).map(|i| {
+            let t = i as f32 / 16000.0;
+            0.3 * (std::f32::consts::TAU * 230.0 * t).sin() * (0.6 + 0.4 * (std::f32::consts::TAU * 2.0 * t).sin())
+                + 0.1 * (std::f32::consts::TAU * 1210.0 * t).sin()
+        }));
+        WavAudio::new_mono(16000, data, AudioFormat::Float32)
+    }
+
+    #[test]
+    fn test_evaluate_aligns_delayed_output() {
+        let clean = tone(32000);
+        // Enhanced output lags the reference by 40 samples
+        let mut delayed = vec![0.0f32; 40];
+        delayed.extend(clean.data().to_mono().iter().take(32000 - 40));
+        let enhanced = WavAudio::new_mono(16000, Array1::from(delayed), AudioFormat::Float32);
+
+        let scores = evaluate_audio("a.wav", &enhanced, &clean).unwrap();
+        assert_eq!(scores.delay_samples, 40);
+        assert!(scores.si_sdr_db > 60.0);
+        assert!(scores.stoi > 0.99);
+    }
+
+    #[test]
+    fn test_report_csv_and_aggregate() {
+        let clean = tone(16000);
+        let scores = evaluate_audio("b.wav", &clean, &clean).unwrap();
+        let report = EvaluationReport::new(vec![scores.clone(), scores]);
+        assert_eq!(report.aggregate.files, 2);
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().last().unwrap().starts_with("MEAN,"));
+    }
+}
@@ -0,0 +1,12 @@
+//! Quality Metrics Module
+//!
+//! Reference-based speech quality metrics, evaluation of enhanced/clean pairs and
+//! reference-free estimates attached to processing results.
+
+pub mod reference;
+pub mod evaluate;
+pub mod estimate;
+
+pub use reference::{snr, si_sdr, segmental_snr, log_spectral_distance, stoi};
+pub use estimate::{QualityEstimate, SignalEstimate};
+pub use evaluate::{evaluate_audio, evaluate_paths, EvaluationReport, FileScores, AggregateScores};
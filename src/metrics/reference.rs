@@ -0,0 +1,322 @@
+//! Reference-based quality metrics (enhanced vs. clean)
+
+use ndarray::{Array1, Array2};
+use crate::audio::{AudioConverter, AudioFormat, WavAudio};
+use crate::audio::spectral::{power, Stft, StftConfig, WindowType};
+use crate::error::Result;
+
+const EPS: f64 = 1e-10;
+/// Segmental SNR frame length (seconds) and clamping range (dB)
+const SEG_SNR_FRAME_SECS: f32 = 0.03;
+const SEG_SNR_RANGE: (f64, f64) = (-10.0, 35.0);
+/// Log-spectral distance FFT size
+const LSD_FFT_SIZE: usize = 512;
+
+/// STOI parameters (Taal et al., 2011)
+const STOI_FS: u32 = 10000;
+const STOI_FRAME: usize = 256;
+const STOI_FFT: usize = 512;
+const STOI_BANDS: usize = 15;
+const STOI_MIN_FREQ: f64 = 150.0;
+const STOI_SEGMENT: usize = 30;
+const STOI_BETA_DB: f64 = -15.0;
+const STOI_DYN_RANGE_DB: f64 = 40.0;
+
+fn energy(x: &[f32]) -> f64 {
+    x.iter().map(|&v| (v as f64) * (v as f64)).sum()
+}
+
+/// Signal-to-noise ratio of `estimate` against `reference` in dB
+pub fn snr(reference: &[f32], estimate: &[f32]) -> f64 {
+    let len = reference.len().min(estimate.len());
+    let noise: f64 = (0..len).map(|i| (reference[i] as f64 - estimate[i] as f64).powi(2)).sum();
+    10.0 * ((energy(&reference[..len]) + EPS) / (noise + EPS)).log10()
+}
+
+/// Scale-invariant signal-to-distortion ratio in dB (Le Roux et al., 2019)
+pub fn si_sdr(reference: &[f32], estimate: &[f32]) -> f64 {
+    let len = reference.len().min(estimate.len());
+    let (r, e) = (&reference[..len], &estimate[..len]);
+    let r_mean = r.iter().map(|&v| v as f64).sum::<f64>() / len.max(1) as f64;
+    let e_mean = e.iter().map(|&v| v as f64).sum::<f64>() / len.max(1) as f64;
+
+    let dot: f64 = (0..len).map(|i| (r[i] as f64 - r_mean) * (e[i] as f64 - e_mean)).sum();
+    let ref_energy: f64 = r.iter().map(|&v| (v as f64 - r_mean).powi(2)).sum();
+    let alpha = dot / (ref_energy + EPS);
+
+    let (mut target, mut distortion) = (0.0, 0.0);
+    for i in 0..len {
+        let t = alpha * (r[i] as f64 - r_mean);
+        target += t * t;
+        distortion += (e[i] as f64 - e_mean - t).powi(2);
+    }
+    10.0 * ((target + EPS) / (distortion + EPS)).log10()
+}
+
+/// Segmental SNR in dB: mean of per-frame SNRs clamped to [-10, 35] dB
+pub fn segmental_snr(reference: &[f32], estimate: &[f32], sample_rate: u32) -> f64 {
+    let len = reference.len().min(estimate.len());
+    let frame = ((SEG_SNR_FRAME_SECS * sample_rate as f32) as usize).max(1);
+    let hop = (frame / 2).max(1);
+    if len < frame {
+        return snr(reference, estimate).clamp(SEG_SNR_RANGE.0, SEG_SNR_RANGE.1);
+    }
+    let values: Vec<f64> = (0..=len - frame).step_by(hop)
+        .map(|start| snr(&reference[start..start + frame], &estimate[start..start + frame]).clamp(SEG_SNR_RANGE.0, SEG_SNR_RANGE.1))
+        .collect();
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Symmetric Hann window matching the STOI reference (`hanning(n + 2)[1:-1]`)
+fn hann(n: usize) -> Vec<f32> {
+    (1..=n).map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / (n + 1) as f32).cos()).collect()
+}
+
+/// Power spectrogram of non-centred frames, shape (frames, fft_size / 2 + 1)
+fn power_spectrogram(stft: &Stft, x: &[f32]) -> Array2<f32> {
+    power(&stft.forward(&Array1::from(x.to_vec())))
+}
+
+/// Log-spectral distance in dB (mean over frames of the RMS log-power difference)
+pub fn log_spectral_distance(reference: &[f32], estimate: &[f32]) -> Result<f64> {
+    let len = reference.len().min(estimate.len());
+    let config = StftConfig { center: false, window: WindowType::Hann, ..StftConfig::new(LSD_FFT_SIZE, LSD_FFT_SIZE / 2) };
+    let stft = Stft::new(config)?;
+    let r = power_spectrogram(&stft, &reference[..len]);
+    let e = power_spectrogram(&stft, &estimate[..len]);
+    if r.nrows() == 0 {
+        return Ok(0.0);
+    }
+    let per_frame: Vec<f64> = r.rows().into_iter().zip(e.rows()).map(|(rr, er)| {
+        let sum: f64 = rr.iter().zip(er.iter())
+            .map(|(&a, &b)| (10.0 * ((a as f64 + EPS) / (b as f64 + EPS)).log10()).powi(2))
+            .sum();
+        (sum / rr.len() as f64).sqrt()
+    }).collect();
+    Ok(per_frame.iter().sum::<f64>() / per_frame.len() as f64)
+}
+
+/// Drop frames more than 40 dB below the loudest reference frame, from both signals
+fn remove_silent_frames(x: &[f32], y: &[f32]) -> (Vec<f32>, Vec<f32>) {
+    let (frame, hop) = (STOI_FRAME, STOI_FRAME / 2);
+    let window = hann(frame);
+    let len = x.len().min(y.len());
+    if len < frame {
+        return (x[..len].to_vec(), y[..len].to_vec());
+    }
+    // As in the reference, no frame ends on the last sample
+    let starts: Vec<usize> = (0..len - frame).step_by(hop).collect();
+    let frame_db = |s: usize| {
+        let e: f64 = (0..frame).map(|i| ((x[s + i] * window[i]) as f64).powi(2)).sum();
+        20.0 * (e.sqrt() + EPS).log10()
+    };
+    let energies: Vec<f64> = starts.iter().map(|&s| frame_db(s)).collect();
+    let max = energies.iter().copied().fold(f64::MIN, f64::max);
+    let kept: Vec<usize> = starts.iter().zip(&energies).filter(|(_, e)| **e > max - STOI_DYN_RANGE_DB).map(|(&s, _)| s).collect();
+
+    let out_len = if kept.is_empty() { 0 } else { (kept.len() - 1) * hop + frame };
+    let (mut xs, mut ys) = (vec![0.0f32; out_len], vec![0.0f32; out_len]);
+    for (j, &s) in kept.iter().enumerate() {
+        for i in 0..frame {
+            xs[j * hop + i] += x[s + i] * window[i];
+            ys[j * hop + i] += y[s + i] * window[i];
+        }
+    }
+    (xs, ys)
+}
+
+/// One-third octave band envelopes, shape (bands, frames)
+fn third_octave_envelopes(stft: &Stft, x: &[f32]) -> Array2<f64> {
+    let spec = power_spectrogram(stft, x);
+    let bins = STOI_FFT / 2 + 1;
+    let bin_freq = |k: usize| k as f64 * STOI_FS as f64 / STOI_FFT as f64;
+    let nearest = |f: f64| (0..bins).min_by(|&a, &b| (bin_freq(a) - f).abs().total_cmp(&(bin_freq(b) - f).abs())).unwrap_or(0);
+
+    let mut out = Array2::zeros((STOI_BANDS, spec.nrows()));
+    for band in 0..STOI_BANDS {
+        let low = nearest(STOI_MIN_FREQ * 2f64.powf((2.0 * band as f64 - 1.0) / 6.0));
+        let high = nearest(STOI_MIN_FREQ * 2f64.powf((2.0 * band as f64 + 1.0) / 6.0));
+        for (t, row) in spec.rows().into_iter().enumerate() {
+            out[[band, t]] = (low..high).map(|k| row[k] as f64).sum::<f64>().sqrt();
+        }
+    }
+    out
+}
+
+fn resample(x: &[f32], from: u32, to: u32) -> Result<Vec<f32>> {
+    if from == to {
+        return Ok(x.to_vec());
+    }
+    let audio = WavAudio::new_mono(from, Array1::from(x.to_vec()), AudioFormat::Float32);
+    Ok(AudioConverter::convert_sample_rate(&audio, to)?.data().to_mono().to_vec())
+}
+
+fn normalize_vector(v: &mut [f64]) {
+    let mean = v.iter().sum::<f64>() / v.len().max(1) as f64;
+    v.iter_mut().for_each(|x| *x -= mean);
+    let norm = v.iter().map(|x| x * x).sum::<f64>().sqrt() + EPS;
+    v.iter_mut().for_each(|x| *x /= norm);
+}
+
+/// Short-time objective intelligibility (STOI), or extended STOI when `extended` is set.
+///
+/// Returns a value in roughly [0, 1]; signals shorter than one 384 ms analysis segment
+/// (after silence removal) yield 1e-5 as in the reference implementation.
+pub fn stoi(reference: &[f32], estimate: &[f32], sample_rate: u32, extended: bool) -> Result<f64> {
+    let len = reference.len().min(estimate.len());
+    let x = resample(&reference[..len], sample_rate, STOI_FS)?;
+    let y = resample(&estimate[..len], sample_rate, STOI_FS)?;
+    let (x, y) = remove_silent_frames(&x, &y);
+
+    let config = StftConfig { win_length: STOI_FRAME, center: false, ..StftConfig::new(STOI_FFT, STOI_FRAME / 2) };
+    let stft = Stft::with_window(config, hann(STOI_FRAME))?;
+    // Without the last sample the STFT frames match the reference's `range(0, len - frame, hop)`
+    let x_tob = third_octave_envelopes(&stft, &x[..x.len().saturating_sub(1)]);
+    let y_tob = third_octave_envelopes(&stft, &y[..y.len().saturating_sub(1)]);
+    let frames = x_tob.ncols();
+    if frames < STOI_SEGMENT {
+        log::warn!("STOI: not enough frames ({}) for a {}-frame segment", frames, STOI_SEGMENT);
+        return Ok(1e-5);
+    }
+
+    let clip = 10f64.powf(-STOI_BETA_DB / 20.0);
+    let segments = frames - STOI_SEGMENT + 1;
+    let mut total = 0.0;
+
+    for m in 0..segments {
+        let window = m..m + STOI_SEGMENT;
+        let mut xs: Vec<Vec<f64>> = (0..STOI_BANDS).map(|b| window.clone().map(|t| x_tob[[b, t]]).collect()).collect();
+        let mut ys: Vec<Vec<f64>> = (0..STOI_BANDS).map(|b| window.clone().map(|t| y_tob[[b, t]]).collect()).collect();
+
+        if extended {
+            // Row (time) normalization, then column (band) normalization
+            xs.iter_mut().for_each(|r| normalize_vector(r));
+            ys.iter_mut().for_each(|r| normalize_vector(r));
+            for t in 0..STOI_SEGMENT {
+                let mut xc: Vec<f64> = xs.iter().map(|r| r[t]).collect();
+                let mut yc: Vec<f64> = ys.iter().map(|r| r[t]).collect();
+                normalize_vector(&mut xc);
+                normalize_vector(&mut yc);
+                total += xc.iter().zip(&yc).map(|(a, b)| a * b).sum::<f64>() / STOI_SEGMENT as f64;
+            }
+        } else {
+            for (xr, yr) in xs.iter_mut().zip(ys.iter_mut()) {
+                let scale = xr.iter().map(|v| v * v).sum::<f64>().sqrt() / (yr.iter().map(|v| v * v).sum::<f64>().sqrt() + EPS);
+                for (yv, &xv) in yr.iter_mut().zip(xr.iter()) {
+                    *yv = (*yv * scale).min(xv * (1.0 + clip));
+                }
+                normalize_vector(xr);
+                normalize_vector(yr);
+                total += xr.iter().zip(yr.iter()).map(|(a, b)| a * b).sum::<f64>() / STOI_BANDS as f64;
+            }
+        }
+    }
+
+    Ok(total / segments as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn speechlike(len: usize, sample_rate: u32) -> Vec<f32> {
+        (0..len).map(|i| {
+            let t = i as f32 / sample_rate as f32;
+            let envelope = 0.5 + 0.5 * (std::f32::consts::TAU * 3.0 * t).sin();
+            envelope * ((std::f32::consts::TAU * 220.0 * t).sin() * 0.4
+                + (std::f32::consts::TAU * 660.0 * t).sin() * 0.2
+                + (std::f32::consts::TAU * 1800.0 * t).sin() * 0.1)
+        }).collect()
+    }
+
+    fn noise(len: usize, amplitude: f32, seed: u64) -> Vec<f32> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..len).map(|_| rng.gen_range(-amplitude..amplitude)).collect()
+    }
+
+    /// `n` values in [-1, 1) from a 32-bit LCG (Numerical Recipes constants)
+    fn lcg(seed: u32, n: usize) -> Vec<f64> {
+        let mut state = seed;
+        (0..n).map(|_| {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            (state >> 8) as f64 / 16777216.0 * 2.0 - 1.0
+        }).collect()
+    }
+
+    /// Modulated noise at 10 kHz, silent for the first 0.3 s, and a copy with added noise
+    fn stoi_signals() -> (Vec<f32>, Vec<f32>) {
+        let (u1, u2) = (lcg(1, 20000), lcg(2, 20000));
+        (0..20000).map(|i| {
+            let env = 0.55 + 0.45 * (std::f64::consts::TAU * 4.0 * i as f64 / 10000.0).sin();
+            let clean = (0.5 * u1[i] * env * if i < 3000 { 0.001 } else { 1.0 }) as f32;
+            (clean, clean + (0.25 * u2[i]) as f32)
+        }).unzip()
+    }
+
+    #[test]
+    fn test_stoi_reference_values() {
+        // From a line-by-line Python transcription of pystoi 0.3 on the same samples; at
+        // 10 kHz no resampling is involved, so only float precision separates the two
+        let (clean, noisy) = stoi_signals();
+        let (s, e) = (stoi(&clean, &noisy, 10000, false).unwrap(), stoi(&clean, &noisy, 10000, true).unwrap());
+        assert!((s - 0.811549).abs() < 1e-4, "{}", s);
+        assert!((e - 0.484473).abs() < 1e-4, "{}", e);
+    }
+
+    #[test]
+    fn test_lsd_known_values() {
+        // A constant gain g shifts every bin by 20*log10(g) dB: 6.0206 dB for 2x, 20 dB for 0.1x
+        let (clean, _) = stoi_signals();
+        let clean = &clean[3000..];
+        let louder: Vec<f32> = clean.iter().map(|v| v * 2.0).collect();
+        let quieter: Vec<f32> = clean.iter().map(|v| v * 0.1).collect();
+        assert!((log_spectral_distance(clean, &louder).unwrap() - 6.0206).abs() < 1e-3);
+        assert!((log_spectral_distance(clean, &quieter).unwrap() - 20.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_snr_known_value() {
+        // Constant 1.0 reference, error of 0.1 everywhere: 10*log10(1 / 0.01) = 20 dB
+        let reference = vec![1.0f32; 1000];
+        let estimate = vec![1.1f32; 1000];
+        assert!((snr(&reference, &estimate) - 20.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_si_sdr_is_scale_invariant() {
+        // sin and cos over whole periods are orthogonal: SI-SDR = 10*log10(0.5^2 / 0.05^2) = 20 dB
+        let n = 1600;
+        let s: Vec<f32> = (0..n).map(|i| (std::f32::consts::TAU * 10.0 * i as f32 / n as f32).sin()).collect();
+        let c: Vec<f32> = (0..n).map(|i| (std::f32::consts::TAU * 10.0 * i as f32 / n as f32).cos()).collect();
+        let estimate: Vec<f32> = s.iter().zip(&c).map(|(a, b)| 0.5 * a + 0.05 * b).collect();
+        assert!((si_sdr(&s, &estimate) - 20.0).abs() < 1e-2);
+        let scaled: Vec<f32> = estimate.iter().map(|v| v * 3.0).collect();
+        assert!((si_sdr(&s, &scaled) - si_sdr(&s, &estimate)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_segmental_snr_and_lsd_identity() {
+        let x = speechlike(16000, 16000);
+        assert_eq!(segmental_snr(&x, &x, 16000), SEG_SNR_RANGE.1);
+        assert!(log_spectral_distance(&x, &x).unwrap() < 1e-6);
+        let noisy: Vec<f32> = x.iter().zip(noise(16000, 0.2, 1)).map(|(a, b)| a + b).collect();
+        assert!(log_spectral_distance(&x, &noisy).unwrap() > 1.0);
+    }
+
+    #[test]
+    fn test_stoi_reference_behaviour() {
+        // Broadband, syllable-rate modulated noise so every third-octave band carries signal
+        let x: Vec<f32> = noise(32000, 0.5, 9).iter().enumerate()
+            .map(|(i, v)| v * (0.55 + 0.45 * (std::f32::consts::TAU * 4.0 * i as f32 / 16000.0).sin()))
+            .collect();
+        assert!((stoi(&x, &x, 16000, false).unwrap() - 1.0).abs() < 1e-6);
+        assert!((stoi(&x, &x, 16000, true).unwrap() - 1.0).abs() < 1e-6);
+
+        let light: Vec<f32> = x.iter().zip(noise(32000, 0.02, 2)).map(|(a, b)| a + b).collect();
+        let heavy: Vec<f32> = x.iter().zip(noise(32000, 0.8, 3)).map(|(a, b)| a + b).collect();
+        let (s_light, s_heavy) = (stoi(&x, &light, 16000, false).unwrap(), stoi(&x, &heavy, 16000, false).unwrap());
+        assert!(s_light > 0.9 && s_heavy < s_light, "{} {}", s_light, s_heavy);
+        assert!(stoi(&x, &heavy, 16000, true).unwrap() < stoi(&x, &light, 16000, true).unwrap());
+    }
+}
@@ -3,8 +3,8 @@
 use std::path::Path;
 use std::time::{Duration, Instant};
 use ndarray::ArrayD;
-use crate::error::Result;
-use super::{OnnxSession, SessionConfig, DynamicTensor};
+use crate::error::{Result, ZipEnhancerError};
+use super::{DynamicTensor, OnnxSession, PoolLimits, PooledSession, SessionConfig, SessionKey, SessionPool};
 
 /// Inference configuration
 #[derive(Debug, Clone)]
@@ -12,11 +12,13 @@ pub struct InferenceConfig {
     pub max_retries: u32,
     pub retry_delay_ms: u64,
     pub intra_threads: i32,
+    /// Limits of the session checkout from the process-wide pool
+    pub pool_limits: PoolLimits,
 }
 
 impl Default for InferenceConfig {
     fn default() -> Self {
-        Self { max_retries: 3, retry_delay_ms: 100, intra_threads: 1 }
+        Self { max_retries: 3, retry_delay_ms: 100, intra_threads: 1, pool_limits: PoolLimits::default() }
     }
 }
 
@@ -51,7 +53,7 @@ pub struct InferenceResult {
     pub outputs: Vec<ArrayD<f32>>,
     pub time_ms: u64,
     pub success: bool,
-    pub error: Option<String>,
+    pub error: Option<ZipEnhancerError>,
 }
 
 impl InferenceResult {
@@ -59,8 +61,8 @@ impl InferenceResult {
         Self { outputs, time_ms, success: true, error: None }
     }
 
-    pub fn err(msg: String, time_ms: u64) -> Self {
-        Self { outputs: vec![], time_ms, success: false, error: Some(msg) }
+    pub fn err(error: ZipEnhancerError, time_ms: u64) -> Self {
+        Self { outputs: vec![], time_ms, success: false, error: Some(error) }
     }
 
     pub fn first_output(&self) -> Option<&ArrayD<f32>> {
@@ -70,7 +72,8 @@ impl InferenceResult {
 
 /// Unified inference engine
 pub struct InferenceEngine {
-    session: OnnxSession,
+    /// Returned to the session pool when the engine is dropped
+    session: PooledSession,
     config: InferenceConfig,
     stats: InferenceStats,
 }
@@ -89,14 +92,14 @@ impl InferenceEngine {
     pub fn new(model_path: &Path, config: InferenceConfig) -> Result<Self> {
         let session_config = SessionConfig {
             intra_op_num_threads: config.intra_threads,
-            ..Default::default()
         };
-        let session = OnnxSession::new(model_path, session_config)?;
+        let session = SessionPool::global().checkout(model_path, session_config, config.pool_limits)?;
         Ok(Self { session, config, stats: InferenceStats::default() })
     }
 
-    pub fn from_session(session: OnnxSession, config: InferenceConfig) -> Self {
-        Self { session, config, stats: InferenceStats::default() }
+    /// Engine over a session that is not returned to the pool
+    pub fn from_session(session: OnnxSession, key: SessionKey, config: InferenceConfig) -> Self {
+        Self { session: PooledSession::detached(session, key), config, stats: InferenceStats::default() }
     }
 
     pub fn run(&mut self, inputs: Vec<DynamicTensor>) -> Result<InferenceResult> {
@@ -107,25 +110,27 @@ impl InferenceEngine {
             if attempt > 0 {
                 std::thread::sleep(Duration::from_millis(self.config.retry_delay_ms));
                 log::warn!("Retry {}/{}", attempt, self.config.max_retries);
+                crate::telemetry::metrics().inference_retries.inc();
             }
 
             match self.session.run(inputs.clone()) {
                 Ok(outputs) => {
                     let time_ms = start.elapsed().as_millis() as u64;
                     self.stats.record(time_ms, true);
-                    let arrays = outputs.into_iter().map(|t| t.into_ndarray()).collect();
+                    let arrays = outputs.into_iter().map(|t| t.into_ndarray()).collect::<Result<Vec<_>>>()?;
                     return Ok(InferenceResult::ok(arrays, time_ms));
                 }
                 Err(e) => {
-                    last_err = Some(e.to_string());
-                    log::error!("Inference failed (attempt {}): {}", attempt + 1, last_err.as_ref().unwrap());
+                    log::error!("Inference failed (attempt {}): {}", attempt + 1, e.report());
+                    last_err = Some(e);
                 }
             }
         }
 
         let time_ms = start.elapsed().as_millis() as u64;
         self.stats.record(time_ms, false);
-        Ok(InferenceResult::err(last_err.unwrap_or_else(|| "Unknown error".into()), time_ms))
+        crate::telemetry::metrics().inference_failures.inc();
+        Ok(InferenceResult::err(last_err.expect("at least one attempt"), time_ms))
     }
 
     pub fn warm_up(&mut self) -> Result<()> {
@@ -2,6 +2,8 @@
 
 pub mod session;
 pub mod inference;
+pub mod pool;
 
 pub use session::{OnnxSession, SessionConfig, DynamicTensor};
 pub use inference::{InferenceEngine, InferenceConfig, InferenceResult};
+pub use pool::{PoolLimits, PoolStats, PooledSession, SessionKey, SessionPool};
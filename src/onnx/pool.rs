@@ -0,0 +1,352 @@
+//! Session Pool
+//!
+//! Loading a model is slow, so sessions are kept per model file and session options
+//! and handed out again after use. Limits are given with every checkout, so owners
+//! with different limits can share a key without overriding each other: a checkout
+//! creates a session while fewer than its `max_sessions` of the key are in use, and a
+//! returned session is closed beyond its `max_idle` or once idle for longer than its
+//! `idle_timeout`. Idle sessions are expired on every checkout and return.
+
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::error::{ZipEnhancerError, Result};
+use crate::telemetry;
+use super::{OnnxSession, SessionConfig};
+
+/// Sessions created from the same key are interchangeable
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionKey {
+    pub model_path: PathBuf,
+    pub config: SessionConfig,
+}
+
+impl SessionKey {
+    /// Relative paths and symlinks to the same model share a key
+    pub fn new(model_path: &Path, config: SessionConfig) -> Self {
+        Self { model_path: std::fs::canonicalize(model_path).unwrap_or_else(|_| model_path.to_path_buf()), config }
+    }
+}
+
+/// Size limits of one checkout, applied to its key
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolLimits {
+    /// Sessions checked out or idle
+    pub max_sessions: usize,
+    /// Idle sessions kept for reuse; workers return their sessions after every file,
+    /// so fewer than the worker count means reloading the model for every file
+    pub max_idle: usize,
+    /// Idle sessions unused for this long are closed
+    pub idle_timeout: Duration,
+    /// How long a checkout waits for a session once `max_sessions` are in use
+    pub checkout_timeout: Duration,
+}
+
+impl Default for PoolLimits {
+    fn default() -> Self {
+        Self { max_sessions: 16, max_idle: 16, idle_timeout: Duration::from_secs(300), checkout_timeout: Duration::from_secs(30) }
+    }
+}
+
+/// Session counts over all keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub idle: usize,
+    pub in_use: usize,
+}
+
+/// Idle item, when it was returned and its idle timeout
+type Idle<T> = (T, Instant, Duration);
+
+struct Slot<T> {
+    idle: Vec<Idle<T>>,
+    /// Checked out or being created
+    in_use: usize,
+}
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self { Self { idle: Vec::new(), in_use: 0 } }
+}
+
+struct State<T> {
+    slots: HashMap<SessionKey, Slot<T>>,
+}
+
+impl<T> State<T> {
+    /// Take the idle sessions past their timeout, to be closed outside the lock
+    fn expire(&mut self, now: Instant) -> Vec<T> {
+        let mut expired = Vec::new();
+        for slot in self.slots.values_mut() {
+            let (keep, old) = std::mem::take(&mut slot.idle).into_iter().partition(|(_, since, timeout)| now.duration_since(*since) < *timeout);
+            slot.idle = keep;
+            expired.extend(old.into_iter().map(|(item, _, _)| item));
+        }
+        self.slots.retain(|_, slot| slot.in_use > 0 || !slot.idle.is_empty());
+        expired
+    }
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    returned: Condvar,
+    /// Report counts in the process metrics
+    telemetry: bool,
+}
+
+/// Keyed pool of reusable items; see [`SessionPool`]
+pub struct Pool<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for Pool<T> {
+    fn clone(&self) -> Self { Self { shared: self.shared.clone() } }
+}
+
+impl<T> std::fmt::Debug for Pool<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pool").field("stats", &self.stats()).finish()
+    }
+}
+
+/// Pool of ONNX sessions
+pub type SessionPool = Pool<OnnxSession>;
+/// ONNX session checked out of a [`SessionPool`]
+pub type PooledSession = Pooled<OnnxSession>;
+
+impl SessionPool {
+    /// The pool used by every [`InferenceEngine`](super::InferenceEngine) of this process
+    pub fn global() -> &'static SessionPool {
+        static POOL: OnceLock<SessionPool> = OnceLock::new();
+        POOL.get_or_init(|| Self::with_telemetry(true))
+    }
+
+    /// An idle session for `model_path` and `config`, or a new one while under `limits`
+    pub fn checkout(&self, model_path: &Path, config: SessionConfig, limits: PoolLimits) -> Result<PooledSession> {
+        let key = SessionKey::new(model_path, config.clone());
+        self.acquire(key, limits, || OnnxSession::new(model_path, config))
+    }
+}
+
+impl<T> Default for Pool<T> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T> Pool<T> {
+    pub fn new() -> Self { Self::with_telemetry(false) }
+
+    fn with_telemetry(telemetry: bool) -> Self {
+        let state = State { slots: HashMap::new() };
+        Self { shared: Arc::new(Shared { state: Mutex::new(state), returned: Condvar::new(), telemetry }) }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State<T>> {
+        self.shared.state.lock().expect("pool lock")
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.lock().slots.values().fold(PoolStats::default(), |s, slot| PoolStats { idle: s.idle + slot.idle.len(), in_use: s.in_use + slot.in_use })
+    }
+
+    /// Close every idle session, e.g. after a model file was replaced
+    pub fn clear(&self) {
+        let closed: Vec<T> = self.lock().slots.values_mut().flat_map(|slot| slot.idle.drain(..).map(|(item, _, _)| item)).collect();
+        self.record(-(closed.len() as i64), 0, 0);
+    }
+
+    fn record(&self, idle: i64, in_use: i64, created: u64) {
+        if !self.shared.telemetry { return; }
+        let metrics = telemetry::metrics();
+        metrics.sessions_idle.add(idle);
+        metrics.sessions_in_use.add(in_use);
+        if created > 0 { metrics.sessions_created.add(created as f64); }
+    }
+
+    /// Reuse an idle item of `key`, create one with `create` while fewer than
+    /// `limits.max_sessions` are in use, or wait for one to be returned
+    pub(crate) fn acquire(&self, key: SessionKey, limits: PoolLimits, create: impl FnOnce() -> Result<T>) -> Result<Pooled<T>> {
+        let start = Instant::now();
+        // Declared before the guard so expired sessions are closed after it is released
+        let mut expired = Vec::new();
+        let mut state = self.lock();
+        loop {
+            let now = Instant::now();
+            let before = expired.len();
+            expired.extend(state.expire(now));
+            self.record(before as i64 - expired.len() as i64, 0, 0);
+            let slot = state.slots.entry(key.clone()).or_default();
+            if let Some((item, _, _)) = slot.idle.pop() {
+                slot.in_use += 1;
+                self.record(-1, 1, 0);
+                return Ok(Pooled { pool: Some(self.clone()), key, limits, item: Some(item) });
+            }
+            if slot.in_use < limits.max_sessions {
+                slot.in_use += 1;
+                drop(state);
+                return match create() {
+                    Ok(item) => {
+                        self.record(0, 1, 1);
+                        Ok(Pooled { pool: Some(self.clone()), key, limits, item: Some(item) })
+                    }
+                    Err(e) => {
+                        self.release(&key, limits, None);
+                        Err(e)
+                    }
+                };
+            }
+            let Some(wait) = limits.checkout_timeout.checked_sub(now.duration_since(start)).filter(|w| !w.is_zero()) else {
+                return Err(ZipEnhancerError::processing(format!(
+                    "No session free for {} after {:?}: all {} in use", key.model_path.display(), limits.checkout_timeout, limits.max_sessions)));
+            };
+            state = self.shared.returned.wait_timeout(state, wait).expect("pool lock").0;
+        }
+    }
+
+    /// Give back an item checked out with `limits`; `None` when its creation failed
+    fn release(&self, key: &SessionKey, limits: PoolLimits, item: Option<T>) {
+        let mut state = self.lock();
+        let expired = state.expire(Instant::now());
+        self.record(-(expired.len() as i64), 0, 0);
+        let slot = state.slots.entry(key.clone()).or_default();
+        slot.in_use = slot.in_use.saturating_sub(1);
+        let returned = item.is_some();
+        // Beyond `max_idle` the item is closed once the lock is released
+        let closed = match item {
+            Some(item) if slot.idle.len() < limits.max_idle => {
+                slot.idle.push((item, Instant::now(), limits.idle_timeout));
+                self.record(1, -1, 0);
+                None
+            }
+            item => {
+                if returned { self.record(0, -1, 0); }
+                item
+            }
+        };
+        drop(state);
+        // Waiters may have different limits, so any of them could be the one to proceed
+        self.shared.returned.notify_all();
+        drop((closed, expired));
+    }
+}
+
+/// Item checked out of a [`Pool`]; returned to it on drop
+pub struct Pooled<T> {
+    pool: Option<Pool<T>>,
+    key: SessionKey,
+    /// Limits it was checked out with, applied again on return
+    limits: PoolLimits,
+    item: Option<T>,
+}
+
+impl<T> Pooled<T> {
+    /// An item that belongs to no pool and is simply dropped
+    pub fn detached(item: T, key: SessionKey) -> Self { Self { pool: None, key, limits: PoolLimits::default(), item: Some(item) } }
+
+    pub fn key(&self) -> &SessionKey { &self.key }
+}
+
+impl<T> Deref for Pooled<T> {
+    type Target = T;
+    fn deref(&self) -> &T { self.item.as_ref().expect("pooled item") }
+}
+
+impl<T> DerefMut for Pooled<T> {
+    fn deref_mut(&mut self) -> &mut T { self.item.as_mut().expect("pooled item") }
+}
+
+impl<T> std::fmt::Debug for Pooled<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pooled").field("key", &self.key).field("pooled", &self.pool.is_some()).finish()
+    }
+}
+
+impl<T> Drop for Pooled<T> {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.release(&self.key, self.limits, self.item.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn key(name: &str) -> SessionKey {
+        SessionKey::new(Path::new(name), SessionConfig::default())
+    }
+
+    #[test]
+    fn test_reuses_and_shrinks() {
+        let pool = Pool::new();
+        let limits = PoolLimits { max_sessions: 4, max_idle: 1, ..Default::default() };
+        let created = AtomicUsize::new(0);
+        let create = || Ok(created.fetch_add(1, Ordering::SeqCst));
+
+        let (a, b) = (pool.acquire(key("m.onnx"), limits, create).unwrap(), pool.acquire(key("m.onnx"), limits, create).unwrap());
+        let other = pool.acquire(SessionKey::new(Path::new("m.onnx"), SessionConfig { intra_op_num_threads: 2 }), limits, create).unwrap();
+        assert_eq!((*a, *b, *other), (0, 1, 2));
+        assert_eq!(pool.stats(), PoolStats { idle: 0, in_use: 3 });
+
+        // Only one idle session per key is kept
+        drop((a, b, other));
+        assert_eq!(pool.stats(), PoolStats { idle: 2, in_use: 0 });
+        assert_eq!(*pool.acquire(key("m.onnx"), limits, create).unwrap(), 0);
+        assert_eq!(created.load(Ordering::SeqCst), 3);
+
+        // Returned with a zero idle timeout, the session is closed on the next checkout
+        drop(pool.acquire(key("m.onnx"), PoolLimits { idle_timeout: Duration::ZERO, ..limits }, create).unwrap());
+        let _fresh = pool.acquire(key("n.onnx"), limits, create).unwrap();
+        assert_eq!(pool.stats(), PoolStats { idle: 1, in_use: 1 });
+
+        assert!(pool.acquire(key("x.onnx"), limits, || Err(ZipEnhancerError::processing("broken"))).is_err());
+        assert_eq!(pool.stats().in_use, 1);
+    }
+
+    #[test]
+    fn test_return_expires_idle_sessions() {
+        let pool = Pool::new();
+        let limits = PoolLimits { idle_timeout: Duration::from_millis(20), ..Default::default() };
+        let (a, b) = (pool.acquire(key("m.onnx"), limits, || Ok(1)).unwrap(), pool.acquire(key("n.onnx"), limits, || Ok(2)).unwrap());
+        drop(a);
+        std::thread::sleep(Duration::from_millis(40));
+        // Without another checkout, returning `b` closes the session idle since `a` was returned
+        drop(b);
+        assert_eq!(pool.stats(), PoolStats { idle: 1, in_use: 0 });
+        assert_eq!(*pool.acquire(key("m.onnx"), limits, || Ok(3)).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_waits_at_the_limit() {
+        let pool = Pool::new();
+        let limits = PoolLimits { max_sessions: 1, checkout_timeout: Duration::from_millis(50), ..Default::default() };
+        let held = pool.acquire(key("m.onnx"), limits, || Ok(7)).unwrap();
+        assert!(pool.acquire(key("m.onnx"), limits, || Ok(8)).is_err());
+
+        // A session returned while waiting is handed over
+        let waiter = {
+            let pool = pool.clone();
+            let limits = PoolLimits { checkout_timeout: Duration::from_secs(10), ..limits };
+            std::thread::spawn(move || *pool.acquire(key("m.onnx"), limits, || Ok(9)).unwrap())
+        };
+        std::thread::sleep(Duration::from_millis(20));
+        drop(held);
+        assert_eq!(waiter.join().unwrap(), 7);
+    }
+
+    #[test]
+    fn test_limits_apply_per_checkout() {
+        let pool = Pool::new();
+        let small = PoolLimits { max_sessions: 2, checkout_timeout: Duration::ZERO, ..Default::default() };
+        let large = PoolLimits { max_sessions: 8, checkout_timeout: Duration::ZERO, ..Default::default() };
+        let held: Vec<_> = (0..2).map(|i| pool.acquire(key("m.onnx"), small, || Ok(i)).unwrap()).collect();
+
+        // The smaller limit on the same key does not hold back a checkout with a larger one
+        assert!(pool.acquire(key("m.onnx"), small, || Ok(2)).is_err());
+        let more: Vec<_> = (2..8).map(|i| pool.acquire(key("m.onnx"), large, || Ok(i)).unwrap()).collect();
+        assert!(pool.acquire(key("m.onnx"), large, || Ok(8)).is_err());
+        assert_eq!(pool.stats().in_use, held.len() + more.len());
+    }
+}
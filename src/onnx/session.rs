@@ -1,12 +1,13 @@
 //! ONNX Runtime Session - Simplified
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 use ndarray::ArrayD;
 use onnxruntime::{environment::Environment, session::Session, GraphOptimizationLevel, LoggingLevel};
 use crate::error::{Result, ZipEnhancerError};
 
 /// Session configuration
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct SessionConfig {
     pub intra_op_num_threads: i32,
 }
@@ -18,6 +19,13 @@ pub struct DynamicTensor {
     shape: Vec<i64>,
 }
 
+/// Array of `shape` over `data`, or `ShapeMismatch` if the element counts differ
+fn shaped<T>(shape: &[i64], data: Vec<T>) -> Result<ArrayD<T>> {
+    let shape: Vec<usize> = shape.iter().map(|&x| x as usize).collect();
+    let len = data.len();
+    ArrayD::from_shape_vec(shape.clone(), data).map_err(|_| ZipEnhancerError::ShapeMismatch { shape, len })
+}
+
 #[derive(Debug, Clone)]
 enum TensorData {
     Float32(Vec<f32>),
@@ -31,22 +39,20 @@ impl DynamicTensor {
 
     pub fn shape(&self) -> &[i64] { &self.shape }
 
-    pub fn into_ndarray(self) -> ArrayD<f32> {
-        let shape: Vec<usize> = self.shape.iter().map(|&x| x as usize).collect();
+    pub fn into_ndarray(self) -> Result<ArrayD<f32>> {
         let data = match self.data {
             TensorData::Float32(d) => d,
             TensorData::Int16(d) => d.into_iter().map(|x| x as f32 / 32767.0).collect(),
         };
-        ArrayD::from_shape_vec(shape, data).expect("Shape mismatch")
+        shaped(&self.shape, data)
     }
 
-    pub fn into_i16_ndarray(self) -> ArrayD<i16> {
-        let shape: Vec<usize> = self.shape.iter().map(|&x| x as usize).collect();
+    pub fn into_i16_ndarray(self) -> Result<ArrayD<i16>> {
         let data = match self.data {
             TensorData::Int16(d) => d,
             TensorData::Float32(d) => d.into_iter().map(|x| (x.clamp(-1.0, 1.0) * 32767.0) as i16).collect(),
         };
-        ArrayD::from_shape_vec(shape, data).expect("Shape mismatch")
+        shaped(&self.shape, data)
     }
 
     fn new_f32(data: Vec<f32>, shape: Vec<i64>) -> Self {
@@ -54,13 +60,34 @@ impl DynamicTensor {
     }
 }
 
-/// ONNX inference session
+/// The process-wide ONNX Runtime environment, created on first use and shared by every session
+fn environment() -> Result<&'static Environment> {
+    static ENVIRONMENT: OnceLock<Environment> = OnceLock::new();
+    static INIT: Mutex<()> = Mutex::new(());
+    if let Some(env) = ENVIRONMENT.get() {
+        return Ok(env);
+    }
+    let _init = INIT.lock().expect("environment lock");
+    if let Some(env) = ENVIRONMENT.get() {
+        return Ok(env);
+    }
+    let env = Environment::builder()
+        .with_name("zipenhancer")
+        .with_log_level(LoggingLevel::Warning)
+        .build()
+        .map_err(|source| ZipEnhancerError::Runtime { context: "environment", source })?;
+    Ok(ENVIRONMENT.get_or_init(|| env))
+}
+
+/// ONNX inference session; see [`SessionPool`](super::SessionPool) for reusing sessions
 pub struct OnnxSession {
     session: Session<'static>,
-    #[allow(dead_code)]
-    env_holder: Box<Environment>,
 }
 
+// SAFETY: ONNX Runtime sessions are not tied to the thread that created them; the raw
+// pointers are only used through `&mut self`, so moving the owner is sound.
+unsafe impl Send for OnnxSession {}
+
 impl std::fmt::Debug for OnnxSession {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("OnnxSession").finish()
@@ -70,34 +97,28 @@ impl std::fmt::Debug for OnnxSession {
 impl OnnxSession {
     pub fn new(model_path: &Path, config: SessionConfig) -> Result<Self> {
         if !model_path.exists() {
-            return Err(ZipEnhancerError::onnx(format!("Model not found: {}", model_path.display())));
+            return Err(ZipEnhancerError::ModelNotFound { path: model_path.to_path_buf() });
         }
 
-        let env = Environment::builder()
-            .with_name("zipenhancer")
-            .with_log_level(LoggingLevel::Warning)
-            .build()
-            .map_err(|e| ZipEnhancerError::onnx(format!("Environment error: {}", e)))?;
-
-        let env_box = Box::new(env);
-        let env_ref: &'static Environment = unsafe { &*(env_box.as_ref() as *const Environment) };
-        let model_path_static: &'static Path = Box::leak(model_path.to_path_buf().into_boxed_path());
-
-        let session = env_ref.new_session_builder()?
+        let session = environment()?.new_session_builder()?
             .with_optimization_level(GraphOptimizationLevel::All)?
             .with_number_threads(config.intra_op_num_threads.max(1) as i16)?
-            .with_model_from_file(model_path_static)?;
+            // Owned, as the session borrows the path for its whole lifetime
+            .with_model_from_file(PathBuf::from(model_path))?;
 
         log::info!("ONNX session created");
 
-        Ok(Self { session, env_holder: env_box })
+        Ok(Self { session })
     }
 
     pub fn run(&mut self, inputs: Vec<DynamicTensor>) -> Result<Vec<DynamicTensor>> {
-        let input_arrays: Vec<ArrayD<i16>> = inputs.into_iter().map(|t| t.into_i16_ndarray()).collect();
+        let _span = tracing::info_span!("infer").entered();
+        let start = std::time::Instant::now();
+        let input_arrays = inputs.into_iter().map(|t| t.into_i16_ndarray()).collect::<Result<Vec<_>>>()?;
 
         let outputs = self.session.run(input_arrays)
-            .map_err(|e| ZipEnhancerError::onnx(format!("Inference failed: {}", e)))?;
+            .map_err(|source| ZipEnhancerError::Runtime { context: "inference", source })?;
+        crate::telemetry::metrics().inference_seconds.observe(start.elapsed().as_secs_f64());
 
         Ok(outputs.into_iter().map(|tensor| {
             let shape: Vec<i64> = tensor.shape().iter().map(|&x| x as i64).collect();
@@ -115,9 +136,12 @@ mod tests {
     fn test_dynamic_tensor() {
         let t = DynamicTensor::new_i16(vec![16384, -16384], vec![1, 2]);
         assert_eq!(t.shape(), &[1, 2]);
-        let arr = t.into_ndarray();
+        let arr = t.into_ndarray().unwrap();
         assert_eq!(arr.len(), 2);
         assert!((arr[[0, 0]] - 0.5).abs() < 0.01);
+
+        let bad = DynamicTensor::new_i16(vec![0; 3], vec![1, 1, 4]);
+        assert!(matches!(bad.into_i16_ndarray(), Err(ZipEnhancerError::ShapeMismatch { len: 3, .. })));
     }
 
     #[test]
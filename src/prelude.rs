@@ -0,0 +1,17 @@
+//! Common imports for library users
+//!
+//! ```no_run
+//! use zipenhancer::prelude::*;
+//!
+//! let enhancer = Enhancer::builder().model("model/ZipEnhancer.onnx").build()?;
+//! enhancer.process_file("noisy.wav".as_ref(), "clean.wav".as_ref())?;
+//! # Ok::<(), ZipEnhancerError>(())
+//! ```
+
+pub use crate::audio::{AudioFormat, WavAudio};
+pub use crate::config::Config;
+pub use crate::error::{ErrorCode, ErrorKind, Result, ZipEnhancerError};
+pub use crate::processing::{
+    CancellationToken, DenoiseBackend, Enhancer, EnhancerBuilder, Execution, NormalizationPolicy,
+    ProcessingResult, Progress, ProgressObserver, Stage,
+};
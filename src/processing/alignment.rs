@@ -0,0 +1,289 @@
+//! Output Alignment - Model delay compensation and exact-length restoration
+
+use ndarray::{s, Array1};
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde::{Deserialize, Serialize};
+use crate::audio::{AudioConverter, AudioFormat, WavAudio};
+use crate::config::AlignmentConfig;
+use crate::error::Result;
+
+/// Minimum normalized correlation for a measured delay to be trusted
+const MIN_CORRELATION: f32 = 0.6;
+/// How far the correlation peak must stand above any peak outside its main lobe
+const MIN_PEAK_MARGIN: f32 = 0.15;
+/// Length of the analysis window used for delay measurement (samples)
+const ANALYSIS_WINDOW: usize = 65536;
+
+/// How the model delay is determined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DelayMode {
+    /// Assume the model introduces no shift
+    None,
+    /// Use `declared_delay_samples` from the config
+    #[default]
+    Declared,
+    /// Estimate the shift by cross-correlating input and output; only applied when
+    /// the correlation peak is unambiguous
+    Measured,
+}
+
+/// Original input geometry the output must match
+#[derive(Debug, Clone, Copy)]
+pub struct AlignmentTarget {
+    pub sample_rate: u32,
+    pub samples: usize,
+}
+
+/// Alignment applied to the output, reported in processing results
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlignmentInfo {
+    /// Delay removed from the output, in working-rate samples (positive = output lagged input)
+    pub delay_samples: i64,
+    pub delay_mode: DelayMode,
+    /// Peak normalized cross-correlation (measured mode only)
+    pub correlation: Option<f32>,
+    pub output_sample_rate: u32,
+    pub output_samples: usize,
+    pub padded_samples: usize,
+    pub trimmed_samples: usize,
+}
+
+/// Estimate the lag of `output` relative to `input` within `±max_lag` samples.
+///
+/// Returns `(lag, normalized_correlation)`; a positive lag means `output[n] ≈ input[n - lag]`.
+pub fn estimate_delay(input: &Array1<f32>, output: &Array1<f32>, max_lag: usize) -> (i64, f32) {
+    let (curve, max_lag) = cross_correlation(input, output, max_lag);
+    match peak(&curve) {
+        Some(best) => (best as i64 - max_lag as i64, curve[best]),
+        None => (0, 0.0),
+    }
+}
+
+/// Measured lag, or `None` when the correlation peak is too weak or not clearly above
+/// its side peaks (periodic signals correlate almost as well at pitch-period lags)
+fn measure_delay(input: &Array1<f32>, output: &Array1<f32>, max_lag: usize) -> (Option<i64>, f32) {
+    let (curve, max_lag) = cross_correlation(input, output, max_lag);
+    let Some(best) = peak(&curve) else { return (None, 0.0) };
+    let corr = curve[best];
+    let confident = corr >= MIN_CORRELATION && corr - side_peak(&curve, best) >= MIN_PEAK_MARGIN;
+    (confident.then_some(best as i64 - max_lag as i64), corr)
+}
+
+fn peak(curve: &[f32]) -> Option<usize> {
+    curve.iter().enumerate().filter(|(_, c)| **c > 0.0)
+        .max_by(|a, b| a.1.total_cmp(b.1)).map(|(i, _)| i)
+}
+
+/// Highest correlation outside the main lobe around `best`
+fn side_peak(curve: &[f32], best: usize) -> f32 {
+    let mut lo = best;
+    while lo > 0 && curve[lo - 1] < curve[lo] { lo -= 1; }
+    let mut hi = best;
+    while hi + 1 < curve.len() && curve[hi + 1] < curve[hi] { hi += 1; }
+    curve[..lo].iter().chain(&curve[hi + 1..]).copied().fold(0.0, f32::max)
+}
+
+/// Normalized cross-correlation over the analysis window for lags `-max_lag..=max_lag`,
+/// computed with one FFT; entry `k` is lag `k - max_lag`. Also returns the clamped `max_lag`.
+fn cross_correlation(input: &Array1<f32>, output: &Array1<f32>, max_lag: usize) -> (Vec<f32>, usize) {
+    let len = input.len().min(output.len());
+    if len == 0 {
+        return (Vec::new(), 0);
+    }
+    let (start, end) = analysis_window(input, len);
+    let max_lag = max_lag.min(len / 2);
+    let window = end - start;
+
+    // Output over the window extended by `max_lag` on both sides, zero beyond the signal
+    let span = window + 2 * max_lag;
+    let shifted: Vec<f64> = (0..span).map(|i| {
+        let m = (start + i) as i64 - max_lag as i64;
+        if m >= 0 && (m as usize) < len { output[m as usize] as f64 } else { 0.0 }
+    }).collect();
+
+    let size = span.next_power_of_two();
+    let mut planner = FftPlanner::<f64>::new();
+    let (forward, inverse) = (planner.plan_fft_forward(size), planner.plan_fft_inverse(size));
+    let mut x: Vec<Complex<f64>> = (0..size).map(|i| Complex::new(if i < window { input[start + i] as f64 } else { 0.0 }, 0.0)).collect();
+    let mut y: Vec<Complex<f64>> = (0..size).map(|i| Complex::new(shifted.get(i).copied().unwrap_or(0.0), 0.0)).collect();
+    forward.process(&mut x);
+    forward.process(&mut y);
+    let mut product: Vec<Complex<f64>> = x.iter().zip(&y).map(|(a, b)| a.conj() * b).collect();
+    inverse.process(&mut product);
+
+    // Energy of each shifted output window from a running sum of squares
+    let ex: f64 = (start..end).map(|n| (input[n] as f64).powi(2)).sum();
+    let mut squares = vec![0.0f64; span + 1];
+    for (i, y) in shifted.iter().enumerate() {
+        squares[i + 1] = squares[i] + y * y;
+    }
+    let curve = (0..=2 * max_lag).map(|k| {
+        let ey = squares[k + window] - squares[k];
+        if ex <= 0.0 || ey <= 0.0 { 0.0 } else { (product[k].re / size as f64 / (ex * ey).sqrt()) as f32 }
+    }).collect();
+    (curve, max_lag)
+}
+
+/// Pick the highest-energy window of the input so silence does not dominate the estimate
+fn analysis_window(input: &Array1<f32>, len: usize) -> (usize, usize) {
+    let window = ANALYSIS_WINDOW.min(len);
+    let step = (window / 4).max(1);
+    let mut best = (0, 0.0f32);
+    let mut start = 0;
+    while start + window <= len {
+        let energy: f32 = input.slice(s![start..start + window]).iter().map(|x| x * x).sum();
+        if energy > best.1 {
+            best = (start, energy);
+        }
+        start += step;
+    }
+    (best.0, best.0 + window)
+}
+
+/// Shift `data` left by `delay` samples (right if negative), keeping its length
+fn compensate_delay(data: &Array1<f32>, delay: i64) -> Array1<f32> {
+    let len = data.len();
+    let mut shifted = Array1::zeros(len);
+    for i in 0..len {
+        let src = i as i64 + delay;
+        if src >= 0 && (src as usize) < len {
+            shifted[i] = data[src as usize];
+        }
+    }
+    shifted
+}
+
+/// Determine the model delay: none, declared, or measured between the `(input, output)`
+/// pair returned by `signals`, which is only called in measured mode
+pub fn resolve_delay(
+    config: &AlignmentConfig,
+    working_rate: u32,
+    signals: impl FnOnce() -> Result<(Array1<f32>, Array1<f32>)>,
+) -> Result<AlignmentInfo> {
+    let mut info = AlignmentInfo { delay_mode: config.delay_mode, ..Default::default() };
+    match config.delay_mode {
+        DelayMode::None => {}
+        DelayMode::Declared => info.delay_samples = config.declared_delay_samples,
+        DelayMode::Measured => {
+            let (input, output) = signals()?;
+            let max_lag = (config.max_delay_ms / 1000.0 * working_rate as f32) as usize;
+            let (lag, corr) = measure_delay(&input, &output, max_lag);
+            info.delay_samples = lag.unwrap_or(0);
+            info.correlation = Some(corr);
+        }
+    }
+    Ok(info)
+}
+
+/// Remove the resolved delay from `output` and restore the original sample rate and
+/// exact length.
+///
+/// `output` is the reconstructed model output at the working rate and `working_len`
+/// the length of the prepared input fed to the model.
+pub fn restore_output(
+    output: Array1<f32>,
+    mut info: AlignmentInfo,
+    working_len: usize,
+    working_rate: u32,
+    target: AlignmentTarget,
+    config: &AlignmentConfig,
+) -> Result<(Array1<f32>, AlignmentInfo)> {
+    let mut data = if info.delay_samples != 0 {
+        compensate_delay(&output, info.delay_samples)
+    } else {
+        output
+    };
+
+    let (rate, expected) = if config.match_input_rate && target.sample_rate != working_rate {
+        let audio = WavAudio::new_mono(working_rate, data, AudioFormat::Float32);
+        data = AudioConverter::convert_sample_rate(&audio, target.sample_rate)?.data().to_mono();
+        (target.sample_rate, target.samples)
+    } else if config.match_input_rate {
+        (working_rate, target.samples)
+    } else {
+        (working_rate, working_len)
+    };
+
+    if data.len() > expected {
+        info.trimmed_samples = data.len() - expected;
+        data = data.slice(s![..expected]).to_owned();
+    } else if data.len() < expected {
+        info.padded_samples = expected - data.len();
+        let mut padded = Array1::zeros(expected);
+        padded.slice_mut(s![..data.len()]).assign(&data);
+        data = padded;
+    }
+
+    info.output_sample_rate = rate;
+    info.output_samples = data.len();
+    Ok((data, info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn align_output(input: &Array1<f32>, output: Array1<f32>, working_rate: u32, target: AlignmentTarget, config: &AlignmentConfig) -> Result<(Array1<f32>, AlignmentInfo)> {
+        let info = resolve_delay(config, working_rate, || Ok((input.clone(), output.clone())))?;
+        restore_output(output, info, input.len(), working_rate, target, config)
+    }
+
+    fn test_signal(len: usize) -> Array1<f32> {
+        Array1::from_iter((0..len).map(|i| {
+            let t = i as f32 / 16000.0;
+            (t * 220.0 * std::f32::consts::TAU).sin() * 0.5 + (t * 1337.0 * std::f32::consts::TAU).sin() * 0.2
+        }))
+    }
+
+    #[test]
+    fn test_estimate_delay() {
+        let input = test_signal(8000);
+        let output = compensate_delay(&input, -37);
+        let (lag, corr) = estimate_delay(&input, &output, 100);
+        assert_eq!(lag, 37);
+        assert!(corr > 0.9);
+    }
+
+    #[test]
+    fn test_align_compensates_and_matches_length() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let input = Array1::from_iter((0..16000).map(|_| rng.gen_range(-0.25..0.25f32)));
+        let output = compensate_delay(&input, -25);
+        let target = AlignmentTarget { sample_rate: 16000, samples: 16000 };
+        let config = AlignmentConfig { delay_mode: DelayMode::Measured, ..Default::default() };
+        let (aligned, info) = align_output(&input, output, 16000, target, &config).unwrap();
+        assert_eq!(info.delay_samples, 25);
+        assert_eq!(aligned.len(), 16000);
+        assert!((aligned[1000] - input[1000]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_ambiguous_measurement_is_not_applied() {
+        // A steady tone correlates as well one period (about 73 samples) away
+        let input = Array1::from_iter((0..16000).map(|i| (i as f32 / 16000.0 * 220.0 * std::f32::consts::TAU).sin()));
+        let output = compensate_delay(&input, -25);
+        let target = AlignmentTarget { sample_rate: 16000, samples: 16000 };
+        let config = AlignmentConfig { delay_mode: DelayMode::Measured, max_delay_ms: 20.0, ..Default::default() };
+        let (_, info) = align_output(&input, output.clone(), 16000, target, &config).unwrap();
+        assert!(info.correlation.unwrap() > 0.9);
+        assert_eq!(info.delay_samples, 0);
+
+        assert_eq!(AlignmentConfig::default().delay_mode, DelayMode::Declared);
+        let (_, info) = align_output(&input, output, 16000, target, &AlignmentConfig::default()).unwrap();
+        assert_eq!((info.delay_samples, info.correlation), (0, None));
+    }
+
+    #[test]
+    fn test_align_restores_input_rate() {
+        let input = test_signal(16000);
+        let target = AlignmentTarget { sample_rate: 44100, samples: 44101 };
+        let config = AlignmentConfig { delay_mode: DelayMode::None, ..Default::default() };
+        let (aligned, info) = align_output(&input, input.clone(), 16000, target, &config).unwrap();
+        assert_eq!(aligned.len(), 44101);
+        assert_eq!(info.output_sample_rate, 44100);
+        assert_eq!(info.padded_samples, 1);
+    }
+}
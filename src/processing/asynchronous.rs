@@ -0,0 +1,103 @@
+//! Async API (feature `async`)
+//!
+//! Each call runs the blocking pipeline on tokio's blocking pool, so runtime
+//! threads stay free. Dropping the future cancels the call: segments not yet
+//! denoised are skipped and no output is written.
+
+use std::path::PathBuf;
+use crate::audio::WavAudio;
+use crate::error::{ZipEnhancerError, Result};
+use crate::processing::{CancellationToken, Enhancer, ProcessingResult};
+
+/// Cancels its token when the owning future is dropped
+struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) { self.0.cancel(); }
+}
+
+impl Enhancer {
+    /// [`process_file`](Self::process_file) without blocking the runtime
+    pub async fn process_file_async(&self, input: impl Into<PathBuf>, output: impl Into<PathBuf>) -> Result<ProcessingResult> {
+        let (input, output) = (input.into(), output.into());
+        self.run_blocking(move |enhancer| enhancer.process_file(&input, &output)).await
+    }
+
+    /// [`enhance`](Self::enhance) without blocking the runtime
+    pub async fn enhance_async(&self, audio: WavAudio) -> Result<WavAudio> {
+        self.run_blocking(move |enhancer| enhancer.enhance(&audio)).await
+    }
+
+    /// Run `f` on a clone whose token is cancelled with this enhancer's or when the future is dropped
+    async fn run_blocking<T: Send + 'static>(&self, f: impl FnOnce(&Enhancer) -> Result<T> + Send + 'static) -> Result<T> {
+        let guard = CancelOnDrop(self.cancellation().child());
+        let enhancer = self.clone().with_cancellation(guard.0.clone());
+        tokio::task::spawn_blocking(move || f(&enhancer)).await
+            .map_err(|e| ZipEnhancerError::processing(format!("Enhancement task failed: {}", e)))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::sync::mpsc::channel;
+    use std::sync::Mutex;
+    use std::task::{Context, Waker};
+    use ndarray::Array1;
+    use crate::audio::AudioFormat;
+    use crate::processing::{Progress, Stage};
+
+    fn noise(rate: u32) -> WavAudio {
+        WavAudio::new_mono(rate, Array1::from_iter((0..rate as usize).map(|i| ((i * 7919 % 1000) as f32 / 1000.0 - 0.5) * 0.1)), AudioFormat::Int16)
+    }
+
+    #[test]
+    fn test_async_matches_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.wav");
+        noise(22050).save_to_file(&input).unwrap();
+        let enhancer = Enhancer::builder().spectral().build().unwrap();
+
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let (result, enhanced) = rt.block_on(async {
+            let result = enhancer.process_file_async(&input, dir.path().join("out.wav")).await.unwrap();
+            (result, enhancer.enhance_async(noise(22050)).await.unwrap())
+        });
+        assert_eq!(result.performance_metrics.segment_count, enhancer.process_file(&input, &dir.path().join("sync.wav")).unwrap().performance_metrics.segment_count);
+        assert_eq!(enhanced.data().to_mono(), enhancer.enhance(&noise(22050)).unwrap().data().to_mono());
+        assert_eq!(WavAudio::from_file(dir.path().join("out.wav")).unwrap().data().len(), 22050);
+    }
+
+    #[test]
+    fn test_dropped_future_cancels() {
+        let dir = tempfile::tempdir().unwrap();
+        let (input, output) = (dir.path().join("in.wav"), dir.path().join("out.wav"));
+        noise(16000).save_to_file(&input).unwrap();
+
+        // The observer holds the blocking task at its first report until the future is dropped
+        let (started_tx, started_rx) = channel();
+        let (go_tx, go_rx) = channel::<()>();
+        let hold = Mutex::new(Some((started_tx, go_rx)));
+        let enhancer = Enhancer::builder().spectral().observer(move |p: &Progress| {
+            if let Some((started, go)) = hold.lock().unwrap().take() {
+                assert_eq!(p.stage, Stage::Loading);
+                started.send(()).unwrap();
+                go.recv().unwrap();
+            }
+        }).build().unwrap();
+
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let _entered = rt.enter();
+        let mut call = Box::pin(enhancer.process_file_async(input, &output));
+        assert!(call.as_mut().poll(&mut Context::from_waker(Waker::noop())).is_pending());
+        started_rx.recv().unwrap();
+        drop(call);
+        go_tx.send(()).unwrap();
+        drop(_entered);
+        drop(rt);
+
+        assert!(!output.exists());
+        assert!(!enhancer.cancellation().is_cancelled());
+    }
+}
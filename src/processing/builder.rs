@@ -0,0 +1,182 @@
+//! Enhancer Builder
+//!
+//! Library entry point: starts from defaults that work without a config file and
+//! only exposes the settings that matter when enhancing audio from code.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use crate::config::{utils, Config};
+use crate::error::Result;
+use crate::processing::{CancellationToken, DenoiseBackend, Enhancer, Execution, NormalizationPolicy, ProgressObserver};
+
+/// Builds an [`Enhancer`]; obtained from [`Enhancer::builder`].
+///
+/// Defaults to the bundled model path, one session using every logical CPU and
+/// the pipeline defaults of [`Config`].
+#[derive(Clone)]
+pub struct EnhancerBuilder {
+    config: Config,
+    execution: Execution,
+    observer: Option<Arc<dyn ProgressObserver>>,
+    cancel: Option<CancellationToken>,
+}
+
+impl std::fmt::Debug for EnhancerBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnhancerBuilder")
+            .field("config", &self.config)
+            .field("execution", &self.execution)
+            .field("observer", &self.observer.is_some())
+            .finish()
+    }
+}
+
+impl Default for EnhancerBuilder {
+    fn default() -> Self { Self::new() }
+}
+
+impl EnhancerBuilder {
+    pub fn new() -> Self {
+        let mut config = Config::default();
+        config.model.inference_threads = utils::cpu_count();
+        Self::from_config(config).serial()
+    }
+
+    /// Start from a loaded config, e.g. one written by `zipenhancer bench --write-config`;
+    /// its worker count selects parallel execution
+    pub fn from_config(config: Config) -> Self {
+        Self { execution: Execution::from_config(&config), config, observer: None, cancel: None }
+    }
+
+    pub fn model(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.model.path = path.into();
+        self.config.denoiser.backend = DenoiseBackend::Onnx;
+        self
+    }
+
+    /// Denoise with the built-in spectral gate; no model or ONNX Runtime needed
+    pub fn spectral(mut self) -> Self {
+        self.config.denoiser.backend = DenoiseBackend::Spectral;
+        self
+    }
+
+    /// Total inference threads, split evenly across the sessions; every session gets at
+    /// least one, so with fewer threads than workers each worker runs single-threaded
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.config.model.inference_threads = threads;
+        self
+    }
+
+    /// One session on the calling thread
+    pub fn serial(mut self) -> Self {
+        self.execution = Execution::Serial;
+        self
+    }
+
+    /// `workers` sessions on worker threads
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.config.processing.parallel_workers = workers;
+        self.execution = Execution::Parallel { workers };
+        self
+    }
+
+    /// Segment length in samples at the model rate, and the overlap between segments
+    pub fn segment(mut self, size: usize, overlap_ratio: f32) -> Self {
+        self.config.audio.segment_size = size;
+        self.config.audio.overlap_ratio = overlap_ratio;
+        self
+    }
+
+    /// Output level normalization; `target_db` is dBFS for peak/rms and LUFS for loudness
+    pub fn normalization(mut self, policy: NormalizationPolicy, target_db: f32) -> Self {
+        self.config.normalization.policy = policy;
+        self.config.normalization.target_db = target_db;
+        self
+    }
+
+    /// Enhancement strength: 1.0 = fully enhanced, 0.0 = original
+    pub fn strength(mut self, strength: f32) -> Self {
+        self.config.mix.strength = strength;
+        self
+    }
+
+    pub fn vad(mut self, enabled: bool) -> Self {
+        self.config.vad.enabled = enabled;
+        self
+    }
+
+    pub fn agc(mut self, enabled: bool) -> Self {
+        self.config.processing.enable_agc = enabled;
+        self
+    }
+
+    /// Any other setting
+    pub fn configure(mut self, f: impl FnOnce(&mut Config)) -> Self {
+        f(&mut self.config);
+        self
+    }
+
+    pub fn observer(mut self, observer: impl ProgressObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Validate the settings and create the model sessions
+    pub fn build(self) -> Result<Enhancer> {
+        self.config.validate()?;
+        let mut enhancer = Enhancer::new(self.config, self.execution)?;
+        if let Some(observer) = self.observer {
+            enhancer = enhancer.with_observer(observer);
+        }
+        if let Some(token) = self.cancel {
+            enhancer = enhancer.with_cancellation(token);
+        }
+        Ok(enhancer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array1;
+    use crate::audio::{AudioFormat, WavAudio};
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn test_settings_reach_the_config() {
+        let enhancer = Enhancer::builder().spectral().threads(1).workers(2).segment(8000, 0.2)
+            .normalization(NormalizationPolicy::Peak, -3.0).strength(0.5).build().unwrap();
+        let config = enhancer.config();
+        assert_eq!(enhancer.execution(), Execution::Parallel { workers: 2 });
+        assert_eq!((config.audio.segment_size, config.audio.overlap_ratio), (8000, 0.2));
+        assert_eq!((config.normalization.policy, config.normalization.target_db, config.mix.strength), (NormalizationPolicy::Peak, -3.0, 0.5));
+
+        let err = Enhancer::builder().spectral().segment(8001, 0.1).build().unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Config);
+        let err = Enhancer::builder().model("/nonexistent/model.onnx").build().unwrap_err();
+        assert_eq!(err.code(), ErrorCode::ModelNotFound);
+    }
+
+    #[test]
+    fn test_clones_enhance_from_several_threads() {
+        fn assert_shareable<T: Send + Sync + Clone>() {}
+        assert_shareable::<Enhancer>();
+
+        let enhancer = Enhancer::builder().spectral().build().unwrap();
+        let input = WavAudio::new_mono(44100, Array1::from_iter((0..44100).map(|i| ((i * 7919 % 1000) as f32 / 1000.0 - 0.5) * 0.1)), AudioFormat::Int16);
+        let outputs: Vec<WavAudio> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..3).map(|_| {
+                let (enhancer, input) = (enhancer.clone(), &input);
+                s.spawn(move || enhancer.enhance(input).unwrap())
+            }).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert!(outputs.iter().all(|o| o.sample_rate() == 44100 && o.data().len() == 44100));
+        assert_eq!(outputs[0].data().to_mono(), outputs[2].data().to_mono());
+    }
+}
@@ -1,13 +1,26 @@
-//! Common processing utilities shared between serial and parallel processors
+//! Processing stages shared by both execution strategies of the enhancer
 
+use std::path::Path;
+use std::time::Instant;
+use ndarray::Array1;
+use rayon::prelude::*;
 use crate::audio::{WavAudio, AudioConverter, AudioData};
 use crate::onnx::DynamicTensor;
 use crate::config::Config;
-use crate::error::Result;
-use super::AudioSegment;
+use crate::error::{ZipEnhancerError, Result};
+use crate::metrics::QualityEstimate;
+use crate::telemetry;
+use super::{AudioPostprocessor, AudioSegment, PreprocessingConfig, PostprocessingConfig};
+use super::vad::{export_regions, SilenceMode, SpeechRegion, VadResult};
+use super::residual::{compute_residual, segment_energy_db};
+use super::alignment::{resolve_delay, restore_output, AlignmentInfo, AlignmentTarget};
+use super::loudness::{from_db, normalize, LoudnessReport};
+use super::mix::blend_segment;
+use super::spectral_gate::SpectralGate;
 
 /// Prepare audio: convert to mono and resample if needed
 pub fn prepare_audio(audio: &mut WavAudio, config: &Config) -> Result<()> {
+    let _span = tracing::info_span!("resample", from = audio.sample_rate(), to = config.sample_rate()).entered();
     if audio.channels() > 1 {
         *audio.data_mut() = AudioData::Mono(audio.data().to_mono());
         audio.header.channels = 1;
@@ -18,6 +31,84 @@ pub fn prepare_audio(audio: &mut WavAudio, config: &Config) -> Result<()> {
     Ok(())
 }
 
+/// Preprocessor settings derived from the top-level config
+pub fn preprocessing_config(config: &Config) -> PreprocessingConfig {
+    PreprocessingConfig {
+        target_sample_rate: config.sample_rate(),
+        segment_size: config.segment_size(),
+        overlap_ratio: config.overlap_ratio(),
+        vad: Some(config.vad.clone()),
+    }
+}
+
+/// Postprocessor settings derived from the top-level config
+pub fn postprocessing_config(config: &Config) -> PostprocessingConfig {
+    PostprocessingConfig {
+        output_sample_rate: config.sample_rate(),
+        overlap_ratio: config.overlap_ratio(),
+        output_format: crate::audio::AudioFormat::Int16,
+    }
+}
+
+/// Gain of the original for a segment that skips inference because VAD classified it
+/// as non-speech, or `None` if the segment goes through the model
+fn skip_gain(segment: &AudioSegment, config: &Config) -> Option<f32> {
+    if segment.is_speech || !config.vad.enabled {
+        return None;
+    }
+    match config.vad.silence_mode {
+        SilenceMode::Infer => None,
+        SilenceMode::Attenuate => Some(from_db(-config.vad.attenuation_db.abs())),
+        SilenceMode::Passthrough => Some(1.0),
+    }
+}
+
+/// Output for a segment that skips inference because VAD classified it as non-speech
+pub fn skipped_segment_output(segment: &AudioSegment, segment_size: usize, config: &Config) -> Option<Vec<f32>> {
+    let gain = skip_gain(segment, config)?;
+    let mut data = vec![0.0f32; segment_size];
+    for (out, &x) in data.iter_mut().zip(segment.mono_data()?.iter()) {
+        *out = x * gain;
+    }
+    telemetry::metrics().fallback_vad_skips.inc();
+    Some(data)
+}
+
+/// Re-take skipped segments from the input delayed by the model delay, so that delay
+/// compensation leaves them where they were
+fn delay_skipped_outputs(outputs: &mut [SegmentOutput], segments: &[AudioSegment], input: &Array1<f32>, delay: i64, config: &Config) {
+    if delay == 0 { return; }
+    for (idx, data, _) in outputs.iter_mut() {
+        let Some(gain) = skip_gain(&segments[*idx], config) else { continue };
+        let start = segments[*idx].start_sample as i64 - delay;
+        for (i, out) in data.iter_mut().enumerate() {
+            let src = start + i as i64;
+            *out = if src >= 0 && (src as usize) < input.len() { input[src as usize] * gain } else { 0.0 };
+        }
+    }
+}
+
+/// Average inference time over inferred segments and the estimated time saved by skipping
+pub fn inference_timing(outputs: &[SegmentOutput], skipped: usize) -> (f64, f64) {
+    let inferred = outputs.len().saturating_sub(skipped);
+    if inferred == 0 {
+        return (0.0, 0.0);
+    }
+    let avg = outputs.iter().map(|(_, _, t)| *t as f64).sum::<f64>() / inferred as f64;
+    (avg, avg * skipped as f64)
+}
+
+/// Write VAD speech regions of an input file if an export path is configured
+pub fn export_speech_regions(vad: Option<&VadResult>, input: Option<&Path>, config: &Config) -> Result<Option<Vec<SpeechRegion>>> {
+    let Some(vad) = vad else { return Ok(None) };
+    let regions = vad.regions();
+    if let (Some(path), Some(input)) = (&config.vad.export_path, input) {
+        let file_id = input.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        export_regions(&regions, path, &file_id)?;
+    }
+    Ok(Some(regions))
+}
+
 /// Convert f32 audio data to i16 ONNX input tensor
 pub fn to_onnx_input(data: &ndarray::Array1<f32>, target_len: usize) -> DynamicTensor {
     let vec: Vec<f32> = if data.len() >= target_len {
@@ -31,47 +122,201 @@ pub fn to_onnx_input(data: &ndarray::Array1<f32>, target_len: usize) -> DynamicT
     DynamicTensor::new_i16(i16_data, vec![1, 1, target_len as i64])
 }
 
-/// Apply automatic gain control to processed segment
-pub fn apply_agc(data: &mut Vec<f32>) {
+/// Processed segment: (index, samples, inference time in ms)
+pub type SegmentOutput = (usize, Vec<f32>, u64);
+
+/// Replace non-finite samples and clamp model output to [-1, 1]
+pub fn sanitize_output(data: &mut [f32]) {
+    let mut replaced = false;
     for s in data.iter_mut() {
-        *s = if s.is_finite() { s.clamp(-1.0, 1.0) } else { 0.0 };
+        *s = if s.is_finite() { s.clamp(-1.0, 1.0) } else { replaced = true; 0.0 };
+    }
+    if replaced {
+        telemetry::metrics().fallback_non_finite.inc();
     }
-    let max = data.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
-    if max < 0.3 && max > 0.001 {
-        let gain = (1.0 / max).clamp(3.0, 10.0);
-        for s in data.iter_mut() {
-            *s = (*s * gain).clamp(-1.0, 1.0);
+}
+
+/// Apply smoothed segment-level automatic gain control.
+///
+/// Quiet segments get a gain towards a 0.5 peak, capped at `max_gain`. Gains are
+/// smoothed across segments and ramped within each segment so that consecutive
+/// segments never jump in level.
+pub fn apply_agc(outputs: &mut [&mut Vec<f32>], max_gain: f32, smoothing: f32) {
+    let mut prev_gain = 1.0f32;
+    for data in outputs.iter_mut() {
+        let peak = data.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
+        let target = if peak < 0.3 && peak > 0.001 { (0.5 / peak).clamp(1.0, max_gain) } else { 1.0 };
+        let gain = smoothing * prev_gain + (1.0 - smoothing) * target;
+
+        let len = data.len().max(1) as f32;
+        for (i, s) in data.iter_mut().enumerate() {
+            let g = prev_gain + (gain - prev_gain) * (i as f32 / len);
+            *s = (*s * g).clamp(-1.0, 1.0);
         }
+        prev_gain = gain;
     }
 }
 
-/// Normalize final output audio
-pub fn normalize_output(data: &mut ndarray::Array1<f32>, verbose: bool) {
-    if data.is_empty() { return; }
+/// Sanitize model output, then apply the post-filter and AGC if enabled in the config.
+/// Segments that skipped inference keep the original untouched.
+pub fn postprocess_outputs(outputs: &mut [SegmentOutput], segments: &[AudioSegment], config: &Config) -> Result<()> {
+    outputs.par_iter_mut().for_each(|(_, data, _)| sanitize_output(data));
+    let mut inferred: Vec<&mut Vec<f32>> = outputs.iter_mut()
+        .filter(|(idx, _, _)| skip_gain(&segments[*idx], config).is_none())
+        .map(|(_, data, _)| data)
+        .collect();
+    if config.denoiser.post_filter && config.denoiser.backend.uses_model() {
+        apply_post_filter(&mut inferred, config)?;
+    }
+    if config.processing.enable_agc {
+        apply_agc(&mut inferred, config.processing.agc_max_gain, config.processing.agc_smoothing);
+    }
+    Ok(())
+}
 
-    let (sum_sq, peak) = data.iter()
-        .map(|&x| (x * x, x.abs()))
-        .fold((0.0f32, 0.0f32), |(s, p), (s2, p2)| (s + s2, p.max(p2)));
+/// Suppress residual musical noise in model output with a mild spectral gate
+pub fn apply_post_filter(outputs: &mut [&mut Vec<f32>], config: &Config) -> Result<()> {
+    if outputs.is_empty() { return Ok(()); }
+    let combined = Array1::from_iter(outputs.iter().flat_map(|data| data.iter().copied()));
+    let gate = SpectralGate::fit(&combined, &config.denoiser, config.denoiser.post_filter_reduction_db)?;
+    outputs.par_iter_mut().for_each(|data| {
+        **data = gate.process(&Array1::from(std::mem::take(*data))).to_vec();
+    });
+    Ok(())
+}
+
+/// Denoise segments with the spectral gate instead of the model (no-model backend).
+///
+/// The noise profile is estimated once from the whole prepared input. `on_segment`
+/// is called after each segment and stops processing when it fails. Returns the
+/// outputs and the number of segments skipped by VAD.
+pub fn spectral_gate_outputs(
+    segments: &[AudioSegment],
+    prepared: &WavAudio,
+    config: &Config,
+    on_segment: &(dyn Fn() -> Result<()> + Sync),
+) -> Result<(Vec<SegmentOutput>, usize)> {
+    let gate = SpectralGate::fit(&prepared.data().to_mono(), &config.denoiser, config.denoiser.reduction_db)?;
+    let segment_size = config.segment_size();
+    let outputs = segments.par_iter().enumerate()
+        .map(|(i, seg)| {
+            if let Some(data) = skipped_segment_output(seg, segment_size, config) {
+                on_segment()?;
+                return Ok(((i, data, 0), true));
+            }
+            let _span = tracing::info_span!("infer", segment = i, backend = "spectral").entered();
+            let start = Instant::now();
+            let input = seg.mono_data().ok_or_else(|| ZipEnhancerError::processing("Not mono"))?;
+            let mut data = gate.process(input).to_vec();
+            data.resize(segment_size, 0.0);
+            on_segment()?;
+            Ok(((i, data, start.elapsed().as_millis() as u64), false))
+        })
+        .collect::<Result<Vec<(SegmentOutput, bool)>>>()?;
+    let skipped = outputs.iter().filter(|(_, skipped)| *skipped).count();
+    Ok((outputs.into_iter().map(|(output, _)| output).collect(), skipped))
+}
 
-    let rms = (sum_sq / data.len() as f32).sqrt();
+/// Blend the original into enhanced segments according to the mix config, delayed by
+/// the model delay so it lines up with the enhanced signal
+pub fn blend_outputs(outputs: &mut [SegmentOutput], segments: &[AudioSegment], input: &Array1<f32>, delay: i64, config: &Config) {
+    if config.mix.is_identity() { return; }
+    outputs.par_iter_mut().for_each(|(idx, data, _)| {
+        blend_segment(data, input, segments[*idx].start_sample, delay, config.sample_rate(), &config.mix);
+    });
+}
 
-    if rms < 0.1 && peak > 0.001 {
-        let rms_gain = 0.2 / rms;
-        let peak_gain = if peak > 0.0 { 0.95 / peak } else { 1.0 };
-        let gain = rms_gain.min(peak_gain).clamp(1.5, 8.0);
+/// Normalize final output audio according to the configured policy
+pub fn normalize_output(data: &mut Array1<f32>, sample_rate: u32, config: &Config) -> LoudnessReport {
+    let report = normalize(data, sample_rate, &config.normalization);
+    if config.verbose() {
+        eprintln!("Normalization ({:?}): {:.1} LUFS -> {:.1} LUFS, gain {:+.1} dB, limiter {:.1} dB",
+            report.policy, report.before.integrated_lufs, report.after.integrated_lufs,
+            report.gain_db, report.limiter_reduction_db);
+    }
+    report
+}
 
-        if verbose {
-            println!("Normalization: RMS={:.4}, Peak={:.4}, gain={:.2}", rms, peak, gain);
-        }
+/// Original input captured before `prepare_audio`, used for alignment and residual stems
+#[derive(Debug, Clone)]
+pub struct InputReference {
+    pub target: AlignmentTarget,
+    pub mono: Array1<f32>,
+}
 
-        for s in data.iter_mut() {
-            *s = (*s * gain).clamp(-1.0, 1.0);
-        }
+/// Capture the original input geometry and mono signal before `prepare_audio` changes it
+pub fn capture_input(audio: &WavAudio) -> InputReference {
+    InputReference {
+        target: AlignmentTarget { sample_rate: audio.sample_rate(), samples: audio.data().len() },
+        mono: audio.data().to_mono(),
     }
 }
 
+/// Overlap-add segment outputs into one signal at the working rate
+fn reconstruct(postprocessor: &mut AudioPostprocessor, outputs: &[SegmentOutput], segments: &[AudioSegment]) -> Result<Array1<f32>> {
+    let audio_segments: Vec<_> = outputs.iter()
+        .map(|(idx, data, _)| build_audio_segment(data.clone(), &segments[*idx]))
+        .collect();
+    postprocessor.reconstruct_from_segments(&audio_segments)
+}
+
+/// Output-stage results of a processed file
+#[derive(Debug, Clone)]
+pub struct OutputSummary {
+    pub enhanced: WavAudio,
+    /// Removed component, when requested
+    pub residual: Option<WavAudio>,
+    pub alignment: AlignmentInfo,
+    pub loudness: LoudnessReport,
+    pub residual_energy_db: Vec<f32>,
+    pub quality: QualityEstimate,
+}
+
+/// Resolve the model delay, blend in the original, reconstruct, align, derive the
+/// residual and normalize the output
+pub fn finish_output(
+    postprocessor: &mut AudioPostprocessor,
+    outputs: &mut [SegmentOutput],
+    segments: &[AudioSegment],
+    prepared: &WavAudio,
+    reference: &InputReference,
+    config: &Config,
+    keep_residual: bool,
+) -> Result<OutputSummary> {
+    let span = tracing::info_span!("reconstruct", segments = outputs.len()).entered();
+    let working = prepared.data().to_mono();
+    // Measured on model output only, before blending, so neither skipped segments nor the
+    // dry signal can pull the estimate towards zero lag
+    let alignment = resolve_delay(&config.alignment, config.sample_rate(), || {
+        let inferred: Vec<SegmentOutput> = outputs.iter().map(|(idx, data, ms)| match skip_gain(&segments[*idx], config) {
+            Some(_) => (*idx, vec![0.0; data.len()], *ms),
+            None => (*idx, data.clone(), *ms),
+        }).collect();
+        Ok((working.clone(), reconstruct(postprocessor, &inferred, segments)?))
+    })?;
+    delay_skipped_outputs(outputs, segments, &working, alignment.delay_samples, config);
+    blend_outputs(outputs, segments, &working, alignment.delay_samples, config);
+    let reconstructed = reconstruct(postprocessor, outputs, segments)?;
+    let (mut output_data, alignment) = restore_output(reconstructed, alignment, working.len(), config.sample_rate(), reference.target, &config.alignment)?;
+    drop(span);
+    let rate = alignment.output_sample_rate;
+
+    // Taken before the loudness gain, so the residual holds only what enhancement removed
+    let input = if rate == reference.target.sample_rate { reference.mono.clone() } else { prepared.data().to_mono() };
+    let residual = compute_residual(&input, &output_data);
+    let residual_energy_db = segment_energy_db(&residual, segments, rate as f64 / config.sample_rate() as f64);
+    let residual = if keep_residual { Some(postprocessor.create_wav_audio_at(residual, rate)?) } else { None };
+
+    let loudness = normalize_output(&mut output_data, rate, config);
+
+    let quality = QualityEstimate::measure(&input, &output_data, rate, loudness.gain_db);
+    let enhanced = postprocessor.create_wav_audio_at(output_data, rate)?;
+
+    Ok(OutputSummary { enhanced, residual, alignment, loudness, residual_energy_db, quality })
+}
+
 /// Build AudioSegment from processed data
-pub fn build_audio_segment(idx: usize, data: Vec<f32>, original: &AudioSegment) -> AudioSegment {
+pub fn build_audio_segment(data: Vec<f32>, original: &AudioSegment) -> AudioSegment {
     AudioSegment {
         index: original.index,
         data: AudioData::Mono(ndarray::Array1::from(data)),
@@ -79,5 +324,110 @@ pub fn build_audio_segment(idx: usize, data: Vec<f32>, original: &AudioSegment)
         end_sample: original.end_sample,
         length: original.length,
         is_complete: original.is_complete,
+        is_speech: original.is_speech,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use crate::audio::AudioFormat;
+    use crate::processing::AudioPreprocessor;
+    use crate::processing::alignment::DelayMode;
+    use crate::processing::loudness::NormalizationPolicy;
+
+    /// Two seconds of noise, its segments and segment outputs of a model that only delays by `delay`
+    fn delayed_model(delay: usize, amplitude: f32, config: &Config) -> (WavAudio, Vec<AudioSegment>, Vec<SegmentOutput>) {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let audio = WavAudio::new_mono(16000, Array1::from_iter((0..32000).map(|_| rng.gen_range(-amplitude..amplitude))), AudioFormat::Float32);
+        let input = audio.data().to_mono();
+        let segments = AudioPreprocessor::new(preprocessing_config(config)).preprocess(&audio).unwrap().segments;
+        let outputs = segments.iter().enumerate().map(|(i, seg)| {
+            let data = (0..config.segment_size()).map(|n| {
+                let src = (seg.start_sample + n) as i64 - delay as i64;
+                if src >= 0 && (src as usize) < input.len() { input[src as usize] } else { 0.0 }
+            }).collect();
+            (i, data, 0)
+        }).collect();
+        (audio, segments, outputs)
+    }
+
+    fn finish(audio: &WavAudio, segments: &[AudioSegment], outputs: &mut [SegmentOutput], config: &Config) -> OutputSummary {
+        let mut postprocessor = AudioPostprocessor::new(postprocessing_config(config));
+        finish_output(&mut postprocessor, outputs, segments, audio, &capture_input(audio), config, true).unwrap()
+    }
+
+    #[test]
+    fn test_dry_signal_aligned_with_delayed_output() {
+        let mut config = Config::default();
+        config.normalization.policy = NormalizationPolicy::None;
+        config.mix.strength = 0.5;
+        for mode in [DelayMode::Declared, DelayMode::Measured] {
+            config.alignment.delay_mode = mode;
+            config.alignment.declared_delay_samples = 40;
+            let (audio, segments, mut outputs) = delayed_model(40, 0.3, &config);
+            let summary = finish(&audio, &segments, &mut outputs, &config);
+            assert_eq!(summary.alignment.delay_samples, 40, "{:?}", mode);
+            // Wet and dry both line up with the input, so their blend reproduces it up to the end fade-out
+            let (input, enhanced) = (audio.data().to_mono(), summary.enhanced.data().to_mono());
+            let error = (100..29000).map(|i| (enhanced[i] - input[i]).abs()).fold(0.0f32, f32::max);
+            assert!(error < 1e-5, "{:?}: {}", mode, error);
+        }
+    }
+
+    #[test]
+    fn test_skipped_segments_bypass_delay_and_agc() {
+        let mut config = Config::default();
+        config.normalization.policy = NormalizationPolicy::None;
+        config.processing.enable_agc = true;
+        config.vad.enabled = true;
+        config.vad.silence_mode = SilenceMode::Passthrough;
+        for delay in [0, 40] {
+            config.alignment.declared_delay_samples = delay as i64;
+            let (audio, mut segments, mut outputs) = delayed_model(delay, 0.02, &config);
+            segments.iter_mut().for_each(|seg| seg.is_speech = seg.index != 1);
+            outputs[1].1 = skipped_segment_output(&segments[1], config.segment_size(), &config).unwrap();
+
+            postprocess_outputs(&mut outputs, &segments, &config).unwrap();
+            let summary = finish(&audio, &segments, &mut outputs, &config);
+            // The passed-through segment is neither shifted nor boosted; the inferred ones are boosted
+            let (input, enhanced) = (audio.data().to_mono(), summary.enhanced.data().to_mono());
+            let error = (16100..28700).map(|i| (enhanced[i] - input[i]).abs()).fold(0.0f32, f32::max);
+            assert!(error < 1e-6, "delay {}: {}", delay, error);
+            assert!(enhanced.iter().take(14000).map(|x| x.abs()).fold(0.0, f32::max) > 0.03);
+        }
+    }
+
+    #[test]
+    fn test_residual_excludes_normalization_gain() {
+        let config = Config::default();
+        let (audio, segments, mut outputs) = delayed_model(0, 0.3, &config);
+        for (_, data, _) in outputs.iter_mut() {
+            data.iter_mut().for_each(|x| *x *= 0.5);
+        }
+        let summary = finish(&audio, &segments, &mut outputs, &config);
+        assert!(summary.loudness.gain_db.abs() > 1.0);
+        // The model halved the input, so the residual is the other half whatever the output gain
+        let (input, residual) = (audio.data().to_mono(), summary.residual.unwrap().data().to_mono());
+        let error = (100..29000).map(|i| (residual[i] - 0.5 * input[i]).abs()).fold(0.0f32, f32::max);
+        assert!(error < 1e-5, "{}", error);
+    }
+
+    #[test]
+    fn test_sanitize_output() {
+        let mut data = vec![f32::NAN, 2.0, -0.5];
+        sanitize_output(&mut data);
+        assert_eq!(data, vec![0.0, 1.0, -0.5]);
+    }
+
+    #[test]
+    fn test_agc_is_smoothed_and_capped() {
+        let mut outputs = [vec![0.5; 100], vec![0.01; 100]];
+        apply_agc(&mut outputs.iter_mut().collect::<Vec<_>>(), 4.0, 0.7);
+        // Loud segment untouched, quiet segment ramps up from unity without exceeding the cap
+        assert!((outputs[0][99] - 0.5).abs() < 1e-6);
+        assert!((outputs[1][0] - 0.01).abs() < 1e-6);
+        assert!(outputs[1][99] > 0.01 && outputs[1][99] <= 0.04);
     }
 }
@@ -0,0 +1,548 @@
+//! Audio Processing Pipeline
+//!
+//! One [`Enhancer`] runs every file through the same stages; only the
+//! [`Execution`] strategy decides how segments reach the model sessions.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use rayon::prelude::*;
+use crate::audio::WavAudio;
+use crate::onnx::InferenceConfig;
+use crate::processing::{AudioPreprocessor, AudioPostprocessor, AudioSegment, EnhancerBuilder, SpeechRegion};
+use crate::processing::executor::{onnx_sessions, Executor, InThread, OpenSession, WorkerPool};
+use crate::processing::progress::{CancellationToken, ProgressHooks, ProgressObserver, Stage, Tracker};
+use crate::processing::alignment::AlignmentInfo;
+use crate::processing::loudness::LoudnessReport;
+use crate::metrics::QualityEstimate;
+use crate::processing::common::{prepare_audio, to_onnx_input, postprocess_outputs, capture_input, finish_output, OutputSummary, SegmentOutput,
+    preprocessing_config, postprocessing_config, skipped_segment_output, inference_timing, export_speech_regions, spectral_gate_outputs};
+use crate::config::Config;
+use crate::error::{ZipEnhancerError, Result};
+use crate::telemetry;
+
+/// How segments reach the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Execution {
+    /// One session on the calling thread using all inference threads
+    Serial,
+    /// `workers` sessions on worker threads sharing the inference threads
+    Parallel { workers: usize },
+}
+
+impl Execution {
+    /// Parallel with the configured worker count
+    pub fn from_config(config: &Config) -> Self {
+        Self::Parallel { workers: config.processing.parallel_workers }
+    }
+}
+
+impl std::fmt::Display for Execution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Serial => write!(f, "serial"),
+            Self::Parallel { workers } => write!(f, "parallel ({} workers)", workers),
+        }
+    }
+}
+
+/// Runs audio through the full pipeline. Cheap to clone: clones share the model
+/// sessions and can be used from several threads at once, while each keeps its own
+/// observer and cancellation token. With [`Execution::Serial`] every file checks out
+/// its own session; with [`Execution::Parallel`] concurrent files share the workers.
+#[derive(Clone)]
+pub struct Enhancer {
+    config: Arc<Config>,
+    execution: Execution,
+    /// `None` when the spectral backend is selected
+    executor: Option<Arc<dyn Executor>>,
+    sessions: usize,
+    preprocessor: AudioPreprocessor,
+    postprocessor: AudioPostprocessor,
+    hooks: ProgressHooks,
+}
+
+impl std::fmt::Debug for Enhancer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Enhancer")
+            .field("execution", &self.execution)
+            .field("sessions", &self.sessions)
+            .field("hooks", &self.hooks)
+            .finish()
+    }
+}
+
+/// Enhanced audio of one input, before anything is written
+struct Enhanced {
+    duration: f64,
+    segments: usize,
+    skipped: usize,
+    avg_inference_ms: f64,
+    cpu_saved_ms: f64,
+    speech_regions: Option<Vec<SpeechRegion>>,
+    output: OutputSummary,
+}
+
+impl Enhancer {
+    /// Builder with library defaults; see [`EnhancerBuilder`]
+    pub fn builder() -> EnhancerBuilder { EnhancerBuilder::new() }
+
+    /// With the ONNX backend, sessions are checked out of the process-wide session pool
+    /// within `config.sessions`; other enhancers sharing the pool keep their own limits
+    pub fn new(config: Config, execution: Execution) -> Result<Self> {
+        let sessions = config.denoiser.backend.uses_model().then(|| {
+            onnx_sessions(config.model_path(), InferenceConfig { max_retries: config.max_retries(), pool_limits: config.sessions.limits(), ..Default::default() })
+        });
+        Self::with_sessions(config, execution, sessions)
+    }
+
+    /// Enhancer whose model sessions are opened by `sessions`; `None` selects the spectral backend
+    pub(crate) fn with_sessions(config: Config, execution: Execution, sessions: Option<OpenSession>) -> Result<Self> {
+        if config.verbose() { eprintln!("Initializing enhancer..."); }
+
+        let executor: Option<Arc<dyn Executor>> = match sessions {
+            Some(open) => {
+                let threads = config.inference_threads();
+                if config.verbose() {
+                    eprintln!("Model: {}", config.model_path().display());
+                    eprintln!("Threads: {}", threads);
+                }
+                Some(match execution {
+                    Execution::Serial => Arc::new(InThread::new(&open, threads)?),
+                    Execution::Parallel { workers } => {
+                        let workers = workers.max(1);
+                        // At least one thread per worker, even when there are fewer threads than workers
+                        Arc::new(WorkerPool::new(&open, (threads / workers).max(1), workers)?)
+                    }
+                })
+            }
+            None => {
+                if config.verbose() { eprintln!("Backend: spectral gate (no model)"); }
+                None
+            }
+        };
+
+        Ok(Self {
+            preprocessor: AudioPreprocessor::new(preprocessing_config(&config)),
+            postprocessor: AudioPostprocessor::new(postprocessing_config(&config)),
+            config: Arc::new(config),
+            execution,
+            sessions: executor.as_ref().map_or(0, |e| e.sessions()),
+            executor,
+            hooks: ProgressHooks::default(),
+        })
+    }
+
+    /// Report progress of every file to `observer`
+    pub fn with_observer(mut self, observer: Arc<dyn ProgressObserver>) -> Self {
+        self.hooks.observer = Some(observer);
+        self
+    }
+
+    /// Stop at the next segment once `token` is cancelled; the output is then not written
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.hooks.cancel = token;
+        self
+    }
+
+    pub fn config(&self) -> &Config { &self.config }
+
+    /// Token that stops this enhancer's files
+    pub fn cancellation(&self) -> &CancellationToken { &self.hooks.cancel }
+
+    pub fn execution(&self) -> Execution { self.execution }
+
+    /// Model sessions in use; zero with the spectral backend
+    pub fn sessions(&self) -> usize { self.sessions }
+
+    /// Run one dummy inference per session so the first file is not slowed by lazy initialization
+    pub fn warm_up(&self) -> Result<()> {
+        let Some(executor) = &self.executor else { return Ok(()) };
+        if self.config.verbose() { eprintln!("Warming up..."); }
+        executor.warm_up()?;
+        if self.config.verbose() { eprintln!("Warm-up complete"); }
+        Ok(())
+    }
+
+    pub fn process_file(&self, input: &Path, output: &Path) -> Result<ProcessingResult> {
+        self.process_file_with_residual(input, output, self.config.residual_path.as_deref())
+    }
+
+    /// Process a file, additionally writing the removed component to `residual`
+    pub fn process_file_with_residual(&self, input: &Path, output: &Path, residual: Option<&Path>) -> Result<ProcessingResult> {
+        let result = self.run_file(input, output, residual);
+        match &result {
+            Ok(r) => telemetry::metrics().record_success(r.performance_metrics.input_duration_seconds, r.performance_metrics.processing_time_seconds),
+            Err(e) => telemetry::metrics().record_failure(e),
+        }
+        result
+    }
+
+    /// Enhance audio in memory; any channel count and sample rate is accepted and
+    /// the output keeps the input's rate and length unless the config says otherwise
+    pub fn enhance(&self, audio: &WavAudio) -> Result<WavAudio> {
+        let start = Instant::now();
+        let progress = self.hooks.tracker();
+        let result = self.run_audio(audio.clone(), None, false, &progress);
+        match &result {
+            Ok(e) => telemetry::metrics().record_success(e.duration, start.elapsed().as_secs_f64()),
+            Err(e) => telemetry::metrics().record_failure(e),
+        }
+        let enhanced = result?;
+        progress.stage(Stage::Done)?;
+        Ok(enhanced.output.enhanced)
+    }
+
+    fn run_file(&self, input: &Path, output: &Path, residual: Option<&Path>) -> Result<ProcessingResult> {
+        let start = Instant::now();
+        let progress = self.hooks.tracker();
+        progress.stage(Stage::Loading)?;
+        let audio = WavAudio::from_file(input)?;
+        let enhanced = self.run_audio(audio, Some(input), residual.is_some(), &progress)?;
+
+        // Write nothing once cancelled, and never leave a residual without its output
+        self.hooks.cancel.check()?;
+        let residual = residual.zip(enhanced.output.residual.as_ref());
+        if let Some((path, audio)) = residual {
+            audio.save_to_file(path)?;
+        }
+        if let Err(e) = enhanced.output.enhanced.save_to_file(output) {
+            if let Some((path, _)) = residual { let _ = std::fs::remove_file(path); }
+            return Err(e);
+        }
+        progress.stage(Stage::Done)?;
+
+        let processing_time = start.elapsed();
+        Ok(ProcessingResult {
+            input_path: input.to_path_buf(),
+            output_path: output.to_path_buf(),
+            residual_path: residual.map(|(path, _)| path.to_path_buf()),
+            processing_time,
+            performance_metrics: PerformanceMetrics {
+                input_duration_seconds: enhanced.duration,
+                processing_time_seconds: processing_time.as_secs_f64(),
+                real_time_factor: processing_time.as_secs_f64() / enhanced.duration,
+                segment_count: enhanced.segments,
+                average_inference_time_ms: enhanced.avg_inference_ms,
+                residual_energy_db: enhanced.output.residual_energy_db,
+                skipped_segments: enhanced.skipped,
+                cpu_saved_ms: enhanced.cpu_saved_ms,
+                worker_count: self.sessions,
+            },
+            speech_regions: enhanced.speech_regions,
+            alignment: enhanced.output.alignment,
+            loudness: enhanced.output.loudness,
+            quality: enhanced.output.quality,
+        })
+    }
+
+    /// Every stage up to the output audio; `input` names the file for speech region export
+    fn run_audio(&self, mut audio: WavAudio, input: Option<&Path>, keep_residual: bool, progress: &Tracker) -> Result<Enhanced> {
+        progress.set_audio(audio.duration());
+        telemetry::metrics().record_audio(audio.duration(), audio.sample_bytes());
+        if self.config.verbose() {
+            eprintln!("Audio: {:.2}s, {}Hz", audio.duration(), audio.sample_rate());
+        }
+
+        progress.stage(Stage::Preprocessing)?;
+        let reference = capture_input(&audio);
+        prepare_audio(&mut audio, &self.config)?;
+
+        let preprocessed = self.preprocessor.preprocess(&audio)?;
+        let segments = preprocessed.segments;
+        let speech_regions = export_speech_regions(preprocessed.vad.as_ref(), input, &self.config)?;
+        if self.config.verbose() { eprintln!("Segments: {}", segments.len()); }
+
+        progress.set_segments(segments.len());
+        progress.stage(Stage::Denoising)?;
+        let (mut processed, skipped) = match &self.executor {
+            Some(executor) => self.run_model(executor.as_ref(), &segments, progress)?,
+            None => spectral_gate_outputs(&segments, &audio, &self.config, &|| progress.segment_done())?,
+        };
+        progress.stage(Stage::Postprocessing)?;
+        postprocess_outputs(&mut processed, &segments, &self.config)?;
+
+        progress.stage(Stage::Writing)?;
+        let output = finish_output(&mut self.postprocessor.clone(), &mut processed, &segments, &audio, &reference, &self.config, keep_residual)?;
+        let (avg_inference_ms, cpu_saved_ms) = inference_timing(&processed, skipped);
+
+        Ok(Enhanced { duration: audio.duration(), segments: segments.len(), skipped, avg_inference_ms, cpu_saved_ms, speech_regions, output })
+    }
+
+    /// Run speech segments through the model; non-speech segments may skip it.
+    /// Returns outputs in segment order and the number of skipped segments.
+    fn run_model(&self, executor: &dyn Executor, segments: &[AudioSegment], progress: &Tracker) -> Result<(Vec<SegmentOutput>, usize)> {
+        let segment_size = self.config.segment_size();
+
+        let mut results: Vec<SegmentOutput> = Vec::with_capacity(segments.len());
+        let mut pending = Vec::with_capacity(segments.len());
+        for (i, seg) in segments.iter().enumerate() {
+            match skipped_segment_output(seg, segment_size, &self.config) {
+                Some(data) => results.push((i, data, 0)),
+                None => pending.push(i),
+            }
+        }
+        let skipped = results.len();
+        for _ in 0..skipped {
+            progress.segment_done()?;
+        }
+
+        let jobs = pending.par_iter()
+            .map(|&i| segments[i].mono_data().map(|d| (i, to_onnx_input(d, segment_size))))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| ZipEnhancerError::processing("Not mono"))?;
+
+        results.extend(executor.run(jobs, segments, progress)?);
+        results.sort_by_key(|(idx, _, _)| *idx);
+        Ok((results, skipped))
+    }
+}
+
+#[derive(Debug)]
+pub struct ProcessingResult {
+    pub input_path: PathBuf,
+    pub output_path: PathBuf,
+    pub residual_path: Option<PathBuf>,
+    pub processing_time: Duration,
+    pub performance_metrics: PerformanceMetrics,
+    pub alignment: AlignmentInfo,
+    pub loudness: LoudnessReport,
+    /// Reference-free input vs. output quality estimates
+    pub quality: QualityEstimate,
+    /// Detected speech regions when VAD is enabled
+    pub speech_regions: Option<Vec<SpeechRegion>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PerformanceMetrics {
+    pub input_duration_seconds: f64,
+    pub processing_time_seconds: f64,
+    pub real_time_factor: f64,
+    pub segment_count: usize,
+    pub average_inference_time_ms: f64,
+    /// Residual (removed component) energy per segment in dBFS
+    pub residual_energy_db: Vec<f32>,
+    /// Segments that skipped inference because VAD classified them as non-speech
+    pub skipped_segments: usize,
+    /// Estimated inference time saved by skipping (ms)
+    pub cpu_saved_ms: f64,
+    /// Model sessions used; zero with the spectral backend
+    pub worker_count: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use ndarray::Array1;
+    use crate::audio::AudioFormat;
+    use crate::processing::{DenoiseBackend, Progress};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use crate::onnx::{DynamicTensor, PoolLimits, SessionConfig, SessionKey};
+    use crate::onnx::pool::{Pool, Pooled};
+    use crate::processing::executor::{Session, StandIn};
+
+    fn spectral_enhancer(execution: Execution) -> Enhancer {
+        let mut config = Config::default();
+        config.denoiser.backend = DenoiseBackend::Spectral;
+        Enhancer::new(config, execution).unwrap()
+    }
+
+    fn noise_file(dir: &Path) -> PathBuf {
+        let path = dir.join("in.wav");
+        let noise = Array1::from_iter((0..32000).map(|i| ((i * 7919 % 1000) as f32 / 1000.0 - 0.5) * 0.1));
+        WavAudio::new_mono(16000, noise, AudioFormat::Int16).save_to_file(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_progress_reaches_done() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = noise_file(dir.path());
+        let seen = Arc::new(Mutex::new(Vec::<Progress>::new()));
+        let log = seen.clone();
+        let enhancer = spectral_enhancer(Execution::Serial).with_observer(Arc::new(move |p: &Progress| log.lock().unwrap().push(*p)));
+        enhancer.warm_up().unwrap();
+        let result = enhancer.process_file(&input, &dir.path().join("out.wav")).unwrap();
+        assert_eq!(result.performance_metrics.worker_count, 0);
+
+        let seen = seen.lock().unwrap();
+        let last = seen.last().unwrap();
+        assert_eq!(last.stage, Stage::Done);
+        assert!(last.segments_total > 0 && last.segments_done == last.segments_total);
+        assert_eq!(seen.first().unwrap().stage, Stage::Loading);
+    }
+
+    #[test]
+    fn test_cancelled_file_is_not_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = noise_file(dir.path());
+        let token = CancellationToken::new();
+        token.cancel();
+        let output = dir.path().join("out.wav");
+        let err = spectral_enhancer(Execution::Parallel { workers: 2 }).with_cancellation(token).process_file(&input, &output).unwrap_err();
+        assert_eq!(err.code(), crate::error::ErrorCode::Cancelled);
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn test_failed_output_removes_residual() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = noise_file(dir.path());
+        let residual = dir.path().join("residual.wav");
+        // An existing directory cannot be replaced by the output file
+        let output = dir.path().join("out.wav");
+        std::fs::create_dir(&output).unwrap();
+        let err = spectral_enhancer(Execution::Serial).process_file_with_residual(&input, &output, Some(&residual)).unwrap_err();
+        assert_eq!(err.code(), crate::error::ErrorCode::Io);
+        assert!(!residual.exists());
+    }
+
+    #[test]
+    fn test_strategies_produce_identical_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = noise_file(dir.path());
+        let mut outputs = Vec::new();
+        for (execution, sessions) in [(Execution::Serial, 1), (Execution::Parallel { workers: 3 }, 3)] {
+            let enhancer = Enhancer::with_sessions(Config::default(), execution, Some(StandIn::sessions())).unwrap();
+            enhancer.warm_up().unwrap();
+            let output = dir.path().join(format!("{}.wav", sessions));
+            let result = enhancer.process_file(&input, &output).unwrap();
+            assert_eq!(result.performance_metrics.worker_count, sessions);
+            outputs.push(WavAudio::from_file(&output).unwrap().data().to_mono());
+        }
+        assert_eq!(outputs[0], outputs[1]);
+        assert!(outputs[0].iter().any(|&x| x != 0.0));
+    }
+
+    /// Stand-in session that, in its first inference, waits until another session is open
+    struct Gathering { open: Arc<AtomicUsize>, met: Arc<AtomicBool>, waited: bool }
+
+    impl Session for Gathering {
+        fn infer(&mut self, tensor: DynamicTensor) -> Result<(Vec<f32>, u64)> {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while !self.waited && self.open.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            self.waited = true;
+            if self.open.load(Ordering::SeqCst) >= 2 { self.met.store(true, Ordering::SeqCst); }
+            StandIn.infer(tensor)
+        }
+
+        fn warm_up(&mut self) -> Result<()> { Ok(()) }
+    }
+
+    impl Drop for Gathering {
+        fn drop(&mut self) { self.open.fetch_sub(1, Ordering::SeqCst); }
+    }
+
+    #[test]
+    fn test_clones_run_the_model_concurrently() {
+        let (open, met) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicBool::new(false)));
+        let sessions: OpenSession = {
+            let (open, met) = (open.clone(), met.clone());
+            Arc::new(move |_| {
+                open.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(Gathering { open: open.clone(), met: met.clone(), waited: false }) as Box<dyn Session>)
+            })
+        };
+        let enhancer = Enhancer::with_sessions(Config::default(), Execution::Serial, Some(sessions)).unwrap();
+        let audio = WavAudio::new_mono(16000, Array1::from_iter((0..16000).map(|i| ((i * 7919 % 1000) as f32 / 1000.0 - 0.5) * 0.1)), AudioFormat::Int16);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..2).map(|_| {
+                let (enhancer, audio) = (enhancer.clone(), &audio);
+                scope.spawn(move || enhancer.enhance(audio).unwrap())
+            }).collect();
+            handles.into_iter().for_each(|h| { h.join().unwrap(); });
+        });
+        assert!(met.load(Ordering::SeqCst), "second file waited for the first one's session");
+        assert_eq!(open.load(Ordering::SeqCst), 0);
+    }
+
+    /// Stand-in session counted while open
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Session for Counted {
+        fn infer(&mut self, tensor: DynamicTensor) -> Result<(Vec<f32>, u64)> { StandIn.infer(tensor) }
+        fn warm_up(&mut self) -> Result<()> { Ok(()) }
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) { self.0.fetch_sub(1, Ordering::SeqCst); }
+    }
+
+    #[test]
+    fn test_workers_return_sessions_after_each_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = noise_file(dir.path());
+        let live = Arc::new(AtomicUsize::new(0));
+        let sessions: OpenSession = {
+            let live = live.clone();
+            Arc::new(move |_| {
+                live.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(Counted(live.clone())) as Box<dyn Session>)
+            })
+        };
+        let enhancer = Enhancer::with_sessions(Config::default(), Execution::Parallel { workers: 3 }, Some(sessions)).unwrap();
+        for _ in 0..2 {
+            enhancer.process_file(&input, &dir.path().join("out.wav")).unwrap();
+            // Replies are sent before a worker looks for more work, so allow it to finish
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while live.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            assert_eq!(live.load(Ordering::SeqCst), 0);
+        }
+    }
+
+    #[test]
+    fn test_missing_model_fails_for_every_strategy() {
+        let mut config = Config::default();
+        config.model.path = PathBuf::from("/nonexistent/model.onnx");
+        for execution in [Execution::Serial, Execution::Parallel { workers: 2 }] {
+            let err = Enhancer::new(config.clone(), execution).unwrap_err();
+            assert_eq!(err.code(), crate::error::ErrorCode::ModelNotFound, "{}", execution);
+        }
+    }
+
+    impl Session for Pooled<StandIn> {
+        fn infer(&mut self, tensor: DynamicTensor) -> Result<(Vec<f32>, u64)> { (**self).infer(tensor) }
+        fn warm_up(&mut self) -> Result<()> { Ok(()) }
+    }
+
+    /// Stand-in sessions of one model checked out of `pool` within the limits of
+    /// `config`, as `Enhancer::new` does with the process-wide session pool
+    fn pooled_stand_in(pool: &Pool<StandIn>, config: &Config) -> OpenSession {
+        let (pool, limits) = (pool.clone(), config.sessions.limits());
+        Arc::new(move |_| {
+            let key = SessionKey::new(Path::new("stand-in.onnx"), SessionConfig::default());
+            Ok(Box::new(pool.acquire(key, limits, || Ok(StandIn))?) as Box<dyn Session>)
+        })
+    }
+
+    #[test]
+    fn test_enhancers_keep_their_own_session_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = noise_file(dir.path());
+        let pool = Pool::new();
+        let build = |workers: usize, max_sessions: usize| {
+            let mut config = Config::default();
+            config.processing.parallel_workers = workers;
+            config.sessions.max_sessions = max_sessions;
+            config.sessions.checkout_timeout_secs = 0;
+            let sessions = pooled_stand_in(&pool, &config);
+            Enhancer::with_sessions(config, Execution::Parallel { workers }, Some(sessions))
+        };
+        let key = SessionKey::new(Path::new("stand-in.onnx"), SessionConfig::default());
+        let held: Vec<_> = (0..2).map(|_| pool.acquire(key.clone(), PoolLimits::default(), || Ok(StandIn)).unwrap()).collect();
+
+        let wide = build(4, 8).unwrap();
+        pool.clear();
+        // Two sessions of the model are in use and none idle, so an enhancer limited to two gets none
+        assert!(build(2, 2).is_err());
+        // ...while the first one still checks out its four next to them
+        wide.process_file(&input, &dir.path().join("out.wav")).unwrap();
+        drop(held);
+        build(2, 2).unwrap().process_file(&input, &dir.path().join("out.wav")).unwrap();
+    }
+}
@@ -0,0 +1,260 @@
+//! Execution strategies: how segment tensors reach the model sessions
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use crate::error::{ResultExt, ZipEnhancerError, Result};
+use crate::onnx::{DynamicTensor, InferenceConfig, InferenceEngine};
+use crate::processing::common::SegmentOutput;
+use crate::processing::progress::{CancellationToken, Tracker};
+use crate::processing::AudioSegment;
+use crate::telemetry;
+
+/// Segment index and model input
+pub(crate) type Job = (usize, DynamicTensor);
+
+/// Runs model inputs through one or more sessions; shared by concurrent files
+pub(crate) trait Executor: Send + Sync {
+    /// Outputs of `jobs` in job order; errors carry the segment context
+    fn run(&self, jobs: Vec<Job>, segments: &[AudioSegment], progress: &Tracker) -> Result<Vec<SegmentOutput>>;
+
+    /// One dummy inference per session
+    fn warm_up(&self) -> Result<()>;
+
+    fn sessions(&self) -> usize;
+}
+
+/// One model session
+pub(crate) trait Session: Send {
+    /// Model output samples and inference time of one input
+    fn infer(&mut self, tensor: DynamicTensor) -> Result<(Vec<f32>, u64)>;
+
+    fn warm_up(&mut self) -> Result<()>;
+}
+
+/// Opens a session with the given number of intra-op threads
+pub(crate) type OpenSession = Arc<dyn Fn(usize) -> Result<Box<dyn Session>> + Send + Sync>;
+
+impl Session for InferenceEngine {
+    fn infer(&mut self, tensor: DynamicTensor) -> Result<(Vec<f32>, u64)> {
+        let mut result = self.run(vec![tensor])?;
+        if let Some(error) = result.error.take() {
+            return Err(error);
+        }
+        let data = result.first_output().ok_or_else(|| ZipEnhancerError::processing("No output"))?.iter().cloned().collect();
+        Ok((data, result.time_ms))
+    }
+
+    fn warm_up(&mut self) -> Result<()> { InferenceEngine::warm_up(self) }
+}
+
+/// Sessions of the ONNX model at `model_path`
+pub(crate) fn onnx_sessions(model_path: &Path, config: InferenceConfig) -> OpenSession {
+    let model_path = model_path.to_path_buf();
+    Arc::new(move |threads| {
+        let config = InferenceConfig { intra_threads: threads.max(1) as i32, ..config.clone() };
+        Ok(Box::new(InferenceEngine::new(&model_path, config)?) as Box<dyn Session>)
+    })
+}
+
+/// Deterministic stand-in for the model: a three-tap moving average of its input.
+/// Lets the execution strategies be compared without ONNX Runtime or a model file.
+pub(crate) struct StandIn;
+
+impl StandIn {
+    pub fn sessions() -> OpenSession {
+        Arc::new(|_| Ok(Box::new(StandIn) as Box<dyn Session>))
+    }
+}
+
+impl Session for StandIn {
+    fn infer(&mut self, tensor: DynamicTensor) -> Result<(Vec<f32>, u64)> {
+        let input: Vec<f32> = tensor.into_ndarray()?.iter().copied().collect();
+        let last = input.len().saturating_sub(1);
+        let data = (0..input.len()).map(|i| (input[i.saturating_sub(1)] + input[i] + input[(i + 1).min(last)]) / 3.0).collect();
+        Ok((data, 0))
+    }
+
+    fn warm_up(&mut self) -> Result<()> { Ok(()) }
+}
+
+/// Single session on the calling thread, opened for each file so that files
+/// processed concurrently do not wait for one another
+pub(crate) struct InThread {
+    open: OpenSession,
+    threads: usize,
+}
+
+impl InThread {
+    /// Fails when no session can be opened
+    pub fn new(open: &OpenSession, threads: usize) -> Result<Self> {
+        open(threads)?;
+        Ok(Self { open: open.clone(), threads })
+    }
+}
+
+impl Executor for InThread {
+    fn run(&self, jobs: Vec<Job>, segments: &[AudioSegment], progress: &Tracker) -> Result<Vec<SegmentOutput>> {
+        let mut session = (self.open)(self.threads)?;
+        let mut results = Vec::with_capacity(jobs.len());
+        for (index, tensor) in jobs {
+            let (data, time_ms) = session.infer(tensor).with_segment(index, segments[index].start_sample)?;
+            results.push((index, data, time_ms));
+            progress.segment_done()?;
+        }
+        Ok(results)
+    }
+
+    fn warm_up(&self) -> Result<()> { (self.open)(self.threads)?.warm_up() }
+
+    fn sessions(&self) -> usize { 1 }
+}
+
+enum Task {
+    Segment { index: usize, tensor: DynamicTensor, cancel: CancellationToken, reply: Sender<Reply> },
+    WarmUp { reply: Sender<Reply> },
+}
+
+struct Reply { index: usize, data: Result<Vec<f32>>, time_ms: u64 }
+
+struct Worker {
+    tx: Sender<Option<Task>>,
+    handle: JoinHandle<()>,
+}
+
+impl Worker {
+    /// Spawn the thread; it opens a session to check the model and reports on `ready`.
+    /// Afterwards a session is opened for each batch of queued tasks and closed, e.g.
+    /// returned to the session pool, once the queue is empty.
+    fn spawn(open: OpenSession, threads: usize, id: usize, ready: Sender<Result<()>>) -> Self {
+        let (task_tx, task_rx) = channel::<Option<Task>>();
+
+        let handle = thread::spawn(move || {
+            let opened = open(threads).map(drop);
+            let failed = opened.is_err();
+            let _ = ready.send(opened);
+            if failed { return; }
+
+            let metrics = telemetry::metrics();
+            let mut session: Option<Box<dyn Session>> = None;
+            let mut queued = None;
+            loop {
+                let task = match queued.take() {
+                    Some(task) => task,
+                    None => {
+                        session = None;
+                        match task_rx.recv() { Ok(task) => task, Err(_) => break }
+                    }
+                };
+                let Some(task) = task else { break };
+                let start = Instant::now();
+                let (index, data, reply) = match task {
+                    Task::WarmUp { reply } => (id, with_session(&mut session, &open, threads, |s| s.warm_up().map(|_| Vec::new())), reply),
+                    Task::Segment { index, tensor, cancel, reply } => {
+                        metrics.queue_depth.add(-1);
+                        // Queued tasks of a cancelled file are answered without running them
+                        let data = cancel.check().and_then(|_| with_session(&mut session, &open, threads, |s| s.infer(tensor)));
+                        (index, data.map(|(data, _)| data), reply)
+                    }
+                };
+                metrics.record_worker_busy(id, start.elapsed().as_secs_f64());
+                let _ = reply.send(Reply { index, data, time_ms: start.elapsed().as_millis() as u64 });
+                queued = task_rx.try_recv().ok();
+            }
+        });
+
+        Self { tx: task_tx, handle }
+    }
+
+    fn send(&self, task: Task) -> Result<()> {
+        self.tx.send(Some(task)).map_err(|_| ZipEnhancerError::processing("Worker stopped"))
+    }
+}
+
+/// Run `f` on the open session, opening one first if there is none
+fn with_session<T>(session: &mut Option<Box<dyn Session>>, open: &OpenSession, threads: usize, f: impl FnOnce(&mut dyn Session) -> Result<T>) -> Result<T> {
+    let session = match session {
+        Some(session) => session,
+        None => session.insert(open(threads)?),
+    };
+    f(session.as_mut())
+}
+
+/// N sessions on worker threads; segments are dealt round-robin. Files processed
+/// concurrently share the workers: each gets its own replies while their segments
+/// interleave in the worker queues.
+pub(crate) struct WorkerPool {
+    workers: Vec<Worker>,
+}
+
+impl WorkerPool {
+    pub fn new(open: &OpenSession, threads_per_worker: usize, workers: usize) -> Result<Self> {
+        let (ready_tx, ready_rx) = channel();
+        let pool = Self { workers: (0..workers.max(1)).map(|id| Worker::spawn(open.clone(), threads_per_worker, id, ready_tx.clone())).collect() };
+        // Every worker reports whether its session could be created
+        let mut failure = None;
+        for _ in &pool.workers {
+            if let Err(e) = ready_rx.recv().map_err(|_| ZipEnhancerError::processing("Worker stopped")).and_then(|r| r) {
+                failure.get_or_insert(e);
+            }
+        }
+        failure.map_or(Ok(pool), Err)
+    }
+}
+
+/// Receive `count` replies, even after a failure, so none is left queued
+fn collect(rx: &Receiver<Reply>, count: usize, mut on_reply: impl FnMut(Reply) -> Result<()>) -> Result<()> {
+    let mut failure = None;
+    for _ in 0..count {
+        let reply = rx.recv().map_err(|_| ZipEnhancerError::processing("Worker stopped"))?;
+        if let Err(e) = on_reply(reply) {
+            failure.get_or_insert(e);
+        }
+    }
+    failure.map_or(Ok(()), Err)
+}
+
+impl Executor for WorkerPool {
+    fn run(&self, jobs: Vec<Job>, segments: &[AudioSegment], progress: &Tracker) -> Result<Vec<SegmentOutput>> {
+        let (reply_tx, reply_rx) = channel();
+        let count = jobs.len();
+        for (n, (index, tensor)) in jobs.into_iter().enumerate() {
+            telemetry::metrics().queue_depth.add(1);
+            self.workers[n % self.workers.len()].send(Task::Segment { index, tensor, cancel: progress.cancellation().clone(), reply: reply_tx.clone() })?;
+        }
+        // Only the tasks hold senders, so a worker that died ends the wait
+        drop(reply_tx);
+
+        let mut results = Vec::with_capacity(count);
+        collect(&reply_rx, count, |reply| {
+            let data = reply.data.with_segment(reply.index, segments[reply.index].start_sample);
+            let done = progress.segment_done();
+            results.push((reply.index, data?, reply.time_ms));
+            done
+        })?;
+        results.sort_by_key(|(index, _, _)| *index);
+        Ok(results)
+    }
+
+    fn warm_up(&self) -> Result<()> {
+        let (reply_tx, reply_rx) = channel();
+        for worker in &self.workers {
+            worker.send(Task::WarmUp { reply: reply_tx.clone() })?;
+        }
+        drop(reply_tx);
+        collect(&reply_rx, self.workers.len(), |reply| reply.data.map(|_| ()))
+    }
+
+    fn sessions(&self) -> usize { self.workers.len() }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        for worker in self.workers.drain(..) {
+            let _ = worker.tx.send(None);
+            let _ = worker.handle.join();
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! Loudness Normalization - ITU-R BS.1770 measurement, gain policies and true-peak limiting
+
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use crate::config::NormalizationConfig;
+
+/// Level reported for digital silence (dB)
+const SILENCE_DB: f32 = -120.0;
+/// BS.1770 gating block length and step (seconds)
+const BLOCK_SECS: f32 = 0.4;
+const BLOCK_STEP_SECS: f32 = 0.1;
+const ABSOLUTE_GATE_LUFS: f32 = -70.0;
+const RELATIVE_GATE_LU: f32 = -10.0;
+/// Oversampling factor and taps per phase for true-peak estimation
+const OVERSAMPLE: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+/// Limiter lookahead and release times (seconds)
+const LIMITER_LOOKAHEAD_SECS: f32 = 0.0015;
+const LIMITER_RELEASE_SECS: f32 = 0.05;
+
+/// Output level normalization policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NormalizationPolicy {
+    /// Leave levels untouched (no gain, no limiter)
+    None,
+    /// Scale so the sample peak reaches `target_db` dBFS
+    Peak,
+    /// Scale so the RMS level reaches `target_db` dBFS
+    Rms,
+    /// Scale so the gated integrated loudness reaches `target_db` LUFS
+    #[default]
+    Loudness,
+}
+
+impl std::str::FromStr for NormalizationPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "peak" => Ok(Self::Peak),
+            "rms" => Ok(Self::Rms),
+            "loudness" | "lufs" => Ok(Self::Loudness),
+            _ => Err(format!("Unknown normalization policy: {} (expected none|peak|rms|loudness)", s)),
+        }
+    }
+}
+
+/// Level measurements of a signal
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct LevelStats {
+    pub integrated_lufs: f32,
+    pub peak_dbfs: f32,
+    pub true_peak_dbtp: f32,
+    pub rms_dbfs: f32,
+}
+
+impl LevelStats {
+    pub fn measure(data: &Array1<f32>, sample_rate: u32) -> Self {
+        let samples = data.to_vec();
+        let peak = samples.iter().fold(0.0f32, |p, x| p.max(x.abs()));
+        let rms = if samples.is_empty() { 0.0 } else {
+            (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+        };
+        Self {
+            integrated_lufs: integrated_loudness(&samples, sample_rate),
+            peak_dbfs: to_db(peak),
+            true_peak_dbtp: to_db(true_peak(&samples)),
+            rms_dbfs: to_db(rms),
+        }
+    }
+}
+
+/// Normalization applied to the output, reported in processing results
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoudnessReport {
+    pub policy: NormalizationPolicy,
+    pub before: LevelStats,
+    pub after: LevelStats,
+    pub gain_db: f32,
+    /// Largest gain reduction applied by the true-peak limiter (dB, ≤ 0)
+    pub limiter_reduction_db: f32,
+}
+
+pub fn to_db(linear: f32) -> f32 {
+    if linear > 0.0 { (20.0 * linear.log10()).max(SILENCE_DB) } else { SILENCE_DB }
+}
+
+pub fn from_db(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Second-order IIR section (direct form I)
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&self, input: &[f64]) -> Vec<f64> {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        input.iter().map(|&x| {
+            let y = self.b[0] * x + self.b[1] * x1 + self.b[2] * x2 - self.a[0] * y1 - self.a[1] * y2;
+            x2 = x1; x1 = x;
+            y2 = y1; y1 = y;
+            y
+        }).collect()
+    }
+}
+
+/// K-weighting filter pair (pre-filter shelf + RLB high-pass) for any sample rate.
+///
+/// Coefficients are re-derived from the analog prototypes so that 48 kHz reproduces
+/// the BS.1770 reference values exactly.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    let (f0, g, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(g / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let highpass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    [shelf, highpass]
+}
+
+/// Gated integrated loudness of a mono signal in LUFS (ITU-R BS.1770-4)
+pub fn integrated_loudness(data: &[f32], sample_rate: u32) -> f32 {
+    if data.is_empty() || sample_rate == 0 {
+        return SILENCE_DB;
+    }
+
+    let [shelf, highpass] = k_weighting(sample_rate);
+    let input: Vec<f64> = data.iter().map(|&x| x as f64).collect();
+    let weighted = highpass.process(&shelf.process(&input));
+
+    let block = ((BLOCK_SECS * sample_rate as f32) as usize).min(weighted.len()).max(1);
+    let step = ((BLOCK_STEP_SECS * sample_rate as f32) as usize).max(1);
+    let powers: Vec<f64> = (0..=weighted.len() - block)
+        .step_by(step)
+        .map(|start| weighted[start..start + block].iter().map(|x| x * x).sum::<f64>() / block as f64)
+        .collect();
+
+    let loudness = |z: f64| -0.691 + 10.0 * z.log10();
+    let gated_mean = |threshold: f64| {
+        let passed: Vec<f64> = powers.iter().copied().filter(|&z| z > 0.0 && loudness(z) > threshold).collect();
+        if passed.is_empty() { None } else { Some(passed.iter().sum::<f64>() / passed.len() as f64) }
+    };
+
+    let Some(absolute) = gated_mean(ABSOLUTE_GATE_LUFS as f64) else { return SILENCE_DB };
+    let relative_threshold = loudness(absolute) + RELATIVE_GATE_LU as f64;
+    gated_mean(relative_threshold)
+        .map(|z| (loudness(z) as f32).max(SILENCE_DB))
+        .unwrap_or(SILENCE_DB)
+}
+
+/// Windowed-sinc interpolation kernels, one per fractional oversampling phase
+fn oversampling_kernels() -> Vec<Vec<f32>> {
+    let half = TAPS_PER_PHASE as f32 / 2.0;
+    (1..OVERSAMPLE).map(|phase| {
+        let frac = phase as f32 / OVERSAMPLE as f32;
+        (0..TAPS_PER_PHASE).map(|k| {
+            let t = k as f32 - (half - 1.0) - frac;
+            let sinc = if t.abs() < 1e-6 { 1.0 } else {
+                (std::f32::consts::PI * t).sin() / (std::f32::consts::PI * t)
+            };
+            let window = 0.5 * (1.0 + (std::f32::consts::PI * t / (half + 1.0)).cos());
+            sinc * window
+        }).collect()
+    }).collect()
+}
+
+/// Per-sample peak envelope including inter-sample peaks (4x oversampled)
+fn true_peak_envelope(data: &[f32]) -> Vec<f32> {
+    let kernels = oversampling_kernels();
+    let offset = TAPS_PER_PHASE / 2 - 1;
+    (0..data.len()).map(|n| {
+        let mut peak = data[n].abs();
+        for kernel in &kernels {
+            let value: f32 = kernel.iter().enumerate().map(|(k, &c)| {
+                let idx = n as i64 + k as i64 - offset as i64;
+                if idx >= 0 && (idx as usize) < data.len() { data[idx as usize] * c } else { 0.0 }
+            }).sum();
+            peak = peak.max(value.abs());
+        }
+        peak
+    }).collect()
+}
+
+/// Linear true-peak level of a signal
+pub fn true_peak(data: &[f32]) -> f32 {
+    true_peak_envelope(data).into_iter().fold(0.0f32, f32::max)
+}
+
+/// Lookahead true-peak limiter; returns the largest gain reduction in dB
+pub fn limit_true_peak(data: &mut Array1<f32>, ceiling_db: f32, sample_rate: u32) -> f32 {
+    let ceiling = from_db(ceiling_db);
+    let samples = data.to_vec();
+    let envelope = true_peak_envelope(&samples);
+    if envelope.iter().all(|&p| p <= ceiling) {
+        return 0.0;
+    }
+
+    let len = samples.len();
+    let lookahead = ((LIMITER_LOOKAHEAD_SECS * sample_rate as f32) as usize).max(1);
+    let required: Vec<f32> = envelope.iter().map(|&p| if p > ceiling { ceiling / p } else { 1.0 }).collect();
+
+    // Sliding minimum over the lookahead window, then a moving average so the
+    // gain has fully ramped down by the time the peak arrives
+    let held: Vec<f32> = (0..len)
+        .map(|n| required[n..(n + lookahead + 1).min(len)].iter().copied().fold(1.0, f32::min))
+        .collect();
+    let mut smoothed = vec![1.0f32; len];
+    let mut acc = 0.0f32;
+    for n in 0..len {
+        acc += held[n];
+        if n > lookahead { acc -= held[n - lookahead - 1]; }
+        smoothed[n] = acc / (n.min(lookahead) + 1) as f32;
+    }
+
+    let release = (-1.0 / (LIMITER_RELEASE_SECS * sample_rate as f32)).exp();
+    let mut gain = 1.0f32;
+    let mut min_gain = 1.0f32;
+    for (n, sample) in data.iter_mut().enumerate() {
+        let target = smoothed[n].min(required[n]);
+        gain = if target < gain { target } else { target + (gain - target) * release };
+        min_gain = min_gain.min(gain);
+        *sample = (*sample * gain).clamp(-ceiling, ceiling);
+    }
+    to_db(min_gain)
+}
+
+/// Apply the configured normalization policy and return before/after measurements
+pub fn normalize(data: &mut Array1<f32>, sample_rate: u32, config: &NormalizationConfig) -> LoudnessReport {
+    let before = LevelStats::measure(data, sample_rate);
+    let mut report = LoudnessReport { policy: config.policy, before, after: before, ..Default::default() };
+
+    let current = match config.policy {
+        NormalizationPolicy::None => return report,
+        NormalizationPolicy::Peak => before.peak_dbfs,
+        NormalizationPolicy::Rms => before.rms_dbfs,
+        NormalizationPolicy::Loudness => before.integrated_lufs,
+    };
+
+    if current > SILENCE_DB {
+        report.gain_db = (config.target_db - current).min(config.max_gain_db);
+        let gain = from_db(report.gain_db);
+        data.mapv_inplace(|x| x * gain);
+    }
+
+    if config.limiter {
+        report.limiter_reduction_db = limit_true_peak(data, config.true_peak_limit_db, sample_rate);
+    } else {
+        data.mapv_inplace(|x| x.clamp(-1.0, 1.0));
+    }
+
+    report.after = LevelStats::measure(data, sample_rate);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f32, amplitude: f32, sample_rate: u32, secs: f32) -> Vec<f32> {
+        (0..(sample_rate as f32 * secs) as usize)
+            .map(|i| amplitude * (std::f32::consts::TAU * freq * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_integrated_loudness_reference_sine() {
+        // A full-scale 997 Hz sine on one channel measures -3.01 LUFS
+        let full = sine(997.0, 1.0, 48000, 3.0);
+        assert!((integrated_loudness(&full, 48000) + 3.01).abs() < 0.1);
+
+        let quiet = sine(997.0, 0.1, 16000, 3.0);
+        assert!((integrated_loudness(&quiet, 16000) + 23.01).abs() < 0.15);
+    }
+
+    #[test]
+    fn test_silence_is_gated() {
+        assert_eq!(integrated_loudness(&vec![0.0; 16000], 16000), SILENCE_DB);
+        assert_eq!(integrated_loudness(&vec![1e-6; 16000], 16000), SILENCE_DB);
+    }
+
+    #[test]
+    fn test_true_peak_exceeds_sample_peak() {
+        // fs/4 sine sampled at 45° phase: samples peak at 0.707, true peak is 1.0
+        let data: Vec<f32> = (0..64)
+            .map(|i| (std::f32::consts::FRAC_PI_2 * i as f32 + std::f32::consts::FRAC_PI_4).sin())
+            .collect();
+        let tp = true_peak(&data);
+        assert!(tp > 0.95, "true peak {}", tp);
+    }
+
+    #[test]
+    fn test_loudness_policy_hits_target() {
+        let mut data = Array1::from(sine(440.0, 0.05, 16000, 2.0));
+        let config = NormalizationConfig { target_db: -20.0, ..Default::default() };
+        let report = normalize(&mut data, 16000, &config);
+        assert!((report.after.integrated_lufs + 20.0).abs() < 0.3);
+        assert!(report.after.true_peak_dbtp <= config.true_peak_limit_db + 0.1);
+    }
+
+    #[test]
+    fn test_limiter_enforces_ceiling() {
+        let mut data = Array1::from(sine(440.0, 1.0, 16000, 0.5));
+        let reduction = limit_true_peak(&mut data, -3.0, 16000);
+        assert!(reduction < -2.5);
+        assert!(data.iter().all(|x| x.abs() <= from_db(-3.0) + 1e-6));
+    }
+
+    #[test]
+    fn test_policy_parse() {
+        assert_eq!("LUFS".parse::<NormalizationPolicy>().unwrap(), NormalizationPolicy::Loudness);
+        assert!("loud".parse::<NormalizationPolicy>().is_err());
+    }
+}
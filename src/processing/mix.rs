@@ -0,0 +1,114 @@
+//! Dry/Wet Mix - Blend time-aligned original audio back into the enhanced signal
+
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use crate::config::MixConfig;
+use super::loudness::from_db;
+
+/// Fade length at the edges of a strength range (seconds), avoids audible steps
+const RANGE_FADE_SECS: f32 = 0.01;
+
+/// Enhancement strength override for a time range of the input
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MixRange {
+    pub start_secs: f32,
+    pub end_secs: f32,
+    pub strength: f32,
+}
+
+impl std::str::FromStr for MixRange {
+    type Err = String;
+
+    /// Parse `START:END:STRENGTH`, e.g. `12.5:20:0.6`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parts: Vec<f32> = s.split(':')
+            .map(|p| p.trim().parse::<f32>())
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| format!("Invalid mix range '{}': {}", s, e))?;
+        match parts.as_slice() {
+            &[start_secs, end_secs, strength] => Ok(Self { start_secs, end_secs, strength }),
+            _ => Err(format!("Invalid mix range '{}' (expected START:END:STRENGTH)", s)),
+        }
+    }
+}
+
+impl MixConfig {
+    /// True if the mix leaves the enhanced signal untouched
+    pub fn is_identity(&self) -> bool {
+        self.strength >= 1.0 && self.attenuation_limit_db.is_none()
+            && self.ranges.iter().all(|r| r.strength >= 1.0)
+    }
+
+    /// Enhancement strength at time `t`, with ranges faded in over `RANGE_FADE_SECS`
+    pub fn strength_at(&self, t: f32) -> f32 {
+        self.ranges.iter().fold(self.strength, |current, range| {
+            if t < range.start_secs || t >= range.end_secs {
+                return current;
+            }
+            let edge = (t - range.start_secs).min(range.end_secs - t);
+            let weight = (edge / RANGE_FADE_SECS).clamp(0.0, 1.0);
+            current + (range.strength - current) * weight
+        })
+    }
+
+    /// Weight of the original signal at time `t`
+    pub fn dry_gain_at(&self, t: f32) -> f32 {
+        let dry = 1.0 - self.strength_at(t).clamp(0.0, 1.0);
+        match self.attenuation_limit_db {
+            Some(limit) => dry.max(from_db(-limit.abs())),
+            None => dry,
+        }
+    }
+}
+
+/// Blend the original input into one enhanced segment.
+///
+/// `start_sample` is the segment position in `input`; `delay` is the model delay in
+/// samples, so the original is shifted to line up with the enhanced output.
+pub fn blend_segment(enhanced: &mut [f32], input: &Array1<f32>, start_sample: usize, delay: i64, sample_rate: u32, config: &MixConfig) {
+    for (i, sample) in enhanced.iter_mut().enumerate() {
+        let pos = start_sample + i;
+        if pos >= input.len() {
+            break;
+        }
+        let src = pos as i64 - delay;
+        let original = if src >= 0 && (src as usize) < input.len() { input[src as usize] } else { 0.0 };
+        let dry = config.dry_gain_at(pos as f32 / sample_rate as f32);
+        *sample = (1.0 - dry) * *sample + dry * original;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_by_default() {
+        assert!(MixConfig::default().is_identity());
+    }
+
+    #[test]
+    fn test_blend_strength() {
+        let config = MixConfig { strength: 0.25, ..Default::default() };
+        let input = Array1::from(vec![1.0; 4]);
+        let mut enhanced = vec![0.0; 4];
+        blend_segment(&mut enhanced, &input, 0, 0, 16000, &config);
+        assert!(enhanced.iter().all(|&x| (x - 0.75).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_attenuation_limit() {
+        let config = MixConfig { attenuation_limit_db: Some(20.0), ..Default::default() };
+        assert!((config.dry_gain_at(0.0) - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_range_override_and_parse() {
+        let range: MixRange = "1:2:0".parse().unwrap();
+        let config = MixConfig { ranges: vec![range], ..Default::default() };
+        assert_eq!(config.strength_at(0.5), 1.0);
+        assert_eq!(config.strength_at(1.5), 0.0);
+        assert!(config.strength_at(1.005) > 0.0);
+        assert!("1:2".parse::<MixRange>().is_err());
+    }
+}
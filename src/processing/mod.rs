@@ -1,12 +1,28 @@
 //! Audio Processing Pipeline
 
+pub mod alignment;
+#[cfg(feature = "async")]
+mod asynchronous;
+pub mod builder;
 pub mod common;
+pub mod loudness;
+pub mod mix;
 pub mod preprocessor;
 pub mod postprocessor;
-pub mod processor;
-pub mod parallel_processor;
+pub mod residual;
+pub mod spectral_gate;
+pub mod vad;
+pub mod enhancer;
+pub(crate) mod executor;
+pub mod progress;
 
-pub use preprocessor::{AudioPreprocessor, PreprocessingConfig, AudioSegment};
+pub use preprocessor::{AudioPreprocessor, PreprocessingConfig, AudioSegment, Preprocessed};
 pub use postprocessor::{AudioPostprocessor, PostprocessingConfig};
-pub use processor::{AudioProcessor, ProcessingResult, PerformanceMetrics};
-pub use parallel_processor::{ParallelAudioProcessor, ParallelProcessingResult};
+pub use alignment::{AlignmentInfo, DelayMode};
+pub use loudness::{LoudnessReport, LevelStats, NormalizationPolicy};
+pub use mix::MixRange;
+pub use spectral_gate::{DenoiseBackend, SpectralGate};
+pub use vad::{SilenceMode, SpeechRegion, VadResult};
+pub use builder::EnhancerBuilder;
+pub use enhancer::{Enhancer, Execution, ProcessingResult, PerformanceMetrics};
+pub use progress::{CancellationToken, Progress, ProgressObserver, Stage};
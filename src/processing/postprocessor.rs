@@ -24,7 +24,7 @@ impl Default for PostprocessingConfig {
 }
 
 /// Audio postprocessor
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AudioPostprocessor {
     config: PostprocessingConfig,
 }
@@ -111,8 +111,13 @@ impl AudioPostprocessor {
     }
 
     pub fn create_wav_audio(&self, data: Array1<f32>) -> Result<WavAudio> {
+        self.create_wav_audio_at(data, self.config.output_sample_rate)
+    }
+
+    /// Create output audio at an explicit sample rate (e.g. the restored input rate)
+    pub fn create_wav_audio_at(&self, data: Array1<f32>, sample_rate: u32) -> Result<WavAudio> {
         Ok(WavAudio {
-            header: AudioHeader::new(self.config.output_sample_rate, 1, self.config.output_format, data.len() as u32),
+            header: AudioHeader::new(sample_rate, 1, self.config.output_format, data.len() as u32),
             data: AudioData::Mono(data),
         })
     }
@@ -2,7 +2,9 @@
 
 use ndarray::Array1;
 use crate::audio::{WavAudio, AudioData};
+use crate::config::VadConfig;
 use crate::error::{ZipEnhancerError, Result};
+use super::vad::{self, VadResult};
 
 /// Preprocessing configuration
 #[derive(Debug, Clone)]
@@ -10,6 +12,8 @@ pub struct PreprocessingConfig {
     pub target_sample_rate: u32,
     pub segment_size: usize,
     pub overlap_ratio: f32,
+    /// Voice activity detection; `None` treats every segment as speech
+    pub vad: Option<VadConfig>,
 }
 
 impl Default for PreprocessingConfig {
@@ -18,10 +22,18 @@ impl Default for PreprocessingConfig {
             target_sample_rate: 16000,
             segment_size: 16000,
             overlap_ratio: 0.1,
+            vad: None,
         }
     }
 }
 
+/// Segments plus optional VAD decisions
+#[derive(Debug, Clone)]
+pub struct Preprocessed {
+    pub segments: Vec<AudioSegment>,
+    pub vad: Option<VadResult>,
+}
+
 /// Audio segment
 #[derive(Debug, Clone)]
 pub struct AudioSegment {
@@ -31,6 +43,8 @@ pub struct AudioSegment {
     pub end_sample: usize,
     pub length: usize,
     pub is_complete: bool,
+    /// Cleared by VAD for segments without enough speech
+    pub is_speech: bool,
 }
 
 impl AudioSegment {
@@ -42,6 +56,7 @@ impl AudioSegment {
             end_sample: end,
             length: end - start,
             is_complete,
+            is_speech: true,
         }
     }
 
@@ -54,7 +69,7 @@ impl AudioSegment {
 }
 
 /// Audio preprocessor
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AudioPreprocessor {
     config: PreprocessingConfig,
 }
@@ -70,6 +85,12 @@ impl AudioPreprocessor {
 
     /// Preprocess and segment audio
     pub fn preprocess_and_segment(&self, audio: &WavAudio) -> Result<Vec<AudioSegment>> {
+        Ok(self.preprocess(audio)?.segments)
+    }
+
+    /// Preprocess, segment and, if enabled, classify segments with VAD
+    pub fn preprocess(&self, audio: &WavAudio) -> Result<Preprocessed> {
+        let _span = tracing::info_span!("segment", samples = audio.data().len()).entered();
         if audio.channels() != 1 {
             return Err(ZipEnhancerError::processing("Input must be mono"));
         }
@@ -78,7 +99,15 @@ impl AudioPreprocessor {
                 "Sample rate mismatch: {} vs {}", audio.sample_rate(), self.config.target_sample_rate
             )));
         }
-        self.segment(audio)
+        let mut segments = self.segment(audio)?;
+
+        let vad = self.config.vad.as_ref().filter(|v| v.enabled).map(|config| {
+            let result = vad::detect(&audio.data().to_mono(), audio.sample_rate(), config);
+            result.classify_segments(&mut segments, config.min_speech_ratio);
+            result
+        });
+
+        Ok(Preprocessed { segments, vad })
     }
 
     /// Segment audio into overlapping chunks
@@ -92,9 +121,8 @@ impl AudioPreprocessor {
         }
 
         let mut segments = Vec::new();
-        let mut idx = 0;
 
-        for start in (0..total).step_by(hop) {
+        for (idx, start) in (0..total).step_by(hop).enumerate() {
             let end = (start + seg_size).min(total);
             let is_complete = (end - start) == seg_size;
 
@@ -104,7 +132,6 @@ impl AudioPreprocessor {
             };
 
             segments.push(AudioSegment::new(idx, seg_data, start, end, is_complete));
-            idx += 1;
         }
 
         Ok(segments)
@@ -138,6 +165,7 @@ mod tests {
             segment_size: 4,
             overlap_ratio: 0.5,
             target_sample_rate: 16000,
+            vad: None,
         };
         let preprocessor = AudioPreprocessor::new(config);
         
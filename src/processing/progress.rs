@@ -0,0 +1,221 @@
+//! Progress reporting and cancellation
+//!
+//! The enhancer reports stage changes and finished segments to an optional
+//! [`ProgressObserver`] and stops at the next segment boundary once its
+//! [`CancellationToken`] is cancelled.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use crate::error::{ZipEnhancerError, Result};
+
+/// Pipeline stage of the file being processed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Loading,
+    Preprocessing,
+    Denoising,
+    Postprocessing,
+    Writing,
+    Done,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Loading => "loading",
+            Self::Preprocessing => "preprocessing",
+            Self::Denoising => "denoising",
+            Self::Postprocessing => "postprocessing",
+            Self::Writing => "writing",
+            Self::Done => "done",
+        })
+    }
+}
+
+/// Snapshot passed to observers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub stage: Stage,
+    pub segments_done: usize,
+    /// Zero until segmentation finished
+    pub segments_total: usize,
+    /// Time since the file was started
+    pub elapsed: Duration,
+    /// Duration of the input audio; zero until it is loaded
+    pub audio_secs: f64,
+}
+
+impl Progress {
+    /// Finished fraction of the denoising stage
+    pub fn fraction(&self) -> f64 {
+        match self.stage {
+            Stage::Postprocessing | Stage::Writing | Stage::Done => 1.0,
+            _ if self.segments_total == 0 => 0.0,
+            _ => self.segments_done as f64 / self.segments_total as f64,
+        }
+    }
+
+    /// Remaining time extrapolated from the segments done so far
+    pub fn eta(&self) -> Option<Duration> {
+        let fraction = self.fraction();
+        (fraction > 0.0).then(|| self.elapsed.mul_f64((1.0 - fraction) / fraction))
+    }
+
+    /// Real-time factor so far: elapsed time over the audio time denoised
+    pub fn rtf(&self) -> Option<f64> {
+        let audio = self.audio_secs * self.fraction();
+        (audio > 0.0).then(|| self.elapsed.as_secs_f64() / audio)
+    }
+}
+
+/// Receives progress of an enhancer; called from worker threads
+pub trait ProgressObserver: Send + Sync {
+    fn on_progress(&self, progress: &Progress);
+}
+
+impl<F: Fn(&Progress) + Send + Sync> ProgressObserver for F {
+    fn on_progress(&self, progress: &Progress) { self(progress) }
+}
+
+/// Shared flag to stop processing; clones cancel together
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<TokenState>);
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    parent: Option<CancellationToken>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self { Self::default() }
+
+    /// Token that is also cancelled by this one, but can be cancelled on its own
+    pub fn child(&self) -> Self {
+        Self(Arc::new(TokenState { cancelled: AtomicBool::new(false), parent: Some(self.clone()) }))
+    }
+
+    pub fn cancel(&self) { self.0.cancelled.store(true, Ordering::SeqCst); }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst) || self.0.parent.as_ref().is_some_and(|p| p.is_cancelled())
+    }
+
+    /// `Err(Cancelled)` once cancelled
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() { Err(ZipEnhancerError::Cancelled) } else { Ok(()) }
+    }
+}
+
+/// Observer and cancellation token held by an enhancer
+#[derive(Clone, Default)]
+pub(crate) struct ProgressHooks {
+    pub observer: Option<Arc<dyn ProgressObserver>>,
+    pub cancel: CancellationToken,
+}
+
+impl fmt::Debug for ProgressHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressHooks")
+            .field("observer", &self.observer.is_some())
+            .field("cancel", &self.cancel)
+            .finish()
+    }
+}
+
+impl ProgressHooks {
+    /// Tracker for one file
+    pub fn tracker(&self) -> Tracker { Tracker::new(self.observer.clone(), self.cancel.clone()) }
+}
+
+/// Progress of one file, shared by the processing stages
+pub(crate) struct Tracker {
+    observer: Option<Arc<dyn ProgressObserver>>,
+    cancel: CancellationToken,
+    start: Instant,
+    progress: Mutex<Progress>,
+    done: AtomicUsize,
+}
+
+impl Tracker {
+    pub fn new(observer: Option<Arc<dyn ProgressObserver>>, cancel: CancellationToken) -> Self {
+        let progress = Progress { stage: Stage::Loading, segments_done: 0, segments_total: 0, elapsed: Duration::ZERO, audio_secs: 0.0 };
+        Self { observer, cancel, start: Instant::now(), progress: Mutex::new(progress), done: AtomicUsize::new(0) }
+    }
+
+    /// Enter `stage`; fails if cancelled
+    pub fn stage(&self, stage: Stage) -> Result<()> {
+        if stage != Stage::Done { self.cancel.check()?; }
+        self.update(|p| p.stage = stage);
+        Ok(())
+    }
+
+    pub fn set_audio(&self, audio_secs: f64) { self.update(|p| p.audio_secs = audio_secs); }
+
+    pub fn set_segments(&self, total: usize) { self.update(|p| p.segments_total = total); }
+
+    /// Count a finished segment; fails if cancelled
+    pub fn segment_done(&self) -> Result<()> {
+        self.done.fetch_add(1, Ordering::Relaxed);
+        self.update(|_| {});
+        self.cancel.check()
+    }
+
+    pub fn cancellation(&self) -> &CancellationToken { &self.cancel }
+
+    fn update(&self, change: impl FnOnce(&mut Progress)) {
+        let mut progress = self.progress.lock().expect("progress lock");
+        change(&mut progress);
+        progress.segments_done = self.done.load(Ordering::Relaxed);
+        progress.elapsed = self.start.elapsed();
+        if let Some(observer) = &self.observer {
+            observer.on_progress(&progress);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_estimates() {
+        let mut p = Progress { stage: Stage::Denoising, segments_done: 1, segments_total: 4, elapsed: Duration::from_secs(2), audio_secs: 8.0 };
+        assert_eq!(p.eta(), Some(Duration::from_secs(6)));
+        assert_eq!(p.rtf(), Some(1.0));
+        p.segments_done = 0;
+        assert_eq!((p.eta(), p.rtf()), (None, None));
+        p.stage = Stage::Done;
+        assert_eq!(p.eta(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_tracker_reports_and_cancels() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let token = CancellationToken::new();
+        let tracker = Tracker::new(Some(Arc::new(move |p: &Progress| log.lock().unwrap().push((p.stage, p.segments_done)))), token.clone());
+        tracker.stage(Stage::Denoising).unwrap();
+        tracker.set_segments(2);
+        tracker.segment_done().unwrap();
+
+        token.clone().cancel();
+        assert!(matches!(tracker.segment_done(), Err(ZipEnhancerError::Cancelled)));
+        assert!(tracker.stage(Stage::Writing).is_err());
+        assert!(tracker.stage(Stage::Done).is_ok());
+        assert_eq!(seen.lock().unwrap()[..3], [(Stage::Denoising, 0), (Stage::Denoising, 0), (Stage::Denoising, 1)]);
+    }
+
+    #[test]
+    fn test_child_token() {
+        let parent = CancellationToken::new();
+        let (a, b) = (parent.child(), parent.child());
+        a.cancel();
+        assert!(a.is_cancelled() && !b.is_cancelled() && !parent.is_cancelled());
+        parent.cancel();
+        assert!(b.is_cancelled() && b.child().check().is_err());
+    }
+}
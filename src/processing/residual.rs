@@ -0,0 +1,52 @@
+//! Residual Stem - The component removed by enhancement (input minus enhanced)
+
+use ndarray::Array1;
+use super::AudioSegment;
+use super::loudness::to_db;
+
+/// Compute `input - enhanced` over the common length of both aligned signals, limited
+/// to full scale so the stem can be written as integer PCM
+pub fn compute_residual(input: &Array1<f32>, enhanced: &Array1<f32>) -> Array1<f32> {
+    let len = input.len().min(enhanced.len());
+    Array1::from_iter((0..len).map(|i| (input[i] - enhanced[i]).clamp(-1.0, 1.0)))
+}
+
+/// Residual energy per segment in dBFS.
+///
+/// Segment boundaries are given at the working rate and scaled by `rate_ratio`
+/// (output rate / working rate) onto the residual.
+pub fn segment_energy_db(residual: &Array1<f32>, segments: &[AudioSegment], rate_ratio: f64) -> Vec<f32> {
+    segments.iter().map(|seg| {
+        let start = ((seg.start_sample as f64 * rate_ratio) as usize).min(residual.len());
+        let end = ((seg.end_sample as f64 * rate_ratio) as usize).min(residual.len());
+        if end <= start {
+            return to_db(0.0);
+        }
+        let mean_sq = residual.slice(ndarray::s![start..end]).iter().map(|x| x * x).sum::<f32>() / (end - start) as f32;
+        to_db(mean_sq.sqrt())
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioData;
+
+    #[test]
+    fn test_residual() {
+        let input = Array1::from(vec![0.5, 0.5, 0.5]);
+        let enhanced = Array1::from(vec![0.25, 0.5, 0.75]);
+        assert_eq!(compute_residual(&input, &enhanced).to_vec(), vec![0.25, 0.0, -0.25]);
+        let clipped = compute_residual(&Array1::from(vec![0.9, -0.9]), &Array1::from(vec![-0.9, 0.9]));
+        assert_eq!(clipped.to_vec(), vec![1.0, -1.0]);
+    }
+
+    #[test]
+    fn test_segment_energy_scaled() {
+        let residual = Array1::from_iter((0..8).map(|i| if i < 4 { 0.5 } else { 0.0 }));
+        let seg = |i, s, e| AudioSegment::new(i, AudioData::Mono(Array1::zeros(e - s)), s, e, true);
+        let energy = segment_energy_db(&residual, &[seg(0, 0, 2), seg(1, 2, 4)], 2.0);
+        assert!((energy[0] - to_db(0.5)).abs() < 1e-4);
+        assert_eq!(energy[1], to_db(0.0));
+    }
+}
@@ -0,0 +1,150 @@
+//! Spectral Gating - Model-free DSP denoiser, also usable as a post-filter
+
+use ndarray::{Array1, Array2};
+use serde::{Deserialize, Serialize};
+use crate::audio::spectral::{Stft, StftConfig};
+use crate::config::DenoiserConfig;
+use crate::error::Result;
+use super::loudness::from_db;
+
+/// Width of the soft transition around the gate threshold (dB)
+const SOFT_KNEE_DB: f32 = 6.0;
+/// Floor for magnitudes before conversion to dB
+const MAG_FLOOR: f32 = 1e-10;
+
+/// Denoising backend used by the enhancer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DenoiseBackend {
+    /// ZipEnhancer ONNX model
+    #[default]
+    Onnx,
+    /// Built-in spectral gate, no model or runtime required
+    Spectral,
+}
+
+impl DenoiseBackend {
+    pub fn uses_model(self) -> bool {
+        self == Self::Onnx
+    }
+}
+
+impl std::str::FromStr for DenoiseBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "onnx" | "model" => Ok(Self::Onnx),
+            "spectral" | "gate" | "dsp" => Ok(Self::Spectral),
+            _ => Err(format!("Unknown denoise backend: {} (expected onnx|spectral)", s)),
+        }
+    }
+}
+
+/// Spectral gate with a per-bin threshold estimated from the quietest frames
+#[derive(Debug, Clone)]
+pub struct SpectralGate {
+    stft: Stft,
+    threshold_db: Vec<f32>,
+    floor: f32,
+    smoothing_bins: usize,
+    smoothing_frames: usize,
+}
+
+fn magnitude_db(spectrogram: &Array2<crate::audio::spectral::Complex32>) -> Array2<f32> {
+    spectrogram.mapv(|c| 20.0 * c.norm().max(MAG_FLOOR).log10())
+}
+
+impl SpectralGate {
+    /// Estimate the noise profile from the quietest `noise_fraction` of frames of `signal`
+    pub fn fit(signal: &Array1<f32>, config: &DenoiserConfig, reduction_db: f32) -> Result<Self> {
+        let stft = Stft::new(StftConfig::new(config.fft_size, config.hop_size))?;
+        let mag_db = magnitude_db(&stft.forward(signal));
+        let bins = stft.config().num_bins();
+
+        let levels: Vec<f32> = mag_db.rows().into_iter()
+            .map(|row| row.iter().map(|&d| from_db(d).powi(2)).sum::<f32>())
+            .collect();
+        let mut order: Vec<usize> = (0..levels.len()).collect();
+        order.sort_by(|&a, &b| levels[a].total_cmp(&levels[b]));
+        let count = ((order.len() as f32 * config.noise_fraction) as usize).clamp(1, order.len().max(1));
+        let quiet = &order[..count.min(order.len())];
+
+        let threshold_db = (0..bins).map(|k| {
+            if quiet.is_empty() { return 20.0 * MAG_FLOOR.log10(); }
+            let values: Vec<f32> = quiet.iter().map(|&t| mag_db[[t, k]]).collect();
+            let mean = values.iter().sum::<f32>() / values.len() as f32;
+            let std = (values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32).sqrt();
+            mean + config.threshold_std * std
+        }).collect();
+
+        Ok(Self {
+            stft,
+            threshold_db,
+            floor: from_db(-reduction_db.abs()),
+            smoothing_bins: config.smoothing_bins,
+            smoothing_frames: config.smoothing_frames,
+        })
+    }
+
+    /// Gain mask in [floor, 1], smoothed over neighbouring bins and frames
+    fn mask(&self, mag_db: &Array2<f32>) -> Array2<f32> {
+        let (frames, bins) = mag_db.dim();
+        let raw = Array2::from_shape_fn((frames, bins), |(t, k)| {
+            ((mag_db[[t, k]] - self.threshold_db[k]) / SOFT_KNEE_DB + 0.5).clamp(0.0, 1.0)
+        });
+        let (sf, sb) = (self.smoothing_frames, self.smoothing_bins);
+        Array2::from_shape_fn((frames, bins), |(t, k)| {
+            let (t0, t1) = (t.saturating_sub(sf), (t + sf + 1).min(frames));
+            let (k0, k1) = (k.saturating_sub(sb), (k + sb + 1).min(bins));
+            let window = raw.slice(ndarray::s![t0..t1, k0..k1]);
+            let g = window.sum() / window.len() as f32;
+            self.floor + (1.0 - self.floor) * g
+        })
+    }
+
+    /// Denoise `data`, returning a signal of the same length
+    pub fn process(&self, data: &Array1<f32>) -> Array1<f32> {
+        let spectrogram = self.stft.forward(data);
+        let mask = self.mask(&magnitude_db(&spectrogram));
+        let gated = ndarray::Zip::from(&spectrogram).and(&mask).map_collect(|&c, &g| c * g);
+        self.stft.inverse(&gated, data.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn rms(x: &[f32]) -> f32 {
+        (x.iter().map(|v| v * v).sum::<f32>() / x.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_gate_removes_noise_keeps_tone() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let tone: Vec<f32> = (0..32000)
+            .map(|i| if i >= 16000 { 0.3 * (std::f32::consts::TAU * 440.0 * i as f32 / 16000.0).sin() } else { 0.0 })
+            .collect();
+        let noisy = Array1::from_iter(tone.iter().map(|t| t + rng.gen_range(-0.01..0.01)));
+
+        let config = DenoiserConfig::default();
+        let gate = SpectralGate::fit(&noisy, &config, config.reduction_db).unwrap();
+        let out = gate.process(&noisy).to_vec();
+        assert_eq!(out.len(), noisy.len());
+
+        let noise_only = noisy.as_slice().unwrap();
+        // Noise-only half is attenuated by more than 12 dB, the tone stays within 1 dB
+        assert!(rms(&out[1000..15000]) < rms(&noise_only[1000..15000]) * 0.25);
+        let ratio = rms(&out[18000..31000]) / rms(&tone[18000..31000]);
+        assert!((0.89..1.12).contains(&ratio), "{}", ratio);
+    }
+
+    #[test]
+    fn test_backend_parse() {
+        assert_eq!("spectral".parse::<DenoiseBackend>().unwrap(), DenoiseBackend::Spectral);
+        assert!("ONNX".parse::<DenoiseBackend>().unwrap().uses_model());
+        assert!("wavelet".parse::<DenoiseBackend>().is_err());
+    }
+}
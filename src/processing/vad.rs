@@ -0,0 +1,229 @@
+//! Voice Activity Detection - Energy and spectral-shape classifier, no model required
+
+use std::path::Path;
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use crate::audio::spectral::{Stft, StftConfig};
+use crate::config::VadConfig;
+use crate::error::{ZipEnhancerError, Result};
+use super::AudioSegment;
+use super::loudness::to_db;
+
+/// Analysis frame length in samples for spectral features
+const SPECTRUM_SIZE: usize = 256;
+/// Percentile of frame energies used as the noise floor estimate
+const NOISE_FLOOR_PERCENTILE: f32 = 0.1;
+/// Frames this far above the threshold count as speech regardless of spectral shape
+const STRONG_MARGIN_DB: f32 = 10.0;
+/// Spectral flatness above which a frame looks like noise
+const NOISE_FLATNESS: f32 = 0.45;
+
+/// How segments classified as non-speech are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SilenceMode {
+    /// Run inference on every segment (VAD only reports regions)
+    #[default]
+    Infer,
+    /// Skip inference and output the original attenuated by `attenuation_db`
+    Attenuate,
+    /// Skip inference and output the original unchanged
+    Passthrough,
+}
+
+impl std::str::FromStr for SilenceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "infer" => Ok(Self::Infer),
+            "attenuate" => Ok(Self::Attenuate),
+            "passthrough" => Ok(Self::Passthrough),
+            _ => Err(format!("Unknown VAD silence mode: {} (expected infer|attenuate|passthrough)", s)),
+        }
+    }
+}
+
+/// A contiguous region of detected speech
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeechRegion {
+    pub start_secs: f64,
+    pub end_secs: f64,
+}
+
+/// Frame-level VAD decisions for one signal
+#[derive(Debug, Clone)]
+pub struct VadResult {
+    pub frame_size: usize,
+    pub sample_rate: u32,
+    pub frames: Vec<bool>,
+    pub noise_floor_db: f32,
+}
+
+/// Per-frame features
+#[derive(Debug, Clone, Copy)]
+struct FrameFeatures {
+    energy_db: f32,
+    zero_crossing_rate: f32,
+    flatness: f32,
+}
+
+/// Spectral flatness (geometric / arithmetic mean of the power spectrum) of a Hann-windowed frame
+fn spectral_flatness(stft: &Stft, frame: &[f32]) -> f32 {
+    if frame.len() < 8 { return 1.0; }
+    let spectrum = stft.analyze_frame(frame);
+    // Skip DC and Nyquist
+    let count = spectrum.len() - 2;
+    let (mut log_sum, mut sum) = (0.0f64, 0.0f64);
+    for c in spectrum.iter().skip(1).take(count) {
+        let power = c.norm_sqr() as f64 + 1e-12;
+        log_sum += power.ln();
+        sum += power;
+    }
+    let count = count as f64;
+    ((log_sum / count).exp() / (sum / count)) as f32
+}
+
+fn frame_features(stft: &Stft, frame: &[f32]) -> FrameFeatures {
+    let rms = (frame.iter().map(|x| x * x).sum::<f32>() / frame.len().max(1) as f32).sqrt();
+    let crossings = frame.windows(2).filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0)).count();
+    FrameFeatures {
+        energy_db: to_db(rms),
+        zero_crossing_rate: crossings as f32 / frame.len().max(1) as f32,
+        flatness: spectral_flatness(stft, frame),
+    }
+}
+
+/// Run frame-level voice activity detection on a mono signal
+pub fn detect(data: &Array1<f32>, sample_rate: u32, config: &VadConfig) -> VadResult {
+    let frame_size = ((config.frame_ms / 1000.0 * sample_rate as f32) as usize).max(1);
+    let samples = data.to_vec();
+    // Window the first SPECTRUM_SIZE samples of each frame (all of it for shorter frames)
+    let win_length = SPECTRUM_SIZE.min(frame_size);
+    let stft = Stft::new(StftConfig { win_length, ..StftConfig::new(SPECTRUM_SIZE, win_length) })
+        .expect("valid VAD spectrum size");
+    let features: Vec<FrameFeatures> = samples.chunks(frame_size).map(|f| frame_features(&stft, f)).collect();
+
+    let mut energies: Vec<f32> = features.iter().map(|f| f.energy_db).collect();
+    energies.sort_by(|a, b| a.total_cmp(b));
+    let noise_floor_db = energies.get((energies.len() as f32 * NOISE_FLOOR_PERCENTILE) as usize).copied().unwrap_or(-120.0);
+    let threshold = (noise_floor_db + config.energy_margin_db).max(config.min_energy_db);
+
+    let raw: Vec<bool> = features.iter().map(|f| {
+        if f.energy_db < threshold { return false; }
+        let noisy_shape = f.flatness > NOISE_FLATNESS && f.zero_crossing_rate > 0.3;
+        !noisy_shape || f.energy_db > threshold + STRONG_MARGIN_DB
+    }).collect();
+
+    // Hangover: keep speech active for a while after the last speech frame
+    let hangover = (config.hangover_ms / config.frame_ms).round() as usize;
+    let mut frames = raw.clone();
+    let mut remaining = 0;
+    for (i, &speech) in raw.iter().enumerate() {
+        if speech { remaining = hangover; } else if remaining > 0 { frames[i] = true; remaining -= 1; }
+    }
+
+    VadResult { frame_size, sample_rate, frames, noise_floor_db }
+}
+
+impl VadResult {
+    /// Fraction of speech frames between two sample positions
+    pub fn speech_ratio(&self, start_sample: usize, end_sample: usize) -> f32 {
+        let first = start_sample / self.frame_size;
+        let last = end_sample.div_ceil(self.frame_size).min(self.frames.len());
+        if last <= first { return 0.0; }
+        self.frames[first..last].iter().filter(|&&s| s).count() as f32 / (last - first) as f32
+    }
+
+    /// Merge consecutive speech frames into regions
+    pub fn regions(&self) -> Vec<SpeechRegion> {
+        let frame_secs = self.frame_size as f64 / self.sample_rate as f64;
+        let mut regions = Vec::new();
+        let mut start = None;
+        for (i, &speech) in self.frames.iter().chain(std::iter::once(&false)).enumerate() {
+            match (speech, start) {
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    regions.push(SpeechRegion { start_secs: s as f64 * frame_secs, end_secs: i as f64 * frame_secs });
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        regions
+    }
+
+    /// Mark segments whose speech ratio is below `min_speech_ratio` as non-speech
+    pub fn classify_segments(&self, segments: &mut [AudioSegment], min_speech_ratio: f32) {
+        for seg in segments.iter_mut() {
+            seg.is_speech = self.speech_ratio(seg.start_sample, seg.end_sample) >= min_speech_ratio;
+        }
+    }
+}
+
+/// Render speech regions as RTTM lines
+pub fn to_rttm(regions: &[SpeechRegion], file_id: &str) -> String {
+    regions.iter()
+        .map(|r| format!("SPEAKER {} 1 {:.3} {:.3} <NA> <NA> speech <NA> <NA>\n", file_id, r.start_secs, r.end_secs - r.start_secs))
+        .collect()
+}
+
+/// Write speech regions to `path`; `.rttm` files use RTTM, anything else JSON
+pub fn export_regions(regions: &[SpeechRegion], path: &Path, file_id: &str) -> Result<()> {
+    let content = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("rttm")) {
+        to_rttm(regions, file_id)
+    } else {
+        serde_json::to_string_pretty(regions)
+            .map_err(|e| ZipEnhancerError::processing(format!("Failed to serialize speech regions: {}", e)))?
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn speech_in_noise() -> Array1<f32> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        Array1::from_iter((0..48000).map(|i| {
+            let noise = rng.gen_range(-0.002..0.002);
+            let t = i as f32 / 16000.0;
+            let tone = if (16000..32000).contains(&i) {
+                0.3 * (std::f32::consts::TAU * 180.0 * t).sin() + 0.1 * (std::f32::consts::TAU * 720.0 * t).sin()
+            } else { 0.0 };
+            noise + tone
+        }))
+    }
+
+    #[test]
+    fn test_detects_speech_region() {
+        let config = VadConfig { hangover_ms: 0.0, ..Default::default() };
+        let result = detect(&speech_in_noise(), 16000, &config);
+        let regions = result.regions();
+        assert_eq!(regions.len(), 1);
+        assert!((regions[0].start_secs - 1.0).abs() < 0.03);
+        assert!((regions[0].end_secs - 2.0).abs() < 0.03);
+    }
+
+    #[test]
+    fn test_classify_segments() {
+        let result = detect(&speech_in_noise(), 16000, &VadConfig::default());
+        let seg = |i, s: usize, e: usize| AudioSegment::new(i, crate::audio::AudioData::Mono(Array1::zeros(e - s)), s, e, true);
+        let mut segments = vec![seg(0, 0, 8000), seg(1, 16000, 24000)];
+        result.classify_segments(&mut segments, 0.05);
+        assert!(!segments[0].is_speech);
+        assert!(segments[1].is_speech);
+    }
+
+    #[test]
+    fn test_rttm_format() {
+        let rttm = to_rttm(&[SpeechRegion { start_secs: 1.0, end_secs: 2.5 }], "file1");
+        assert_eq!(rttm, "SPEAKER file1 1 1.000 1.500 <NA> <NA> speech <NA> <NA>\n");
+        assert_eq!("Passthrough".parse::<SilenceMode>().unwrap(), SilenceMode::Passthrough);
+    }
+}
@@ -0,0 +1,232 @@
+//! Golden-Output Regression Harness
+//!
+//! Runs fixed synthetic inputs through the full pipeline with the deterministic
+//! spectral backend standing in for the model, and compares the written outputs
+//! with stored golden WAV files. Every case also runs through the model path of
+//! both the serial and the parallel execution strategy, with a deterministic
+//! stand-in for the model, whose outputs must be bit-identical.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use ndarray::Array1;
+use crate::audio::{AudioFormat, WavAudio};
+use crate::bench::ScratchDir;
+use crate::config::Config;
+use crate::error::{ZipEnhancerError, Result};
+use crate::processing::{DenoiseBackend, Enhancer, Execution, NormalizationPolicy};
+use crate::processing::executor::StandIn;
+use crate::synth::{self, GenerateSpec, NoiseKind, SignalKind};
+
+/// Golden files checked in with the sources
+pub const DEFAULT_GOLDEN_DIR: &str = "tests/golden";
+const INPUT_SECS: f64 = 1.0;
+const INPUT_SEED: u64 = 46;
+const INPUT_SNR_DB: f32 = 5.0;
+
+/// One fixed input and configuration
+#[derive(Debug, Clone, Copy)]
+pub struct GoldenCase {
+    pub name: &'static str,
+    pub sample_rate: u32,
+    pub noise: NoiseKind,
+    configure: fn(&mut Config),
+}
+
+/// The cases, each aimed at a stage whose output drifts silently when changed
+pub const CASES: [GoldenCase; 6] = [
+    GoldenCase { name: "baseline", sample_rate: 16000, noise: NoiseKind::Pink, configure: |_| {} },
+    // Loudness normalization would mask AGC gain changes
+    GoldenCase { name: "agc", sample_rate: 16000, noise: NoiseKind::Babble, configure: |c| {
+        c.processing.enable_agc = true;
+        c.normalization.policy = NormalizationPolicy::None;
+    } },
+    GoldenCase { name: "peak_normalization", sample_rate: 16000, noise: NoiseKind::White, configure: |c| {
+        c.normalization.policy = NormalizationPolicy::Peak;
+        c.normalization.target_db = -3.0;
+    } },
+    GoldenCase { name: "resample_44k", sample_rate: 44100, noise: NoiseKind::Hum, configure: |_| {} },
+    GoldenCase { name: "crossfade_mix", sample_rate: 16000, noise: NoiseKind::Clicks, configure: |c| {
+        c.audio.segment_size = 4000;
+        c.audio.overlap_ratio = 0.25;
+        c.mix.strength = 0.6;
+    } },
+    GoldenCase { name: "vad", sample_rate: 16000, noise: NoiseKind::White, configure: |c| c.vad.enabled = true },
+];
+
+impl GoldenCase {
+    pub fn config(&self) -> Config {
+        let mut config = Config::default();
+        config.denoiser.backend = DenoiseBackend::Spectral;
+        (self.configure)(&mut config);
+        config
+    }
+
+    /// The same settings on the model path, for the stand-in model
+    fn model_config(&self) -> Config {
+        let mut config = self.config();
+        config.denoiser.backend = DenoiseBackend::Onnx;
+        config
+    }
+
+    /// Seeded speech-like input, 32-bit float mono
+    pub fn input(&self) -> WavAudio {
+        let spec = GenerateSpec { duration_secs: INPUT_SECS, sample_rate: self.sample_rate, seed: INPUT_SEED, ..Default::default() };
+        let mixture = synth::generate_mixture(&spec, SignalKind::Speech, self.noise, INPUT_SNR_DB);
+        WavAudio::new_mono(self.sample_rate, Array1::from(mixture.noisy), AudioFormat::Float32)
+    }
+
+    pub fn golden_path(&self, golden_dir: &Path) -> PathBuf {
+        golden_dir.join(format!("{}.wav", self.name))
+    }
+}
+
+/// Difference of an output from its reference
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioDiff {
+    pub max_abs_error: f32,
+    /// Reference energy over error energy; infinite when identical
+    pub snr_db: f64,
+    /// Output length minus reference length (samples)
+    pub length_delta: i64,
+    pub sample_rate_matches: bool,
+}
+
+impl AudioDiff {
+    pub fn between(output: &WavAudio, reference: &WavAudio) -> Self {
+        let (out, reference_data) = (output.data().to_mono(), reference.data().to_mono());
+        let (mut max_abs_error, mut error, mut energy) = (0.0f32, 0.0f64, 0.0f64);
+        for (o, r) in out.iter().zip(reference_data.iter()) {
+            max_abs_error = max_abs_error.max((o - r).abs());
+            error += ((o - r) as f64).powi(2);
+            energy += (*r as f64).powi(2);
+        }
+        Self {
+            max_abs_error,
+            snr_db: if error == 0.0 { f64::INFINITY } else { 10.0 * (energy / error).log10() },
+            length_delta: out.len() as i64 - reference_data.len() as i64,
+            sample_rate_matches: output.sample_rate() == reference.sample_rate(),
+        }
+    }
+
+    pub fn is_identical(&self) -> bool {
+        self.max_abs_error == 0.0 && self.length_delta == 0 && self.sample_rate_matches
+    }
+
+    pub fn within(&self, tolerance: &Tolerance) -> bool {
+        self.length_delta == 0 && self.sample_rate_matches
+            && self.max_abs_error <= tolerance.max_abs_error && self.snr_db >= tolerance.min_snr_db
+    }
+}
+
+impl fmt::Display for AudioDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "max |err| {:.6}, SNR {:.1} dB", self.max_abs_error, self.snr_db)?;
+        if self.length_delta != 0 { write!(f, ", length {:+} samples", self.length_delta)?; }
+        if !self.sample_rate_matches { write!(f, ", sample rate differs")?; }
+        Ok(())
+    }
+}
+
+/// Largest accepted deviation from a golden output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    pub max_abs_error: f32,
+    pub min_snr_db: f64,
+}
+
+impl Default for Tolerance {
+    /// About three 16-bit LSBs, enough for floating-point differences between platforms
+    fn default() -> Self {
+        Self { max_abs_error: 1e-4, min_snr_db: 70.0 }
+    }
+}
+
+/// Outcome of one case
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseStatus {
+    Pass,
+    /// Differs from the golden beyond tolerance, or serial and parallel differ
+    Fail,
+    /// No golden file; bless to create it
+    Missing,
+    /// Golden written from this run
+    Blessed,
+}
+
+impl fmt::Display for CaseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self { Self::Pass => "PASS", Self::Fail => "FAIL", Self::Missing => "MISSING", Self::Blessed => "BLESSED" })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CaseReport {
+    pub name: &'static str,
+    pub status: CaseStatus,
+    /// Against the golden; `None` when missing or blessed
+    pub golden: Option<AudioDiff>,
+    /// Parallel against serial output of the stand-in model
+    pub parity: AudioDiff,
+}
+
+/// Run every case; with `bless`, (re)write the goldens from this run instead of comparing.
+/// Blessing refuses to record outputs on which serial and parallel disagree.
+pub fn run(golden_dir: &Path, tolerance: &Tolerance, bless: bool) -> Result<Vec<CaseReport>> {
+    let scratch = ScratchDir::new("regress")?;
+    CASES.iter().map(|case| {
+        let input = scratch.0.join(format!("{}_in.wav", case.name));
+        case.input().save_to_file(&input)?;
+        let path = |suffix: &str| scratch.0.join(format!("{}_{}.wav", case.name, suffix));
+        let (spectral, serial, parallel) = (path("spectral"), path("serial"), path("parallel"));
+        Enhancer::new(case.config(), Execution::Serial)?.process_file(&input, &spectral)?;
+        Enhancer::with_sessions(case.model_config(), Execution::Serial, Some(StandIn::sessions()))?.process_file(&input, &serial)?;
+        Enhancer::with_sessions(case.model_config(), Execution::Parallel { workers: 2 }, Some(StandIn::sessions()))?.process_file(&input, &parallel)?;
+        let output = WavAudio::from_file(&spectral)?;
+        let parity = AudioDiff::between(&WavAudio::from_file(&parallel)?, &WavAudio::from_file(&serial)?);
+
+        let golden_path = case.golden_path(golden_dir);
+        if bless {
+            if !parity.is_identical() {
+                return Err(ZipEnhancerError::processing(format!("{}: serial and parallel outputs differ ({}), not blessing", case.name, parity)));
+            }
+            std::fs::create_dir_all(golden_dir).map_err(|e| ZipEnhancerError::io_at(golden_dir, e))?;
+            std::fs::copy(&spectral, &golden_path).map_err(|e| ZipEnhancerError::io_at(&golden_path, e))?;
+            return Ok(CaseReport { name: case.name, status: CaseStatus::Blessed, golden: None, parity });
+        }
+        if !golden_path.exists() {
+            return Ok(CaseReport { name: case.name, status: CaseStatus::Missing, golden: None, parity });
+        }
+        let golden = AudioDiff::between(&output, &WavAudio::from_file(&golden_path)?);
+        let status = if golden.within(tolerance) && parity.is_identical() { CaseStatus::Pass } else { CaseStatus::Fail };
+        Ok(CaseReport { name: case.name, status, golden: Some(golden), parity })
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_and_tolerance() {
+        let reference = WavAudio::new_mono(16000, Array1::from(vec![0.5f32; 100]), AudioFormat::Float32);
+        assert!(AudioDiff::between(&reference, &reference).is_identical());
+
+        let mut shifted = vec![0.5f32; 100];
+        shifted[10] = 0.50005;
+        let diff = AudioDiff::between(&WavAudio::new_mono(16000, Array1::from(shifted), AudioFormat::Float32), &reference);
+        assert!((diff.max_abs_error - 5e-5).abs() < 1e-6 && diff.snr_db > 80.0);
+        assert!(diff.within(&Tolerance::default()) && !diff.is_identical());
+
+        let short = WavAudio::new_mono(16000, Array1::from(vec![0.5f32; 99]), AudioFormat::Float32);
+        assert!(!AudioDiff::between(&short, &reference).within(&Tolerance::default()));
+    }
+
+    /// Fails when a change alters the pipeline output; re-bless with `zipenhancer regress --bless`
+    #[test]
+    fn test_outputs_match_goldens() {
+        let golden_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(DEFAULT_GOLDEN_DIR);
+        for report in run(&golden_dir, &Tolerance::default(), false).unwrap() {
+            assert_eq!(report.status, CaseStatus::Pass, "{}: golden {:?}, parity {}", report.name, report.golden, report.parity);
+        }
+    }
+}
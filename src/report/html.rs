@@ -0,0 +1,159 @@
+//! Self-contained HTML QA report (inline CSS/JS, thumbnails as data URIs)
+
+use std::fmt::Write;
+use base64::Engine;
+use ndarray::Array1;
+use crate::audio::WavAudio;
+use crate::error::Result;
+use crate::visualize::{render_spectrogram, render_waveform, RgbImage, VisualizeConfig};
+use super::record::FileRecord;
+
+/// Output clipping above this fraction of samples is an outlier
+const HEAVY_CLIPPING_RATIO: f64 = 0.001;
+/// Output RMS below this level is considered near-silent (dBFS)
+const NEAR_SILENT_DB: f32 = -60.0;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:20px;background:#fafafa;color:#222}\
+table{border-collapse:collapse;font-size:13px}th,td{border:1px solid #ccc;padding:4px 6px;vertical-align:middle}\
+th{background:#333;color:#fff;cursor:pointer;position:sticky;top:0}tr.outlier{background:#fde2e2}\
+td.num{text-align:right;font-variant-numeric:tabular-nums}td img{display:block;image-rendering:pixelated}\
+.flag{color:#b00020;font-weight:bold}.warn{color:#8a5a00}";
+
+const SCRIPT: &str = "document.querySelectorAll('th').forEach(function(th,i){th.addEventListener('click',function(){\
+var body=th.closest('table').tBodies[0],rows=Array.from(body.rows),asc=th.dataset.asc!=='1';\
+rows.sort(function(a,b){var x=a.cells[i].dataset.sort,y=b.cells[i].dataset.sort;\
+var nx=parseFloat(x),ny=parseFloat(y),c=(isNaN(nx)||isNaN(ny))?String(x).localeCompare(String(y)):nx-ny;return asc?c:-c;});\
+rows.forEach(function(r){body.appendChild(r);});th.dataset.asc=asc?'1':'0';});});";
+
+/// Thumbnail geometry used in report rows
+pub fn thumbnail_config() -> VisualizeConfig {
+    VisualizeConfig { fft_size: 512, hop_size: 256, width: 160, height: 48, waveform_height: 32, ..Default::default() }
+}
+
+/// Reasons a record stands out: negative SNR gain, heavy clipping or near-silent output
+pub fn outliers(record: &FileRecord) -> Vec<&'static str> {
+    let mut reasons = Vec::new();
+    if record.quality.snr_improvement_db < 0.0 {
+        reasons.push("negative SNR gain");
+    }
+    let samples = record.alignment.output_samples.max(1) as f64;
+    if record.quality.output.clipped_samples as f64 / samples > HEAVY_CLIPPING_RATIO {
+        reasons.push("heavy clipping");
+    }
+    if record.quality.output.levels.rms_dbfs < NEAR_SILENT_DB {
+        reasons.push("near-silent output");
+    }
+    reasons
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn data_uri(image: &RgbImage) -> Result<String> {
+    Ok(format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(image.encode_png()?)))
+}
+
+fn load_mono(path: &std::path::Path) -> Option<(Array1<f32>, u32)> {
+    WavAudio::from_file(path).ok().map(|a| (a.data().to_mono(), a.sample_rate()))
+}
+
+/// `<img>` cells for input/output spectrograms and the output waveform; empty if files are missing
+fn thumbnail_cells(record: &FileRecord, config: &VisualizeConfig) -> Result<String> {
+    let (Some((input, in_rate)), Some((output, out_rate))) = (load_mono(&record.input_path), load_mono(&record.output_path)) else {
+        return Ok("<td colspan=\"3\">n/a</td>".to_string());
+    };
+    let duration = (input.len() as f64 / in_rate as f64).max(output.len() as f64 / out_rate as f64);
+    let images = [
+        render_spectrogram(&input, in_rate, duration, config)?,
+        render_spectrogram(&output, out_rate, duration, config)?,
+        render_waveform(&output, out_rate, duration, config),
+    ];
+    let mut cells = String::new();
+    for image in &images {
+        write!(cells, "<td><img src=\"{}\" width=\"{}\" height=\"{}\" alt=\"\"></td>", data_uri(image)?, image.width, image.height).ok();
+    }
+    Ok(cells)
+}
+
+fn num_cell(value: f64, precision: usize) -> String {
+    format!("<td class=\"num\" data-sort=\"{:.6}\">{:.*}</td>", value, precision, value)
+}
+
+/// Render the report for `records`; thumbnails are read from each record's input/output WAV
+pub fn render_report(records: &[FileRecord], thumbnails: bool) -> Result<String> {
+    let config = thumbnail_config();
+    let total_secs: f64 = records.iter().map(|r| r.duration_secs).sum();
+    let mean_rtf = records.iter().map(|r| r.rtf).sum::<f64>() / records.len().max(1) as f64;
+    let flagged = records.iter().filter(|r| r.error.is_some() || !outliers(r).is_empty()).count();
+
+    let mut html = String::new();
+    write!(html, "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>ZipEnhancer QA report</title><style>{}</style></head><body>", STYLE).ok();
+    write!(html, "<h1>ZipEnhancer QA report</h1><p>{} files, {:.1} s audio, mean RTF {:.3}, <span class=\"flag\">{} outliers</span></p>",
+        records.len(), total_secs, mean_rtf, flagged).ok();
+    html.push_str("<table><thead><tr><th>File</th><th>Duration (s)</th><th>RTF</th><th>Segments</th><th>Avg inference (ms)</th>\
+<th>Peak &Delta; (dB)</th><th>RMS &Delta; (dB)</th><th>SNR gain (dB)</th><th>Clipped</th><th>Warnings</th>");
+    if thumbnails {
+        html.push_str("<th>Input</th><th>Output</th><th>Waveform</th>");
+    }
+    html.push_str("</tr></thead><tbody>");
+
+    for record in records {
+        let flags = outliers(record);
+        let (input, output) = (&record.quality.input.levels, &record.quality.output.levels);
+        let name = record.input_path.file_name().map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| record.input_path.display().to_string());
+
+        let outlier = record.error.is_some() || !flags.is_empty();
+        write!(html, "<tr{}>", if outlier { " class=\"outlier\"" } else { "" }).ok();
+        write!(html, "<td data-sort=\"{0}\" title=\"{1}\">{0}</td>", escape(&name), escape(&record.input_path.display().to_string())).ok();
+        html.push_str(&num_cell(record.duration_secs, 2));
+        html.push_str(&num_cell(record.rtf, 3));
+        html.push_str(&num_cell(record.segment_count as f64, 0));
+        html.push_str(&num_cell(record.avg_inference_ms, 1));
+        html.push_str(&num_cell((output.peak_dbfs - input.peak_dbfs) as f64, 1));
+        html.push_str(&num_cell((output.rms_dbfs - input.rms_dbfs) as f64, 1));
+        html.push_str(&num_cell(record.quality.snr_improvement_db as f64, 1));
+        html.push_str(&num_cell(record.quality.output.clipped_samples as f64, 0));
+
+        let notes: Vec<String> = record.error.iter().map(|e| format!("<span class=\"flag\">{} error: {}</span>", e.kind, escape(&e.message)))
+            .chain(flags.iter().map(|f| format!("<span class=\"flag\">{}</span>", f)))
+            .chain(record.warnings.iter().map(|w| format!("<span class=\"warn\">{}</span>", escape(w))))
+            .collect();
+        write!(html, "<td data-sort=\"{}\">{}</td>", notes.len(), notes.join("<br>")).ok();
+        if thumbnails {
+            html.push_str(&thumbnail_cells(record, &config)?);
+        }
+        html.push_str("</tr>");
+    }
+    write!(html, "</tbody></table><script>{}</script></body></html>", SCRIPT).ok();
+    Ok(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outliers() {
+        let mut record = FileRecord::default();
+        record.alignment.output_samples = 16000;
+        record.quality.output.levels.rms_dbfs = -20.0;
+        record.quality.snr_improvement_db = 5.0;
+        assert!(outliers(&record).is_empty());
+
+        record.quality.snr_improvement_db = -1.0;
+        record.quality.output.clipped_samples = 100;
+        record.quality.output.levels.rms_dbfs = -80.0;
+        assert_eq!(outliers(&record), vec!["negative SNR gain", "heavy clipping", "near-silent output"]);
+    }
+
+    #[test]
+    fn test_report_is_self_contained() {
+        let record = FileRecord { input_path: "<odd>.wav".into(), warnings: vec!["a & b".into()], ..Default::default() };
+        let html = render_report(&[record], true).unwrap();
+        assert!(html.contains("&lt;odd&gt;.wav") && html.contains("a &amp; b"));
+        assert!(html.contains("n/a"));
+        assert!(!html.contains("http://") && !html.contains("https://"));
+    }
+}
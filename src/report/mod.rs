@@ -0,0 +1,10 @@
+//! QA Report Module
+//!
+//! Per-file result records written after processing, and a single self-contained
+//! HTML report over many of them.
+
+pub mod record;
+pub mod html;
+
+pub use record::{load_records, ErrorRecord, FileRecord};
+pub use html::{outliers, render_report};
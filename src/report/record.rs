@@ -0,0 +1,176 @@
+//! Per-file result records written by the CLI and read back by the report
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::error::{ZipEnhancerError, Result};
+use crate::metrics::QualityEstimate;
+use crate::processing::{AlignmentInfo, LoudnessReport, ProcessingResult, SpeechRegion};
+
+/// Serializable summary of one processed (or failed) file.
+///
+/// Only `input_path` is required, so other JSON files next to the records (a `generate`
+/// manifest, an `evaluate` report) are not mistaken for empty records.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub input_path: PathBuf,
+    #[serde(default)]
+    pub output_path: PathBuf,
+    #[serde(default)]
+    pub residual_path: Option<PathBuf>,
+    #[serde(default)]
+    pub duration_secs: f64,
+    #[serde(default)]
+    pub processing_time_secs: f64,
+    #[serde(default)]
+    pub rtf: f64,
+    #[serde(default)]
+    pub segment_count: usize,
+    #[serde(default)]
+    pub avg_inference_ms: f64,
+    #[serde(default)]
+    pub skipped_segments: usize,
+    #[serde(default)]
+    pub cpu_saved_ms: f64,
+    #[serde(default)]
+    pub worker_count: usize,
+    #[serde(default)]
+    pub residual_energy_db: Vec<f32>,
+    #[serde(default)]
+    pub speech_regions: Option<Vec<SpeechRegion>>,
+    #[serde(default)]
+    pub quality: QualityEstimate,
+    #[serde(default)]
+    pub loudness: LoudnessReport,
+    #[serde(default)]
+    pub alignment: AlignmentInfo,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Set when processing this file failed; the metrics are then empty
+    #[serde(default)]
+    pub error: Option<ErrorRecord>,
+}
+
+/// Why a file failed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErrorRecord {
+    /// `ZipEnhancerError::kind`
+    pub kind: String,
+    /// `ZipEnhancerError::code`, e.g. `input_not_found`
+    pub code: String,
+    pub message: String,
+    pub exit_code: i32,
+}
+
+impl From<&ZipEnhancerError> for ErrorRecord {
+    fn from(error: &ZipEnhancerError) -> Self {
+        Self { kind: error.kind().to_string(), code: error.code().to_string(), message: error.message(), exit_code: error.exit_code() }
+    }
+}
+
+impl From<&ProcessingResult> for FileRecord {
+    fn from(result: &ProcessingResult) -> Self {
+        let metrics = &result.performance_metrics;
+        Self {
+            input_path: result.input_path.clone(),
+            output_path: result.output_path.clone(),
+            residual_path: result.residual_path.clone(),
+            duration_secs: metrics.input_duration_seconds,
+            processing_time_secs: metrics.processing_time_seconds,
+            rtf: metrics.real_time_factor,
+            segment_count: metrics.segment_count,
+            avg_inference_ms: metrics.average_inference_time_ms,
+            skipped_segments: metrics.skipped_segments,
+            cpu_saved_ms: metrics.cpu_saved_ms,
+            worker_count: metrics.worker_count,
+            residual_energy_db: metrics.residual_energy_db.clone(),
+            speech_regions: result.speech_regions.clone(),
+            quality: result.quality,
+            loudness: result.loudness.clone(),
+            alignment: result.alignment.clone(),
+            warnings: result.quality.warnings(),
+            error: None,
+        }
+    }
+}
+
+impl FileRecord {
+    /// Record of a file that could not be processed
+    pub fn failed(input: &Path, output: &Path, error: &ZipEnhancerError) -> Self {
+        Self { input_path: input.to_path_buf(), output_path: output.to_path_buf(), error: Some(error.into()), ..Default::default() }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| ZipEnhancerError::processing(format!("Failed to serialize result: {}", e)))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| ZipEnhancerError::processing(format!("Invalid result file {}: {}", path.display(), e)))
+    }
+}
+
+/// Load every `.json` record under `paths` (files, or directories scanned non-recursively).
+///
+/// Files that are not valid records are skipped with a warning so one stray
+/// JSON file does not abort a report over thousands of results.
+pub fn load_records(paths: &[PathBuf]) -> Result<Vec<FileRecord>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            for entry in std::fs::read_dir(path)? {
+                let file = entry?.path();
+                if file.extension().is_some_and(|e| e.eq_ignore_ascii_case("json")) {
+                    files.push(file);
+                }
+            }
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files.sort();
+
+    let records: Vec<FileRecord> = files.iter()
+        .filter_map(|file| FileRecord::load(file).map_err(|e| log::warn!("{}", e.report())).ok())
+        .collect();
+    if records.is_empty() {
+        return Err(ZipEnhancerError::config("No result records found"));
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_roundtrip_and_directory_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let record = FileRecord { input_path: "a.wav".into(), rtf: 0.25, segment_count: 3, ..Default::default() };
+        record.save(&dir.path().join("a.json")).unwrap();
+        std::fs::write(dir.path().join("manifest.json"), r#"{"seed": 7, "pairs": [{"clean": "c.wav", "noisy": "n.wav"}]}"#).unwrap();
+
+        let records = load_records(&[dir.path().to_path_buf()]).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].segment_count, 3);
+        assert_eq!(records[0].input_path, PathBuf::from("a.wav"));
+    }
+
+    #[test]
+    fn test_failed_record() {
+        let record = FileRecord::failed(Path::new("a.wav"), Path::new("b.wav"), &ZipEnhancerError::audio("bad header"));
+        let json: serde_json::Value = serde_json::from_str(&serde_json::to_string(&record).unwrap()).unwrap();
+        assert_eq!(json["error"]["kind"], "audio");
+        assert_eq!(json["error"]["code"], "audio");
+        assert_eq!(json["error"]["exit_code"], crate::error::exit_code::AUDIO);
+        // Records written before the error field existed still load
+        let old: FileRecord = serde_json::from_str(r#"{"input_path": "x.wav", "rtf": 0.5}"#).unwrap();
+        assert!(old.error.is_none() && old.rtf == 0.5);
+    }
+}
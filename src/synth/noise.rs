@@ -0,0 +1,155 @@
+//! Noise sources: white, pink, brown, babble, mains hum and clicks
+
+use std::f32::consts::{PI, TAU};
+use std::fmt;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use super::signals::speech_like;
+
+/// Voices summed into babble
+const BABBLE_VOICES: usize = 6;
+/// Mean clicks per second
+const CLICK_RATE: f32 = 3.0;
+
+/// Kind of additive noise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoiseKind {
+    /// Flat spectrum Gaussian noise
+    White,
+    /// -3 dB/octave
+    Pink,
+    /// -6 dB/octave
+    Brown,
+    /// Several overlapping speech-like voices
+    Babble,
+    /// 50 or 60 Hz mains hum with harmonics
+    Hum,
+    /// Sparse decaying impulses
+    Clicks,
+}
+
+pub const ALL_NOISES: [NoiseKind; 6] = [NoiseKind::White, NoiseKind::Pink, NoiseKind::Brown, NoiseKind::Babble, NoiseKind::Hum, NoiseKind::Clicks];
+
+impl fmt::Display for NoiseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::White => "white",
+            Self::Pink => "pink",
+            Self::Brown => "brown",
+            Self::Babble => "babble",
+            Self::Hum => "hum",
+            Self::Clicks => "clicks",
+        })
+    }
+}
+
+impl std::str::FromStr for NoiseKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        ALL_NOISES.into_iter().find(|k| k.to_string() == s)
+            .ok_or_else(|| format!("Unknown noise: {} (expected white|pink|brown|babble|hum|clicks)", s))
+    }
+}
+
+/// `len` samples of `kind` at an arbitrary level (mixing sets the level)
+pub fn generate(kind: NoiseKind, len: usize, sample_rate: u32, rng: &mut impl Rng) -> Vec<f32> {
+    match kind {
+        NoiseKind::White => (0..len).map(|_| gaussian(rng)).collect(),
+        NoiseKind::Pink => pink(len, rng),
+        NoiseKind::Brown => {
+            let mut y = 0.0;
+            (0..len).map(|_| { y = 0.995 * y + 0.1 * gaussian(rng); y }).collect()
+        }
+        NoiseKind::Babble => {
+            let mut sum = vec![0.0; len];
+            for _ in 0..BABBLE_VOICES {
+                for (s, v) in sum.iter_mut().zip(speech_like(len, sample_rate, rng)) { *s += v; }
+            }
+            sum
+        }
+        NoiseKind::Hum => {
+            let mains = if rng.gen_bool(0.5) { 50.0 } else { 60.0 };
+            let phases: Vec<f32> = (0..8).map(|_| rng.gen_range(0.0..TAU)).collect();
+            (0..len).map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                phases.iter().enumerate().map(|(k, p)| (TAU * mains * (k + 1) as f32 * t + p).sin() / (k + 1) as f32).sum()
+            }).collect()
+        }
+        NoiseKind::Clicks => clicks(len, sample_rate, rng),
+    }
+}
+
+/// Standard normal sample (Box-Muller)
+pub fn gaussian(rng: &mut impl Rng) -> f32 {
+    let u: f32 = rng.gen_range(f32::EPSILON..1.0);
+    (-2.0 * u.ln()).sqrt() * (TAU * rng.gen_range(0.0f32..1.0)).cos()
+}
+
+/// Paul Kellet's pink filter on white noise
+fn pink(len: usize, rng: &mut impl Rng) -> Vec<f32> {
+    let mut b = [0.0f32; 7];
+    (0..len).map(|_| {
+        let w = gaussian(rng);
+        b[0] = 0.99886 * b[0] + w * 0.0555179;
+        b[1] = 0.99332 * b[1] + w * 0.0750759;
+        b[2] = 0.96900 * b[2] + w * 0.153852;
+        b[3] = 0.86650 * b[3] + w * 0.3104856;
+        b[4] = 0.55000 * b[4] + w * 0.5329522;
+        b[5] = -0.7616 * b[5] - w * 0.0168980;
+        let out = b[..6].iter().sum::<f32>() + b[6] + w * 0.5362;
+        b[6] = w * 0.115926;
+        out
+    }).collect()
+}
+
+/// Exponentially decaying 2 ms noise bursts at random times
+fn clicks(len: usize, sample_rate: u32, rng: &mut impl Rng) -> Vec<f32> {
+    let mut out = vec![0.0; len];
+    let decay = (2e-3 * sample_rate as f32).max(1.0);
+    let probability = (CLICK_RATE / sample_rate as f32) as f64;
+    for start in 0..len {
+        if !rng.gen_bool(probability) { continue; }
+        let amplitude = rng.gen_range(0.3..1.0) * if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+        for (n, s) in out[start..].iter_mut().take((5.0 * decay) as usize).enumerate() {
+            *s += amplitude * (-(n as f32) / decay).exp() * (PI * 0.5 + gaussian(rng) * 0.3).sin();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    /// Energy of the first difference over the signal energy: higher means brighter
+    fn brightness(x: &[f32]) -> f32 {
+        let diff: f32 = x.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum();
+        diff / x.iter().map(|s| s * s).sum::<f32>()
+    }
+
+    #[test]
+    fn test_colored_noise_slopes() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let white = generate(NoiseKind::White, 32000, 16000, &mut rng);
+        let pink = generate(NoiseKind::Pink, 32000, 16000, &mut rng);
+        let brown = generate(NoiseKind::Brown, 32000, 16000, &mut rng);
+        assert!(brightness(&white) > 1.5 && brightness(&white) < 2.5);
+        assert!(brightness(&pink) < 0.5 * brightness(&white));
+        assert!(brightness(&brown) < 0.5 * brightness(&pink));
+    }
+
+    #[test]
+    fn test_every_kind_is_finite_and_nonzero() {
+        for kind in ALL_NOISES {
+            let noise = generate(kind, 16000, 16000, &mut StdRng::seed_from_u64(2));
+            assert!(noise.iter().all(|s| s.is_finite()), "{}", kind);
+            assert!(noise.iter().any(|&s| s != 0.0), "{}", kind);
+            assert_eq!(kind.to_string().parse::<NoiseKind>(), Ok(kind));
+        }
+    }
+}
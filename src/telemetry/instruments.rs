@@ -0,0 +1,120 @@
+//! Lock-free counters, gauges and fixed-bucket histograms
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+/// Monotonic `f64` counter
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) { self.add(1.0); }
+
+    pub fn add(&self, value: f64) {
+        let _ = self.0.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| Some((f64::from_bits(bits) + value).to_bits()));
+    }
+
+    pub fn get(&self) -> f64 { f64::from_bits(self.0.load(Ordering::Relaxed)) }
+}
+
+/// Integer value that goes up and down
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    pub fn add(&self, delta: i64) { self.0.fetch_add(delta, Ordering::Relaxed); }
+
+    pub fn get(&self) -> i64 { self.0.load(Ordering::Relaxed) }
+}
+
+/// Cumulative histogram over fixed upper bounds (Prometheus `le` buckets)
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// Per-bound counts (not cumulative); the last entry is `+Inf`
+    buckets: Vec<AtomicU64>,
+    sum: Counter,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self { bounds, buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(), sum: Counter::default() }
+    }
+
+    pub fn observe(&self, value: f64) {
+        let index = self.bounds.iter().position(|&b| value <= b).unwrap_or(self.bounds.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.sum.add(value);
+    }
+
+    pub fn count(&self) -> u64 { self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).sum() }
+
+    pub fn sum(&self) -> f64 { self.sum.get() }
+
+    /// Quantile `q` in [0, 1], interpolated linearly within its bucket; 0 when empty
+    pub fn quantile(&self, q: f64) -> f64 {
+        let counts: Vec<u64> = self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).collect();
+        let total: u64 = counts.iter().sum();
+        if total == 0 { return 0.0; }
+        let rank = q.clamp(0.0, 1.0) * total as f64;
+        let mut seen = 0u64;
+        for (i, &count) in counts.iter().enumerate() {
+            if count > 0 && (seen + count) as f64 >= rank {
+                let lower = if i == 0 { 0.0 } else { self.bounds[i - 1] };
+                // Values above the last bound are reported at that bound
+                let Some(&upper) = self.bounds.get(i) else { return lower };
+                return lower + (upper - lower) * ((rank - seen as f64) / count as f64);
+            }
+            seen += count;
+        }
+        self.bounds.last().copied().unwrap_or(0.0)
+    }
+
+    /// Append the `_bucket`, `_sum` and `_count` series of `name` with extra `labels`
+    pub fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0u64;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let le = self.bounds.get(i).map_or("+Inf".to_string(), |b| b.to_string());
+            writeln!(out, "{}_bucket{{{}{}le=\"{}\"}} {}", name, labels, sep, le, cumulative).ok();
+        }
+        let braces = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+        writeln!(out, "{}_sum{} {}", name, braces, self.sum()).ok();
+        writeln!(out, "{}_count{} {}", name, braces, cumulative).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_quantiles() {
+        static BOUNDS: [f64; 3] = [1.0, 2.0, 4.0];
+        let h = Histogram::new(&BOUNDS);
+        assert_eq!(h.quantile(0.5), 0.0);
+        for v in [0.5, 1.5, 1.5, 3.0, 10.0] {
+            h.observe(v);
+        }
+        assert_eq!((h.count(), h.sum()), (5, 16.5));
+        assert_eq!(h.quantile(0.5), 1.75);
+        assert_eq!(h.quantile(1.0), 4.0);
+
+        let mut text = String::new();
+        h.render(&mut text, "x", "");
+        assert!(text.contains("x_bucket{le=\"2\"} 3\n") && text.contains("x_bucket{le=\"+Inf\"} 5\n") && text.contains("x_count 5\n"));
+    }
+
+    #[test]
+    fn test_counter_and_gauge() {
+        let c = Counter::default();
+        c.inc();
+        c.add(0.5);
+        assert_eq!(c.get(), 1.5);
+        let g = Gauge::default();
+        g.add(3);
+        g.add(-1);
+        assert_eq!(g.get(), 2);
+    }
+}
@@ -0,0 +1,244 @@
+//! Operational Telemetry
+//!
+//! Process-wide counters and histograms updated by the enhancer, readable as a
+//! [`MetricsSnapshot`] or in Prometheus text format (optionally served on a local
+//! endpoint), plus `tracing` spans around the pipeline stages that can be dumped as
+//! a Chrome trace.
+
+pub mod instruments;
+pub mod server;
+pub mod trace;
+
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use serde::Serialize;
+pub use instruments::{Counter, Gauge, Histogram};
+pub use server::serve;
+pub use trace::{init_chrome_trace, ChromeTraceGuard};
+
+/// Inference latency buckets (seconds)
+const LATENCY_BOUNDS: [f64; 14] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+/// Real-time factor buckets
+const RTF_BOUNDS: [f64; 10] = [0.01, 0.02, 0.05, 0.1, 0.2, 0.3, 0.5, 1.0, 2.0, 5.0];
+
+/// Process-wide metrics; see [`metrics`]
+#[derive(Debug)]
+pub struct Metrics {
+    started: Instant,
+    pub files_ok: Counter,
+    pub files_failed: Counter,
+    pub files_cancelled: Counter,
+    /// Decoded input audio (seconds and bytes of samples)
+    pub audio_seconds: Counter,
+    pub audio_bytes: Counter,
+    /// Latency of one model run (each retry is a separate run)
+    pub inference_seconds: Histogram,
+    /// Extra attempts after a failed model run
+    pub inference_retries: Counter,
+    /// Model runs that failed after all retries
+    pub inference_failures: Counter,
+    /// Segments that bypassed the model because VAD classified them as non-speech
+    pub fallback_vad_skips: Counter,
+    /// Segments whose model output had non-finite samples replaced
+    pub fallback_non_finite: Counter,
+    /// Segments queued for parallel workers and not yet picked up
+    pub queue_depth: Gauge,
+    /// Per-file processing time over audio duration
+    pub rtf: Histogram,
+    /// ONNX sessions kept by the session pool, and those checked out
+    pub sessions_idle: Gauge,
+    pub sessions_in_use: Gauge,
+    /// Sessions loaded from a model file; reused sessions are not counted
+    pub sessions_created: Counter,
+    /// Seconds each parallel worker spent running the model
+    worker_busy: Mutex<Vec<f64>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            files_ok: Counter::default(),
+            files_failed: Counter::default(),
+            files_cancelled: Counter::default(),
+            audio_seconds: Counter::default(),
+            audio_bytes: Counter::default(),
+            inference_seconds: Histogram::new(&LATENCY_BOUNDS),
+            inference_retries: Counter::default(),
+            inference_failures: Counter::default(),
+            fallback_vad_skips: Counter::default(),
+            fallback_non_finite: Counter::default(),
+            queue_depth: Gauge::default(),
+            rtf: Histogram::new(&RTF_BOUNDS),
+            sessions_idle: Gauge::default(),
+            sessions_in_use: Gauge::default(),
+            sessions_created: Counter::default(),
+            worker_busy: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+/// The metrics of this process
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::default)
+}
+
+/// Latency or RTF distribution summary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Distribution {
+    pub count: u64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+}
+
+impl From<&Histogram> for Distribution {
+    fn from(h: &Histogram) -> Self {
+        let count = h.count();
+        Self {
+            count,
+            mean: if count > 0 { h.sum() / count as f64 } else { 0.0 },
+            p50: h.quantile(0.5),
+            p90: h.quantile(0.9),
+            p99: h.quantile(0.99),
+        }
+    }
+}
+
+/// Point-in-time copy of [`Metrics`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MetricsSnapshot {
+    pub uptime_secs: f64,
+    pub files_ok: u64,
+    pub files_failed: u64,
+    pub files_cancelled: u64,
+    pub audio_seconds: f64,
+    pub audio_bytes: u64,
+    /// Seconds per model run
+    pub inference: Distribution,
+    pub inference_retries: u64,
+    pub inference_failures: u64,
+    pub fallback_vad_skips: u64,
+    pub fallback_non_finite: u64,
+    pub queue_depth: i64,
+    pub rtf: Distribution,
+    pub sessions_idle: i64,
+    pub sessions_in_use: i64,
+    pub sessions_created: u64,
+    /// Busy fraction of each parallel worker since start
+    pub worker_utilization: Vec<f64>,
+}
+
+impl Metrics {
+    /// Count a processed file and its real-time factor
+    pub fn record_success(&self, audio_secs: f64, processing_secs: f64) {
+        self.files_ok.inc();
+        if audio_secs > 0.0 { self.rtf.observe(processing_secs / audio_secs); }
+    }
+
+    /// Count a file that failed or was cancelled
+    pub fn record_failure(&self, error: &crate::ZipEnhancerError) {
+        if error.code() == crate::error::ErrorCode::Cancelled { self.files_cancelled.inc() } else { self.files_failed.inc() }
+    }
+
+    pub fn record_audio(&self, seconds: f64, bytes: u64) {
+        self.audio_seconds.add(seconds);
+        self.audio_bytes.add(bytes as f64);
+    }
+
+    pub fn record_worker_busy(&self, worker: usize, seconds: f64) {
+        let mut busy = self.worker_busy.lock().expect("metrics lock");
+        if busy.len() <= worker { busy.resize(worker + 1, 0.0); }
+        busy[worker] += seconds;
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let uptime = self.started.elapsed().as_secs_f64();
+        MetricsSnapshot {
+            uptime_secs: uptime,
+            files_ok: self.files_ok.get() as u64,
+            files_failed: self.files_failed.get() as u64,
+            files_cancelled: self.files_cancelled.get() as u64,
+            audio_seconds: self.audio_seconds.get(),
+            audio_bytes: self.audio_bytes.get() as u64,
+            inference: Distribution::from(&self.inference_seconds),
+            inference_retries: self.inference_retries.get() as u64,
+            inference_failures: self.inference_failures.get() as u64,
+            fallback_vad_skips: self.fallback_vad_skips.get() as u64,
+            fallback_non_finite: self.fallback_non_finite.get() as u64,
+            queue_depth: self.queue_depth.get(),
+            rtf: Distribution::from(&self.rtf),
+            sessions_idle: self.sessions_idle.get(),
+            sessions_in_use: self.sessions_in_use.get(),
+            sessions_created: self.sessions_created.get() as u64,
+            worker_utilization: self.worker_busy.lock().expect("metrics lock").iter()
+                .map(|busy| if uptime > 0.0 { busy / uptime } else { 0.0 })
+                .collect(),
+        }
+    }
+
+    /// Prometheus text exposition format (version 0.0.4)
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut series = |name: &str, kind: &str, help: &str, samples: &[(&str, f64)]| {
+            writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind).ok();
+            for (labels, value) in samples {
+                let labels = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+                writeln!(out, "{}{} {}", name, labels, value).ok();
+            }
+        };
+        series("zipenhancer_files_total", "counter", "Processed files by outcome",
+            &[("status=\"ok\"", self.files_ok.get()), ("status=\"failed\"", self.files_failed.get()), ("status=\"cancelled\"", self.files_cancelled.get())]);
+        series("zipenhancer_audio_seconds_total", "counter", "Seconds of input audio decoded", &[("", self.audio_seconds.get())]);
+        series("zipenhancer_audio_bytes_total", "counter", "Bytes of input samples decoded", &[("", self.audio_bytes.get())]);
+        series("zipenhancer_inference_retries_total", "counter", "Model run retries", &[("", self.inference_retries.get())]);
+        series("zipenhancer_inference_failures_total", "counter", "Model runs failed after all retries", &[("", self.inference_failures.get())]);
+        series("zipenhancer_fallback_segments_total", "counter", "Segments not taken from the model output",
+            &[("reason=\"vad_skip\"", self.fallback_vad_skips.get()), ("reason=\"non_finite_output\"", self.fallback_non_finite.get())]);
+        series("zipenhancer_queue_depth", "gauge", "Segments waiting for a parallel worker", &[("", self.queue_depth.get() as f64)]);
+        series("zipenhancer_sessions", "gauge", "ONNX sessions in the session pool by state",
+            &[("state=\"idle\"", self.sessions_idle.get() as f64), ("state=\"in_use\"", self.sessions_in_use.get() as f64)]);
+        series("zipenhancer_sessions_created_total", "counter", "ONNX sessions loaded from a model file", &[("", self.sessions_created.get())]);
+        let busy = self.worker_busy.lock().expect("metrics lock").clone();
+        let workers: Vec<(String, f64)> = busy.iter().enumerate().map(|(i, &b)| (format!("worker=\"{}\"", i), b)).collect();
+        let workers: Vec<(&str, f64)> = workers.iter().map(|(l, b)| (l.as_str(), *b)).collect();
+        series("zipenhancer_worker_busy_seconds_total", "counter", "Time each parallel worker spent running the model", &workers);
+
+        writeln!(out, "# HELP zipenhancer_inference_seconds Latency of one model run\n# TYPE zipenhancer_inference_seconds histogram").ok();
+        self.inference_seconds.render(&mut out, "zipenhancer_inference_seconds", "");
+        writeln!(out, "# HELP zipenhancer_rtf Per-file real-time factor\n# TYPE zipenhancer_rtf histogram").ok();
+        self.rtf.render(&mut out, "zipenhancer_rtf", "");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_and_exposition() {
+        let m = Metrics::default();
+        m.record_audio(2.0, 64000);
+        m.record_success(2.0, 0.5);
+        m.record_failure(&crate::ZipEnhancerError::Cancelled);
+        m.inference_seconds.observe(0.02);
+        m.record_worker_busy(1, 0.25);
+        m.sessions_in_use.add(2);
+
+        let snap = m.snapshot();
+        assert_eq!((snap.files_ok, snap.files_cancelled, snap.audio_bytes), (1, 1, 64000));
+        assert_eq!(snap.rtf.count, 1);
+        assert_eq!(snap.worker_utilization.len(), 2);
+        assert!(snap.inference.p50 > 0.01 && snap.inference.p50 <= 0.025);
+
+        let text = m.render_prometheus();
+        assert!(text.contains("zipenhancer_files_total{status=\"ok\"} 1\n"));
+        assert!(text.contains("zipenhancer_worker_busy_seconds_total{worker=\"1\"} 0.25\n"));
+        assert!(text.contains("# TYPE zipenhancer_inference_seconds histogram\n"));
+        assert!(text.contains("zipenhancer_sessions{state=\"in_use\"} 2\n"));
+    }
+}
@@ -0,0 +1,79 @@
+//! Minimal HTTP endpoint serving `GET /metrics` in Prometheus text format
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+use crate::error::{ZipEnhancerError, Result};
+use super::metrics;
+
+/// Requests are answered one at a time, so a client that stalls is dropped after this
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Serve the process metrics on `addr` from a background thread; returns the bound address
+pub fn serve(addr: &str) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)
+        .map_err(|e| ZipEnhancerError::config(format!("Cannot listen on {}: {}", addr, e)))?;
+    let local = listener.local_addr()?;
+    std::thread::Builder::new()
+        .name("metrics-http".into())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Err(e) = respond(stream) {
+                    log::debug!("Metrics request failed: {}", e);
+                }
+            }
+        })?;
+    log::info!("Serving metrics on http://{}/metrics", local);
+    Ok(local)
+}
+
+fn respond(stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let (status, body) = if request_line.starts_with("GET ") && (path == "/metrics" || path == "/") {
+        ("200 OK", metrics().render_prometheus())
+    } else {
+        ("404 Not Found", "not found\n".to_string())
+    };
+    write!(&stream, "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, body.len(), body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_serves_metrics() {
+        let addr = serve("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: x\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("zipenhancer_files_total"));
+    }
+
+    #[test]
+    fn test_silent_client_does_not_block_others() {
+        let addr = serve("127.0.0.1:0").unwrap();
+        let _silent = TcpStream::connect(addr).unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(IO_TIMEOUT * 5)).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+    }
+}
@@ -0,0 +1,21 @@
+//! Chrome trace output of the pipeline's `tracing` spans
+//!
+//! Spans: `decode`, `resample`, `segment`, `infer` (one per model run), `reconstruct`
+//! and `encode`. Open the JSON file in `chrome://tracing` or Perfetto.
+
+use std::path::Path;
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+use tracing_subscriber::prelude::*;
+use crate::error::{ZipEnhancerError, Result};
+
+/// Writes the trace file when dropped
+pub struct ChromeTraceGuard(#[allow(dead_code)] FlushGuard);
+
+/// Record all spans of this process to `path` until the guard is dropped
+pub fn init_chrome_trace(path: &Path) -> Result<ChromeTraceGuard> {
+    let (layer, guard) = ChromeLayerBuilder::new().file(path).include_args(true).build();
+    // Not `try_init`: that would also claim the `log` facade, which env_logger owns
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer))
+        .map_err(|e| ZipEnhancerError::config(format!("Cannot install the trace subscriber: {}", e)))?;
+    Ok(ChromeTraceGuard(guard))
+}
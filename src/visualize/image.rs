@@ -0,0 +1,122 @@
+//! RGB raster with PNG encoding and the dB color scale
+
+use std::path::Path;
+use crate::error::{ZipEnhancerError, Result};
+
+pub type Rgb = [u8; 3];
+
+/// Anchors of the dB color scale (dark blue -> purple -> orange -> pale yellow)
+const COLOR_SCALE: [(f32, Rgb); 5] = [
+    (0.0, [0, 0, 4]),
+    (0.25, [60, 15, 110]),
+    (0.5, [180, 55, 85]),
+    (0.75, [250, 140, 10]),
+    (1.0, [252, 255, 164]),
+];
+
+/// Map a value in [0, 1] onto the color scale
+pub fn color_scale(value: f32) -> Rgb {
+    let v = if value.is_finite() { value.clamp(0.0, 1.0) } else { 0.0 };
+    for pair in COLOR_SCALE.windows(2) {
+        let ((p0, c0), (p1, c1)) = (pair[0], pair[1]);
+        if v <= p1 {
+            let t = (v - p0) / (p1 - p0);
+            return std::array::from_fn(|i| (c0[i] as f32 + (c1[i] as f32 - c0[i] as f32) * t).round() as u8);
+        }
+    }
+    COLOR_SCALE[COLOR_SCALE.len() - 1].1
+}
+
+/// 8-bit RGB image, row-major from the top-left corner
+#[derive(Debug, Clone, PartialEq)]
+pub struct RgbImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbImage {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self { width, height, pixels: background.repeat(width * height) }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        if x < self.width && y < self.height {
+            let i = (y * self.width + x) * 3;
+            self.pixels[i..i + 3].copy_from_slice(&color);
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        let i = (y * self.width + x) * 3;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+    }
+
+    /// Vertical line from `y0` to `y1` inclusive
+    pub fn vline(&mut self, x: usize, y0: usize, y1: usize, color: Rgb) {
+        for y in y0.min(y1)..=y0.max(y1) {
+            self.set(x, y, color);
+        }
+    }
+
+    pub fn hline(&mut self, y: usize, x0: usize, x1: usize, color: Rgb) {
+        for x in x0.min(x1)..=x0.max(x1) {
+            self.set(x, y, color);
+        }
+    }
+
+    pub fn encode_png(&self) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()
+            .map_err(png_error)?;
+        writer.write_image_data(&self.pixels)
+            .map_err(png_error)?;
+        writer.finish()
+            .map_err(png_error)?;
+        Ok(out)
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.encode_png()?)?;
+        Ok(())
+    }
+}
+
+/// PNG encoding failure, keeping the png error as the source
+fn png_error(error: png::EncodingError) -> ZipEnhancerError {
+    match error {
+        png::EncodingError::IoError(e) => e.into(),
+        e => std::io::Error::other(e).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_scale_endpoints() {
+        assert_eq!(color_scale(0.0), [0, 0, 4]);
+        assert_eq!(color_scale(1.0), [252, 255, 164]);
+        assert_eq!(color_scale(f32::NAN), [0, 0, 4]);
+        assert_eq!(color_scale(0.5), [180, 55, 85]);
+    }
+
+    #[test]
+    fn test_png_roundtrip() {
+        let mut image = RgbImage::new(4, 3, [10, 20, 30]);
+        image.set(1, 2, [255, 0, 0]);
+        let bytes = image.encode_png().unwrap();
+        let mut reader = png::Decoder::new(std::io::Cursor::new(bytes)).read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        assert_eq!((info.width, info.height), (4, 3));
+        assert_eq!(&buf[..info.buffer_size()], image.pixels.as_slice());
+    }
+}
@@ -0,0 +1,109 @@
+//! Visualization Module
+//!
+//! Deterministic, offline PNG rendering of spectrograms and waveform overviews
+//! for before/after review of input, enhanced output and residual.
+
+pub mod image;
+pub mod plot;
+
+use std::path::{Path, PathBuf};
+use ndarray::Array1;
+use crate::audio::{AudioConverter, WavAudio};
+use crate::error::{ZipEnhancerError, Result};
+use crate::processing::residual::compute_residual;
+
+pub use image::{color_scale, RgbImage};
+pub use plot::{render_spectrogram, render_waveform};
+
+/// Rendering parameters shared by all images of one visualization
+#[derive(Debug, Clone)]
+pub struct VisualizeConfig {
+    pub fft_size: usize,
+    pub hop_size: usize,
+    /// Plot width in pixels (the time axis)
+    pub width: usize,
+    /// Spectrogram height in pixels (the frequency axis)
+    pub height: usize,
+    pub waveform_height: usize,
+    /// Bottom and top of the dB color scale
+    pub min_db: f32,
+    pub max_db: f32,
+}
+
+impl Default for VisualizeConfig {
+    fn default() -> Self {
+        Self { fft_size: 1024, hop_size: 256, width: 1000, height: 256, waveform_height: 120, min_db: -100.0, max_db: 0.0 }
+    }
+}
+
+impl VisualizeConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.width == 0 || self.height < 2 || self.waveform_height < 2 {
+            return Err(ZipEnhancerError::config("Image width must be > 0 and heights at least 2 pixels"));
+        }
+        if self.min_db >= self.max_db {
+            return Err(ZipEnhancerError::config("min_db must be below max_db"));
+        }
+        Ok(())
+    }
+}
+
+fn load_mono(path: &Path, sample_rate: Option<u32>) -> Result<(Array1<f32>, u32)> {
+    let mut audio = WavAudio::from_file(path)?;
+    if let Some(rate) = sample_rate.filter(|&r| r != audio.sample_rate()) {
+        audio = AudioConverter::convert_sample_rate(&audio, rate)?;
+    }
+    Ok((audio.data().to_mono(), audio.sample_rate()))
+}
+
+/// Render spectrogram and waveform PNGs of input, output and residual into `out_dir`.
+///
+/// Everything is drawn at the input sample rate on one time axis. Without a
+/// residual file the residual is computed as input minus output. Returns the
+/// written paths.
+pub fn visualize_files(input: &Path, output: &Path, residual: Option<&Path>, out_dir: &Path, config: &VisualizeConfig) -> Result<Vec<PathBuf>> {
+    config.validate()?;
+    let (input_data, rate) = load_mono(input, None)?;
+    let (output_data, _) = load_mono(output, Some(rate))?;
+    let residual_data = match residual {
+        Some(path) => load_mono(path, Some(rate))?.0,
+        None => compute_residual(&input_data, &output_data),
+    };
+
+    let signals = [("input", &input_data), ("output", &output_data), ("residual", &residual_data)];
+    let longest = signals.iter().map(|(_, d)| d.len()).max().unwrap_or(0);
+    let duration = longest as f64 / rate as f64;
+    let stem = input.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "audio".to_string());
+
+    let mut written = Vec::new();
+    for (label, data) in signals {
+        let spectrogram = out_dir.join(format!("{}_{}_spectrogram.png", stem, label));
+        render_spectrogram(data, rate, duration, config)?.save_png(&spectrogram)?;
+        let waveform = out_dir.join(format!("{}_{}_waveform.png", stem, label));
+        render_waveform(data, rate, duration, config).save_png(&waveform)?;
+        written.extend([spectrogram, waveform]);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioFormat;
+
+    #[test]
+    fn test_visualize_files_writes_all_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let tone = Array1::from_iter((0..8000).map(|i| 0.3 * (i as f32 * 0.1).sin()));
+        let input = dir.path().join("clip.wav");
+        let output = dir.path().join("clip_out.wav");
+        WavAudio::new_mono(16000, tone.clone(), AudioFormat::Int16).save_to_file(&input).unwrap();
+        WavAudio::new_mono(16000, tone * 0.5, AudioFormat::Int16).save_to_file(&output).unwrap();
+
+        let config = VisualizeConfig { width: 64, height: 32, waveform_height: 16, ..Default::default() };
+        let written = visualize_files(&input, &output, None, &dir.path().join("png"), &config).unwrap();
+        assert_eq!(written.len(), 6);
+        assert!(written.iter().all(|p| p.exists()));
+        assert!(written[4].ends_with("clip_residual_spectrogram.png"));
+    }
+}
@@ -0,0 +1,134 @@
+//! Spectrogram and waveform rendering on a shared time axis
+
+use ndarray::Array1;
+use crate::audio::spectral::{Stft, StftConfig};
+use crate::error::Result;
+use super::VisualizeConfig;
+use super::image::{color_scale, Rgb, RgbImage};
+
+const BACKGROUND: Rgb = [16, 16, 24];
+const AXIS: Rgb = [150, 150, 150];
+const WAVE_PEAK: Rgb = [70, 130, 200];
+const WAVE_RMS: Rgb = [150, 200, 250];
+/// Height of the time axis strip below each plot (pixels)
+const AXIS_HEIGHT: usize = 6;
+/// Color bar width and the gap before it; waveforms reserve the same margin
+const COLORBAR_WIDTH: usize = 12;
+const COLORBAR_GAP: usize = 4;
+
+fn right_margin() -> usize {
+    COLORBAR_GAP + COLORBAR_WIDTH
+}
+
+/// Draw one-second ticks (longer every ten seconds) below a plot of `duration_secs`
+fn draw_time_axis(image: &mut RgbImage, plot_width: usize, top: usize, duration_secs: f64) {
+    image.hline(top, 0, plot_width.saturating_sub(1), AXIS);
+    if duration_secs <= 0.0 { return; }
+    let mut second = 0usize;
+    loop {
+        let x = (second as f64 / duration_secs * plot_width as f64) as usize;
+        if x >= plot_width { break; }
+        let length = if second.is_multiple_of(10) { AXIS_HEIGHT - 1 } else { AXIS_HEIGHT / 2 };
+        image.vline(x, top, top + length, AXIS);
+        second += 1;
+    }
+}
+
+/// Sample range covered by plot column `x`
+fn column_range(x: usize, width: usize, duration_secs: f64, sample_rate: u32) -> (usize, usize) {
+    let to_sample = |c: usize| (c as f64 / width as f64 * duration_secs * sample_rate as f64) as usize;
+    let (start, end) = (to_sample(x), to_sample(x + 1));
+    (start, end.max(start + 1))
+}
+
+/// Spectrogram with a dB color scale (bar on the right) and a time axis.
+///
+/// The plot spans `duration_secs`, so images of signals with different lengths
+/// share the same time axis; columns past the end of `data` stay empty.
+pub fn render_spectrogram(data: &Array1<f32>, sample_rate: u32, duration_secs: f64, config: &VisualizeConfig) -> Result<RgbImage> {
+    let stft = Stft::new(StftConfig::new(config.fft_size, config.hop_size))?;
+    // Full-scale sine -> 0 dB
+    let scale = 2.0 / stft.window().iter().sum::<f32>();
+    let db = stft.forward(data).mapv(|c| 20.0 * (c.norm() * scale).max(1e-10).log10());
+    let (frames, bins) = db.dim();
+
+    let (width, height) = (config.width, config.height);
+    let mut image = RgbImage::new(width + right_margin(), height + AXIS_HEIGHT, BACKGROUND);
+    let range = (config.max_db - config.min_db).max(1e-3);
+
+    for x in 0..width {
+        let (start, end) = column_range(x, width, duration_secs, sample_rate);
+        if start >= data.len() { break; }
+        // Centred frames: frame t is centred on sample t * hop
+        let first = (start / config.hop_size).min(frames.saturating_sub(1));
+        let last = end.div_ceil(config.hop_size).clamp(first + 1, frames.max(first + 1));
+        for y in 0..height {
+            let row = height - 1 - y;
+            let (k0, k1) = (row * bins / height, ((row + 1) * bins / height).max(row * bins / height + 1));
+            let value = db.slice(ndarray::s![first..last.min(frames), k0..k1.min(bins)])
+                .iter().copied().fold(f32::MIN, f32::max);
+            image.set(x, y, color_scale((value - config.min_db) / range));
+        }
+    }
+
+    let bar_x = width + COLORBAR_GAP;
+    for y in 0..height {
+        let color = color_scale(1.0 - y as f32 / (height.max(2) - 1) as f32);
+        image.hline(y, bar_x, bar_x + COLORBAR_WIDTH - 1, color);
+    }
+    draw_time_axis(&mut image, width, height, duration_secs);
+    Ok(image)
+}
+
+/// Waveform overview: per-column peak envelope with the RMS level drawn on top
+pub fn render_waveform(data: &Array1<f32>, sample_rate: u32, duration_secs: f64, config: &VisualizeConfig) -> RgbImage {
+    let (width, height) = (config.width, config.waveform_height);
+    let mut image = RgbImage::new(width + right_margin(), height + AXIS_HEIGHT, BACKGROUND);
+    let mid = (height - 1) as f32 / 2.0;
+    let to_y = |v: f32| (mid - v.clamp(-1.0, 1.0) * mid).round() as usize;
+    image.hline(to_y(0.0), 0, width.saturating_sub(1), [60, 60, 70]);
+
+    for x in 0..width {
+        let (start, end) = column_range(x, width, duration_secs, sample_rate);
+        if start >= data.len() { break; }
+        let column = data.slice(ndarray::s![start..end.min(data.len())]);
+        let (lo, hi) = column.iter().fold((0.0f32, 0.0f32), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        let rms = (column.iter().map(|v| v * v).sum::<f32>() / column.len() as f32).sqrt();
+        image.vline(x, to_y(hi), to_y(lo), WAVE_PEAK);
+        image.vline(x, to_y(rms), to_y(-rms), WAVE_RMS);
+    }
+    draw_time_axis(&mut image, width, height, duration_secs);
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f32, secs: f32) -> Array1<f32> {
+        Array1::from_iter((0..(secs * 16000.0) as usize).map(|i| 0.5 * (std::f32::consts::TAU * freq * i as f32 / 16000.0).sin()))
+    }
+
+    #[test]
+    fn test_spectrogram_peak_row() {
+        let config = VisualizeConfig { width: 100, height: 64, ..Default::default() };
+        let image = render_spectrogram(&sine(2000.0, 1.0), 16000, 1.0, &config).unwrap();
+        assert_eq!((image.width, image.height), (100 + right_margin(), 64 + AXIS_HEIGHT));
+        // 2 kHz of 8 kHz Nyquist: a quarter of the way up from the bottom
+        let brightness = |y: usize| image.get(50, y).iter().map(|&c| c as u32).sum::<u32>();
+        let brightest = (0..64).max_by_key(|&y| brightness(y)).unwrap();
+        assert!((brightest as i32 - 48).abs() <= 1, "{}", brightest);
+    }
+
+    #[test]
+    fn test_shared_time_axis_and_determinism() {
+        let config = VisualizeConfig { width: 200, ..Default::default() };
+        let short = render_waveform(&sine(300.0, 1.0), 16000, 2.0, &config);
+        let long = render_waveform(&sine(300.0, 2.0), 16000, 2.0, &config);
+        assert_eq!(short.width, long.width);
+        // The one-second signal fills only the left half of the two-second axis
+        assert_ne!(short.get(50, config.waveform_height / 4), BACKGROUND);
+        assert_eq!(short.get(150, config.waveform_height / 4), BACKGROUND);
+        assert_eq!(render_waveform(&sine(300.0, 1.0), 16000, 2.0, &config), short);
+    }
+}